@@ -0,0 +1,188 @@
+use std::{future::Future, io::Read, pin::Pin, sync::Arc};
+
+use crate::{
+	error::{Error, Result},
+	ironworks::Resource,
+};
+
+/// An owned, dynamically typed future, as returned by asynchronous resources.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Resource layer that can asynchronously provide data to an ironworks instance.
+pub trait AsyncResource: Send + Sync + 'static {
+	/// Get the version string for the file at `path`. A return value of
+	/// `Err(Error::NotFound(ErrorValue::Path(_)))` will result in lookups
+	/// continuing to the next resource.
+	fn version<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<String>>;
+
+	/// Get the raw bytes of the file at `path`. A return value of
+	/// `Err(Error::NotFound(ErrorValue::Path(_)))` will result in lookups
+	/// continuing to the next resource.
+	fn file<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<u8>>>;
+}
+
+/// Adapter allowing a blocking [`Resource`] to be used as an [`AsyncResource`].
+///
+/// Calls to the wrapped resource are executed on tokio's blocking thread pool,
+/// and as such must be made from within a tokio runtime.
+#[derive(Debug)]
+pub struct BlockingResource<R: ?Sized> {
+	resource: Arc<R>,
+}
+
+impl<R: Resource> BlockingResource<R> {
+	/// Wrap a blocking resource for use in asynchronous lookups.
+	pub fn new(resource: R) -> Self {
+		Self::from_arc(resource.into())
+	}
+}
+
+impl<R: Resource + ?Sized> BlockingResource<R> {
+	pub(crate) fn from_arc(resource: Arc<R>) -> Self {
+		Self { resource }
+	}
+}
+
+impl<R: Resource + ?Sized> AsyncResource for BlockingResource<R> {
+	fn version<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<String>> {
+		let resource = self.resource.clone();
+		let path = path.to_string();
+		Box::pin(spawn_blocking(move || resource.version(&path)))
+	}
+
+	fn file<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+		let resource = self.resource.clone();
+		let path = path.to_string();
+		Box::pin(spawn_blocking(move || {
+			let mut buffer = Vec::new();
+			resource.file(&path)?.read_to_end(&mut buffer)?;
+			Ok(buffer)
+		}))
+	}
+}
+
+async fn spawn_blocking<T, F>(function: F) -> Result<T>
+where
+	T: Send + 'static,
+	F: FnOnce() -> Result<T> + Send + 'static,
+{
+	tokio::task::spawn_blocking(function)
+		.await
+		.map_err(|error| Error::Resource(error.into()))?
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::Cursor;
+
+	use crate::{
+		cache::FileCache,
+		error::{Error, ErrorValue, Result},
+		ironworks::{FileStream, Ironworks, Resource},
+	};
+
+	use super::{AsyncResource, BlockingResource, BoxFuture};
+
+	struct TestResource;
+
+	impl Resource for TestResource {
+		fn version(&self, _path: &str) -> Result<String> {
+			Ok("1".into())
+		}
+
+		fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+			match path {
+				"test/file" => Ok(Box::new(Cursor::new(b"hello".to_vec()))),
+				_ => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+			}
+		}
+	}
+
+	fn block_on<F: std::future::Future>(future: F) -> F::Output {
+		tokio::runtime::Builder::new_current_thread()
+			.build()
+			.unwrap()
+			.block_on(future)
+	}
+
+	#[test]
+	fn future_send() {
+		fn assert_send<T: Send>(_: &T) {}
+		let ironworks = Ironworks::new();
+		assert_send(&ironworks.file_async::<Vec<u8>>("test/file"));
+	}
+
+	#[test]
+	fn blocking_adapter() {
+		let ironworks = Ironworks::new().with_async_resource(BlockingResource::new(TestResource));
+		let file = block_on(ironworks.file_async::<Vec<u8>>("test/file")).unwrap();
+		assert_eq!(file, b"hello");
+	}
+
+	#[test]
+	fn blocking_adapter_not_found() {
+		let ironworks = Ironworks::new().with_async_resource(BlockingResource::new(TestResource));
+		let file = block_on(ironworks.file_async::<Vec<u8>>("test/missing"));
+		assert!(matches!(file, Err(Error::NotFound(ErrorValue::Path(_)))));
+	}
+
+	struct AsyncTestResource;
+
+	impl AsyncResource for AsyncTestResource {
+		fn version<'a>(&'a self, _path: &'a str) -> BoxFuture<'a, Result<String>> {
+			Box::pin(async { Ok("2".into()) })
+		}
+
+		fn file<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+			Box::pin(async move {
+				match path {
+					"test/file" => Ok(b"async".to_vec()),
+					_ => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+				}
+			})
+		}
+	}
+
+	#[test]
+	fn searches_blocking_resources() {
+		let ironworks = Ironworks::new().with_resource(TestResource);
+		let file = block_on(ironworks.file_async::<Vec<u8>>("test/file")).unwrap();
+		assert_eq!(file, b"hello");
+	}
+
+	#[test]
+	fn layers_in_order_added() {
+		let ironworks = Ironworks::new()
+			.with_resource(TestResource)
+			.with_async_resource(AsyncTestResource);
+		assert_eq!(block_on(ironworks.version_async("test/file")).unwrap(), "2");
+
+		let ironworks = Ironworks::new()
+			.with_async_resource(AsyncTestResource)
+			.with_resource(TestResource);
+		assert_eq!(block_on(ironworks.version_async("test/file")).unwrap(), "1");
+	}
+
+	#[test]
+	fn uses_file_cache() {
+		let ironworks = Ironworks::new()
+			.with_resource(TestResource)
+			.with_async_resource(AsyncTestResource)
+			.with_file_cache(FileCache::new(100));
+
+		for _ in 0..2 {
+			let file = block_on(ironworks.file_async::<Vec<u8>>("test/file")).unwrap();
+			assert_eq!(file, b"async");
+		}
+		let stats = ironworks.cache_stats().unwrap();
+		assert_eq!((stats.hits, stats.misses), (1, 1));
+
+		// Blocking resources share cached data with synchronous lookups.
+		let ironworks = Ironworks::new()
+			.with_resource(TestResource)
+			.with_file_cache(FileCache::new(100));
+		ironworks.file::<Vec<u8>>("test/file").unwrap();
+		block_on(ironworks.file_async::<Vec<u8>>("test/file")).unwrap();
+		assert_eq!(ironworks.cache_stats().unwrap().hits, 1);
+	}
+}
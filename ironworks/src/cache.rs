@@ -0,0 +1,226 @@
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::{Arc, Mutex},
+};
+
+/// Identifier of the resource that provided a cached file. Blocking and
+/// asynchronous resources are indexed separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum ResourceKey {
+	Blocking(usize),
+	#[cfg(feature = "async")]
+	Async(usize),
+}
+
+type CacheKey = (ResourceKey, String);
+
+/// Byte-budgeted, least-recently-used cache of raw file data.
+///
+/// When registered with an ironworks instance, file data read from resources
+/// will be retained in memory until the configured byte budget is exceeded, at
+/// which point the least recently used files are evicted.
+#[derive(Debug)]
+pub struct FileCache {
+	budget: usize,
+	state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+	entries: HashMap<CacheKey, CacheEntry>,
+	recency: BTreeMap<u64, CacheKey>,
+	tick: u64,
+	bytes: usize,
+
+	hits: u64,
+	misses: u64,
+	evictions: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+	data: Arc<[u8]>,
+	tick: u64,
+}
+
+/// Point-in-time statistics for a file cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+	/// Number of file requests that were served from the cache.
+	pub hits: u64,
+	/// Number of file requests that needed to be read from a resource.
+	pub misses: u64,
+	/// Number of files that have been evicted to remain within the byte budget.
+	pub evictions: u64,
+	/// Number of files currently stored in the cache.
+	pub entries: usize,
+	/// Total size, in bytes, of the files currently stored in the cache.
+	pub bytes: usize,
+}
+
+impl FileCache {
+	/// Build a new file cache that will retain at most `budget` bytes of file data.
+	pub fn new(budget: usize) -> Self {
+		Self {
+			budget,
+			state: Default::default(),
+		}
+	}
+
+	/// Maximum number of bytes of file data this cache will retain.
+	pub fn budget(&self) -> usize {
+		self.budget
+	}
+
+	/// Get a snapshot of the current statistics for this cache.
+	pub fn stats(&self) -> CacheStats {
+		let state = self.state.lock().unwrap();
+		CacheStats {
+			hits: state.hits,
+			misses: state.misses,
+			evictions: state.evictions,
+			entries: state.entries.len(),
+			bytes: state.bytes,
+		}
+	}
+
+	/// Remove all files from the cache. Statistics are retained.
+	pub fn clear(&self) {
+		let mut state = self.state.lock().unwrap();
+		state.entries.clear();
+		state.recency.clear();
+		state.bytes = 0;
+	}
+
+	pub(crate) fn get(&self, resource: ResourceKey, path: &str) -> Option<Arc<[u8]>> {
+		let mut state = self.state.lock().unwrap();
+		let state = &mut *state;
+
+		// TODO: This allocates a key on every lookup - look into borrowed lookups if it shows up in profiles.
+		let key = (resource, path.to_string());
+		let entry = state.entries.get_mut(&key)?;
+
+		// Bump the entry to the most recently used position.
+		state.tick += 1;
+		state.recency.remove(&entry.tick);
+		entry.tick = state.tick;
+		state.recency.insert(entry.tick, key);
+
+		state.hits += 1;
+		Some(entry.data.clone())
+	}
+
+	pub(crate) fn insert(&self, resource: ResourceKey, path: &str, data: Arc<[u8]>) {
+		let mut state = self.state.lock().unwrap();
+		state.misses += 1;
+
+		// Files larger than the entire budget would evict everything else and
+		// then themselves - skip them entirely.
+		if data.len() > self.budget {
+			return;
+		}
+
+		// Another thread may have raced us to reading this file - if so, replace
+		// the existing entry so the byte count stays accurate.
+		let key = (resource, path.to_string());
+		if let Some(previous) = state.entries.remove(&key) {
+			state.recency.remove(&previous.tick);
+			state.bytes -= previous.data.len();
+		}
+
+		// Evict least recently used entries until the new data fits.
+		while state.bytes + data.len() > self.budget {
+			let Some((_, evicted_key)) = state.recency.pop_first() else {
+				break;
+			};
+			if let Some(evicted) = state.entries.remove(&evicted_key) {
+				state.bytes -= evicted.data.len();
+				state.evictions += 1;
+			}
+		}
+
+		state.tick += 1;
+		let tick = state.tick;
+		state.bytes += data.len();
+		state.recency.insert(tick, key.clone());
+		state.entries.insert(key, CacheEntry { data, tick });
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use super::{CacheStats, FileCache, ResourceKey::Blocking};
+
+	fn data(size: usize) -> Arc<[u8]> {
+		vec![0u8; size].into()
+	}
+
+	#[test]
+	fn hit_after_insert() {
+		let cache = FileCache::new(100);
+		assert!(cache.get(Blocking(0), "a").is_none());
+		cache.insert(Blocking(0), "a", data(10));
+		assert_eq!(cache.get(Blocking(0), "a").unwrap().len(), 10);
+		assert_eq!(
+			cache.stats(),
+			CacheStats {
+				hits: 1,
+				misses: 1,
+				evictions: 0,
+				entries: 1,
+				bytes: 10
+			}
+		);
+	}
+
+	#[test]
+	fn keyed_by_resource() {
+		let cache = FileCache::new(100);
+		cache.insert(Blocking(0), "a", data(10));
+		assert!(cache.get(Blocking(1), "a").is_none());
+		#[cfg(feature = "async")]
+		assert!(cache.get(super::ResourceKey::Async(0), "a").is_none());
+	}
+
+	#[test]
+	fn evicts_least_recently_used() {
+		let cache = FileCache::new(30);
+		cache.insert(Blocking(0), "a", data(10));
+		cache.insert(Blocking(0), "b", data(10));
+		cache.insert(Blocking(0), "c", data(10));
+
+		// Touch a so that b becomes the least recently used.
+		cache.get(Blocking(0), "a").unwrap();
+		cache.insert(Blocking(0), "d", data(10));
+
+		assert!(cache.get(Blocking(0), "a").is_some());
+		assert!(cache.get(Blocking(0), "b").is_none());
+		assert!(cache.get(Blocking(0), "c").is_some());
+		assert!(cache.get(Blocking(0), "d").is_some());
+
+		let stats = cache.stats();
+		assert_eq!(stats.evictions, 1);
+		assert_eq!(stats.bytes, 30);
+	}
+
+	#[test]
+	fn skips_oversized() {
+		let cache = FileCache::new(10);
+		cache.insert(Blocking(0), "a", data(5));
+		cache.insert(Blocking(0), "b", data(20));
+		assert!(cache.get(Blocking(0), "a").is_some());
+		assert!(cache.get(Blocking(0), "b").is_none());
+	}
+
+	#[test]
+	fn replaces_existing() {
+		let cache = FileCache::new(100);
+		cache.insert(Blocking(0), "a", data(10));
+		cache.insert(Blocking(0), "a", data(20));
+		let stats = cache.stats();
+		assert_eq!(stats.entries, 1);
+		assert_eq!(stats.bytes, 20);
+	}
+}
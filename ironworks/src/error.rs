@@ -63,6 +63,10 @@ pub enum ErrorValue {
 	#[cfg(feature = "sqpack")]
 	File(Vec<u8>),
 
+	/// A placeholder stored in a SqPack archive in place of a file's content.
+	#[cfg(feature = "sqpack")]
+	Placeholder(crate::sqpack::Placeholder),
+
 	/// A value not represented by other variants.
 	///
 	/// `ErrorValue`s of the `Other` type should only be `match`ed on with a wildcard
@@ -92,6 +96,9 @@ impl fmt::Display for ErrorValue {
 			#[cfg(feature = "sqpack")]
 			Self::File(file) => write!(formatter, "SqPack file ({} bytes)", file.len()),
 
+			#[cfg(feature = "sqpack")]
+			Self::Placeholder(placeholder) => write!(formatter, "SqPack {placeholder}"),
+
 			Self::Other(value) => write!(formatter, "{value}"),
 		}
 	}
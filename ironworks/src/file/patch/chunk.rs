@@ -1,6 +1,6 @@
 use std::io::{Read, Seek};
 
-use binrw::{binread, BinRead, BinResult, Endian};
+use binrw::{binread, binrw, BinRead, BinResult, Endian};
 use getset::{CopyGetters, Getters};
 
 use super::command::{
@@ -40,13 +40,13 @@ pub enum Chunk {
 }
 
 /// Metadata about the .patch file and information it contains.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, Getters, CopyGetters)]
 pub struct FileHeaderChunk {
 	// unk1: u16
 	/// Version of the patch format.
-	#[br(pad_before = 2)]
+	#[brw(pad_before = 2)]
 	#[get_copy = "pub"]
 	version: u8,
 
@@ -56,7 +56,7 @@ pub struct FileHeaderChunk {
 	/// NOTE: This value is likely untrustworthy, and frequently does not match
 	/// the type of patch indicated by the patch's file name. Take with a grain
 	/// of salt.
-	#[br(pad_before = 1)]
+	#[brw(pad_before = 1)]
 	#[get_copy = "pub"]
 	patch_kind: PatchKind,
 
@@ -70,20 +70,34 @@ pub struct FileHeaderChunk {
 	v3: Option<FileHeaderV3>,
 }
 
-#[binread]
-#[br(big)]
+impl FileHeaderChunk {
+	/// Build a version 2 file header for a patch of the specified kind.
+	pub fn new(patch_kind: PatchKind, entry_files: u32) -> Self {
+		Self {
+			version: 2,
+			patch_kind,
+			entry_files,
+			v3: None,
+		}
+	}
+}
+
+/// Kind of patch file.
+#[allow(missing_docs)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, Clone, Copy)]
 pub enum PatchKind {
-	#[br(magic = b"DIFF")]
+	#[brw(magic = b"DIFF")]
 	Diff,
 
-	#[br(magic = b"HIST")]
+	#[brw(magic = b"HIST")]
 	Hist,
 }
 
 /// Additional fields available in file header chunks in version 3 of the format.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct FileHeaderV3 {
@@ -94,11 +108,14 @@ pub struct FileHeaderV3 {
 
 	// wtaf?
 	#[br(temp)]
+	#[bw(calc = (delete_data & u64::from(u32::MAX)).try_into().unwrap())]
 	delete_data_1: u32,
 	#[br(temp)]
+	#[bw(calc = (delete_data >> 32).try_into().unwrap())]
 	delete_data_2: u32,
 	///
 	#[br(calc = u64::from(delete_data_1) | u64::from(delete_data_2) << 32)]
+	#[bw(ignore)]
 	delete_data: u64,
 
 	///
@@ -120,8 +137,8 @@ pub struct FileHeaderV3 {
 }
 
 /// An option key-value pair that should be applied while reading remaining chunks.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[getset(get_copy = "pub")]
 pub struct ApplyChunk {
@@ -131,14 +148,14 @@ pub struct ApplyChunk {
 	// unk1: u32,
 	/// Value to set for the option. For both known options, a non-zero `value`
 	/// represents `true`.
-	#[br(pad_before = 4)]
+	#[brw(pad_before = 4)]
 	value: u32,
 	// unk2: [u8; 4],
 }
 
 #[allow(missing_docs)]
-#[binread]
-#[br(big, repr = u32)]
+#[binrw]
+#[brw(big, repr = u32)]
 #[derive(Debug, Clone, Copy)]
 pub enum OptionKind {
 	IgnoreMissing = 1,
@@ -146,35 +163,53 @@ pub enum OptionKind {
 }
 
 /// Create a new directory.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct AddDirectoryChunk {
 	#[br(temp)]
+	#[bw(calc = path.len().try_into().unwrap())]
 	length: u32,
 
 	/// Path of the directory to add. Path is relative to the target folder.
 	#[br(count = length)]
 	#[br(try_map = String::from_utf8)]
+	#[bw(map = |path: &String| path.as_bytes().to_vec())]
 	path: String,
 }
 
+impl AddDirectoryChunk {
+	/// Build a chunk creating the directory at `path`, relative to the target folder.
+	pub fn new(path: impl Into<String>) -> Self {
+		Self { path: path.into() }
+	}
+}
+
 /// Delete an empty folder. Deleting a non-empty folder is considered an error.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
 pub struct DeleteDirectoryChunk {
 	#[br(temp)]
+	#[bw(calc = path.len().try_into().unwrap())]
 	length: u32,
 
 	/// Path of the directory to delete. Path is relative to the target folder.
 	#[br(count = length)]
 	#[br(try_map = String::from_utf8)]
+	#[bw(map = |path: &String| path.as_bytes().to_vec())]
 	path: String,
 }
 
+impl DeleteDirectoryChunk {
+	/// Build a chunk deleting the directory at `path`, relative to the target folder.
+	pub fn new(path: impl Into<String>) -> Self {
+		Self { path: path.into() }
+	}
+}
+
 /// Extension chunk to perform operations on a SqPack-based game install.
 #[derive(Debug)]
 pub enum SqPackChunk {
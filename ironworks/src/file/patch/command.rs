@@ -1,6 +1,6 @@
 use std::io::{Read, Seek, SeekFrom};
 
-use binrw::{binread, BinRead, BinResult, Endian, NullString, PosValue};
+use binrw::{binread, binrw, BinRead, BinResult, Endian, NullString, PosValue};
 use getset::{CopyGetters, Getters};
 
 const UNCOMPRESSED_MARKER_SIZE: u32 = 32_000;
@@ -14,8 +14,8 @@ const UNCOMPRESSED_MARKER_SIZE: u32 = 32_000;
 /// Where `platform` is a string, such as `"win32"`, `file_type` is `"dat"` or
 /// `"index"`, and `maybe_file_id` is an empty string for indices with `file_id == 0`,
 /// and otherwise equivalent to `file_id`.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, Clone, Copy, CopyGetters)]
 #[get_copy = "pub"]
 pub struct SqPackFile {
@@ -27,81 +27,145 @@ pub struct SqPackFile {
 	file_id: u32,
 }
 
+impl SqPackFile {
+	/// Build a representation of the SqPack file with the specified IDs.
+	pub fn new(main_id: u16, sub_id: u16, file_id: u32) -> Self {
+		Self {
+			main_id,
+			sub_id,
+			file_id,
+		}
+	}
+}
+
 /// Write data to a file.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct AddCommand {
 	// unk1: [u8; 3]
 	/// File to modify.
-	#[br(pad_before = 3)]
+	#[brw(pad_before = 3)]
 	file: SqPackFile,
 	/// Target file offset to start writing, in bytes.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	target_offset: u32,
 	/// Size of data to copy, in bytes.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	data_size: u32,
 	/// Number of bytes to blank after writing.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	delete_size: u32,
 
 	/// Offset in bytes within the patch file to read the data from.
 	#[br(map = |value: PosValue<()>| value.pos)]
+	#[bw(ignore)]
 	source_offset: u64,
 }
 
+impl AddCommand {
+	/// Build a command writing `data_size` bytes of data to `file`, followed by
+	/// `delete_size` blank bytes. Offsets and sizes must be multiples of 128
+	/// bytes. When written, the data must immediately follow the command.
+	pub fn new(file: SqPackFile, target_offset: u32, data_size: u32, delete_size: u32) -> Self {
+		Self {
+			file,
+			target_offset,
+			data_size,
+			delete_size,
+			source_offset: 0,
+		}
+	}
+}
+
 /// Delete data from a file.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct DeleteCommand {
 	// unk1: [u8; 3]
 	/// File to modify.
-	#[br(pad_before = 3)]
+	#[brw(pad_before = 3)]
 	file: SqPackFile,
 	/// Offset to start writing at.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	target_offset: u32,
 	/// Number of blank bytes that that should be written.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	delete_size: u32,
 }
 
+impl DeleteCommand {
+	/// Build a command blanking `delete_size` bytes of `file`. The offset and
+	/// size must be multiples of 128 bytes.
+	pub fn new(file: SqPackFile, target_offset: u32, delete_size: u32) -> Self {
+		Self {
+			file,
+			target_offset,
+			delete_size,
+		}
+	}
+}
+
 /// Expand the size of a file.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct ExpandCommand {
 	// unk1: [u8; 3]
 	/// File to modify.
-	#[br(pad_before = 3)]
+	#[brw(pad_before = 3)]
 	file: SqPackFile,
 	/// Offset to start writing at.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	target_offset: u32,
 	/// Number of blank bytes that that should be written.
 	#[br(map = |value: u32| value << 7)]
+	#[bw(map = |value: &u32| value >> 7)]
 	delete_size: u32,
 }
 
+impl ExpandCommand {
+	/// Build a command expanding `file` with `delete_size` blank bytes. The
+	/// offset and size must be multiples of 128 bytes.
+	pub fn new(file: SqPackFile, target_offset: u32, delete_size: u32) -> Self {
+		Self {
+			file,
+			target_offset,
+			delete_size,
+		}
+	}
+}
+
 /// Perform a file operation.
-#[binread]
+///
+/// When written, only the command's header is emitted - the block headers and
+/// payloads of [`FileOperation::AddFile`] operations must be written following it.
+#[binrw]
 #[derive(Debug, Getters, CopyGetters)]
 #[br(big, import(command_size: u32))]
+#[bw(big)]
 pub struct FileOperationCommand {
 	#[br(temp, map = |value: PosValue<()>| value.pos)]
+	#[bw(ignore)]
 	command_start: u64,
 
 	#[br(temp)]
+	#[bw(calc = operation.magic())]
 	operation_magic: u8,
 
 	// unk1: [u8; 2]
 	/// Offset within the target file to start writing in the case of an AddFile operation.
-	#[br(pad_before = 2)]
+	#[brw(pad_before = 2)]
 	#[get_copy = "pub"]
 	target_offset: u64,
 
@@ -111,6 +175,7 @@ pub struct FileOperationCommand {
 	target_size: u64,
 
 	#[br(temp)]
+	#[bw(calc = (path.len() + 1).try_into().unwrap())]
 	path_length: u32,
 
 	///
@@ -119,17 +184,38 @@ pub struct FileOperationCommand {
 
 	// unk2: [u8; 2]
 	/// Path of the target file within the game's directory.
-	#[br(pad_before = 2)]
+	#[brw(pad_before = 2)]
 	#[br(pad_size_to = path_length)]
 	#[get = "pub"]
 	path: NullString,
 
 	/// File operation to be performed.
 	#[br(args(operation_magic, command_start, command_size))]
+	#[bw(ignore)]
 	#[get = "pub"]
 	operation: FileOperation,
 }
 
+impl FileOperationCommand {
+	/// Build a file operation command performing `operation` on the file at
+	/// `path`, relative to the game's directory.
+	pub fn new(
+		operation: FileOperation,
+		path: &str,
+		repository_id: u16,
+		target_offset: u64,
+		target_size: u64,
+	) -> Self {
+		Self {
+			target_offset,
+			target_size,
+			repository_id,
+			path: path.into(),
+			operation,
+		}
+	}
+}
+
 /// The operation that should be performed by a file operation command.
 #[binread]
 #[br(import(magic: u8, command_start: u64, command_size: u32))]
@@ -158,6 +244,17 @@ pub enum FileOperation {
 	RemoveAll,
 }
 
+impl FileOperation {
+	fn magic(&self) -> u8 {
+		match self {
+			Self::AddFile(_) => b'A',
+			Self::DeleteFile => b'D',
+			Self::MakeDirTree => b'M',
+			Self::RemoveAll => b'R',
+		}
+	}
+}
+
 fn parse_block_headers<R: Read + Seek>(
 	reader: &mut R,
 	options: Endian,
@@ -190,8 +287,8 @@ fn parse_block_headers<R: Read + Seek>(
 
 // This is identical to the `BlockHeader` in `sqpack::file` - TODO: look into sharing.
 /// Block of potentially-compressed data
-#[binread]
-#[br(little)] // REALLY?
+#[binrw]
+#[brw(little)] // REALLY?
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct BlockHeader {
@@ -199,17 +296,29 @@ pub struct BlockHeader {
 	header_size: u32,
 	// unk1: [u8; 4]
 	/// Compressed size of the block, or 32,000 to signify the block is not compressed.
-	#[br(pad_before = 4)]
+	#[brw(pad_before = 4)]
 	compressed_size: u32,
 	/// The decompressed size of the block, or the full size of the data for uncompressed blocks.
 	decompressed_size: u32,
 
 	/// Offset within the patch file that the payload starts.
 	#[br(map = |value: PosValue<()>| value.pos)]
+	#[bw(ignore)]
 	offset: u64,
 }
 
 impl BlockHeader {
+	/// Build a header for a block with the given sizes. Uncompressed blocks
+	/// should use a `compressed_size` of 32,000.
+	pub fn new(compressed_size: u32, decompressed_size: u32) -> Self {
+		Self {
+			header_size: 16,
+			compressed_size,
+			decompressed_size,
+			offset: 0,
+		}
+	}
+
 	/// Whether this block is compressed within the patch file.
 	pub fn is_compressed(&self) -> bool {
 		self.compressed_size != UNCOMPRESSED_MARKER_SIZE
@@ -225,8 +334,8 @@ impl BlockHeader {
 }
 
 /// Update the header of a file.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct HeaderUpdateCommand {
@@ -236,22 +345,38 @@ pub struct HeaderUpdateCommand {
 	header_kind: HeaderKind,
 
 	/// File to modify.
-	#[br(pad_before = 1)]
+	#[brw(pad_before = 1)]
 	file: SqPackFile,
 
 	/// Offset within the patch file that the payload starts.
 	#[br(map = |value: PosValue<()>| value.pos)]
+	#[bw(ignore)]
 	offset: u64,
 
 	// It's _always_ 1kb of data.
 	/// Number of bytes that should be written
 	#[br(calc = 1024)]
+	#[bw(ignore)]
 	size: u32,
 }
 
+impl HeaderUpdateCommand {
+	/// Build a command updating a header of `file`. When written, the 1024 bytes
+	/// of header data must immediately follow the command.
+	pub fn new(file_kind: HeaderFileKind, header_kind: HeaderKind, file: SqPackFile) -> Self {
+		Self {
+			file_kind,
+			header_kind,
+			file,
+			offset: 0,
+			size: 1024,
+		}
+	}
+}
+
 #[allow(missing_docs)]
-#[binread]
-#[br(repr = u8)]
+#[binrw]
+#[brw(repr = u8)]
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum HeaderFileKind {
@@ -260,8 +385,8 @@ pub enum HeaderFileKind {
 }
 
 #[allow(missing_docs)]
-#[binread]
-#[br(repr = u8)]
+#[binrw]
+#[brw(repr = u8)]
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum HeaderKind {
@@ -274,8 +399,8 @@ pub enum HeaderKind {
 }
 
 /// Update an entry in a SqPack index file.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct IndexUpdateCommand {
@@ -283,10 +408,11 @@ pub struct IndexUpdateCommand {
 	kind: IndexUpdateKind,
 	/// If the target entry is a synonym.
 	#[br(map = |value: u8| value != 0)]
+	#[bw(map = |value: &bool| u8::from(*value))]
 	is_synonym: bool,
 	// align: u8
 	/// Index file to modify.
-	#[br(pad_before = 1)]
+	#[brw(pad_before = 1)]
 	file: SqPackFile,
 	/// Hash key of the index entry to modify.
 	file_hash: u64,
@@ -297,8 +423,8 @@ pub struct IndexUpdateCommand {
 }
 
 #[allow(missing_docs)]
-#[binread]
-#[br(repr = u8)]
+#[binrw]
+#[brw(repr = u8)]
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum IndexUpdateKind {
@@ -307,8 +433,8 @@ pub enum IndexUpdateKind {
 }
 
 /// Metadata about the SqPack patch.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct PatchInfoCommand {
@@ -319,26 +445,27 @@ pub struct PatchInfoCommand {
 
 	// align: u8,
 	///
-	#[br(pad_before = 1)]
+	#[brw(pad_before = 1)]
 	install_size: u64,
 }
 
 /// Metadata about the target SqPack install.
-#[binread]
-#[br(big)]
+#[binrw]
+#[brw(big)]
 #[derive(Debug, CopyGetters)]
 #[get_copy = "pub"]
 pub struct TargetInfoCommand {
 	// unk1: [u8; 3]
 	/// The target platform of this patch.
-	#[br(pad_before = 3)]
+	#[brw(pad_before = 3)]
 	platform: TargetPlatform,
 
 	/// The target game service region of this patch.
 	region: TargetRegion,
 
 	///
-	#[br(map = |value: u16| value !=0)]
+	#[br(map = |value: u16| value != 0)]
+	#[bw(map = |value: &bool| u16::from(*value))]
 	is_debug: bool,
 
 	///
@@ -351,9 +478,28 @@ pub struct TargetInfoCommand {
 	_seek_count: u64,
 }
 
+impl TargetInfoCommand {
+	/// Build metadata for a patch targeting the specified platform and region.
+	pub fn new(
+		platform: TargetPlatform,
+		region: TargetRegion,
+		is_debug: bool,
+		version: u16,
+	) -> Self {
+		Self {
+			platform,
+			region,
+			is_debug,
+			version,
+			_deleted_data_size: 0,
+			_seek_count: 0,
+		}
+	}
+}
+
 #[allow(missing_docs)]
-#[binread]
-#[br(repr = u16)]
+#[binrw]
+#[brw(repr = u16)]
 #[derive(Debug, Clone, Copy)]
 pub enum TargetPlatform {
 	Win32 = 0,
@@ -363,8 +509,8 @@ pub enum TargetPlatform {
 }
 
 #[allow(missing_docs)]
-#[binread]
-#[br(repr = i16)]
+#[binrw]
+#[brw(repr = i16)]
 #[derive(Debug, Clone, Copy)]
 pub enum TargetRegion {
 	Global = -1,
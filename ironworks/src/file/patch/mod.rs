@@ -7,7 +7,7 @@ mod zipatch;
 pub use {
 	chunk::{
 		AddDirectoryChunk, ApplyChunk, Chunk, DeleteDirectoryChunk, FileHeaderChunk, FileHeaderV3,
-		OptionKind, SqPackChunk,
+		OptionKind, PatchKind, SqPackChunk,
 	},
 	command::{
 		AddCommand, BlockHeader, DeleteCommand, ExpandCommand, FileOperation, FileOperationCommand,
@@ -16,3 +16,6 @@ pub use {
 	},
 	zipatch::{ChunkIterator, ZiPatch},
 };
+
+#[cfg(feature = "zipatch")]
+pub(crate) use zipatch::ZIPATCH_MAGIC;
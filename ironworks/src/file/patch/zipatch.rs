@@ -1,16 +1,21 @@
 use std::{
-	io::SeekFrom,
+	io::{self, Read, SeekFrom},
 	sync::{Arc, Mutex},
 };
 
 use binrw::BinRead;
 use derivative::Derivative;
 
-use crate::{error::Result, file::File, FileStream};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	file::File,
+	utility::crc32_continue,
+	FileStream,
+};
 
 use super::chunk::Chunk;
 
-const ZIPATCH_MAGIC: &[u8; 12] = b"\x91ZIPATCH\x0D\x0A\x1A\x0A";
+pub(crate) const ZIPATCH_MAGIC: &[u8; 12] = b"\x91ZIPATCH\x0D\x0A\x1A\x0A";
 
 /// ZiPatch incremental patch file format.
 ///
@@ -38,7 +43,10 @@ impl File for ZiPatch {
 		stream.read_exact(&mut magic)?;
 
 		if &magic != ZIPATCH_MAGIC {
-			todo!("error message")
+			return Err(Error::Invalid(
+				ErrorValue::Other("ZiPatch file".into()),
+				format!("unexpected magic {magic:02x?}"),
+			));
 		}
 
 		// Rest of the file is chunks that we'll read lazily.
@@ -59,6 +67,7 @@ pub struct ChunkIterator {
 	stream: Arc<Mutex<Box<dyn FileStream>>>,
 	offset: u64,
 	complete: bool,
+	verify_checksums: bool,
 }
 
 impl ChunkIterator {
@@ -67,10 +76,41 @@ impl ChunkIterator {
 			stream,
 			offset: ZIPATCH_MAGIC.len().try_into().unwrap(),
 			complete: false,
+			verify_checksums: false,
 		}
 	}
 
+	/// Set whether the CRC32 checksum of each chunk should be verified as it is
+	/// read. Verification requires reading the full contents of every chunk,
+	/// but will catch truncated or corrupt data that would otherwise parse
+	/// successfully.
+	#[must_use]
+	pub fn with_verify_checksums(mut self, verify_checksums: bool) -> Self {
+		self.set_verify_checksums(verify_checksums);
+		self
+	}
+
+	/// Set whether the CRC32 checksum of each chunk should be verified as it is
+	/// read. Verification requires reading the full contents of every chunk,
+	/// but will catch truncated or corrupt data that would otherwise parse
+	/// successfully.
+	pub fn set_verify_checksums(&mut self, verify_checksums: bool) {
+		self.verify_checksums = verify_checksums;
+	}
+
 	fn read_chunk(&mut self) -> Result<Chunk> {
+		let offset = self.offset;
+		let invalid = |reason: String| {
+			Error::Invalid(
+				ErrorValue::Other(format!("ZiPatch chunk at offset {offset:#x}")),
+				reason,
+			)
+		};
+		let truncated = |error: io::Error| match error.kind() {
+			io::ErrorKind::UnexpectedEof => invalid("patch file is truncated".into()),
+			_ => error.into(),
+		};
+
 		let mut handle = self.stream.lock().unwrap();
 
 		// Seek to last known offset - in a tight loop this is effectively a noop,
@@ -78,17 +118,56 @@ impl ChunkIterator {
 		// TODO: lots of jumping around would be catastrophic for read performance - it'd be nice to be able to request something cloneable, so i.e. file handles could be cloned between chunk iterators, rather than trying to share access to a single one - but i'm not sure how to mode that without major refactors.
 		handle.seek(SeekFrom::Start(self.offset))?;
 
-		let size = u32::read_be(&mut *handle)?;
+		let mut size = [0; 4];
+		handle.read_exact(&mut size).map_err(truncated)?;
+		let size = u32::from_be_bytes(size);
+
+		if self.verify_checksums {
+			// Check the chunk fits in the stream before reading it, so a corrupt size
+			// is reported rather than acted upon.
+			let length = handle.seek(SeekFrom::End(0))?;
+			if offset + 12 + u64::from(size) > length {
+				return Err(invalid("patch file is truncated".into()));
+			}
+			handle.seek(SeekFrom::Start(offset + 4))?;
+
+			// Magic and data, followed by the checksum. Chunks can be large, hash them piecemeal.
+			let mut content = (&mut *handle).take(u64::from(size) + 4);
+			let mut buffer = [0; 0x10000];
+			let mut working = u32::MAX;
+			loop {
+				let read = content.read(&mut buffer)?;
+				if read == 0 {
+					break;
+				}
+				working = crc32_continue(working, &buffer[..read]);
+			}
+
+			let mut checksum = [0; 4];
+			handle.read_exact(&mut checksum).map_err(truncated)?;
+			let expected = u32::from_be_bytes(checksum);
+			// utility::crc32 omits the final xor of the standard algorithm.
+			let actual = !working;
+			if actual != expected {
+				return Err(invalid(format!(
+					"checksum mismatch: expected {expected:#010x}, got {actual:#010x}"
+				)));
+			}
+
+			// Chunks record positions within the file, so are parsed from the stream directly.
+			handle.seek(SeekFrom::Start(offset + 4))?;
+		}
 
-		let chunk = Chunk::read_args(&mut *handle, (size,))?;
+		let chunk = Chunk::read_args(&mut *handle, (size,)).map_err(|error| match error {
+			binrw::Error::Io(error) => truncated(error),
+			other => invalid(other.to_string()),
+		})?;
 
 		// Update iterator offset to the start of the next chunk. `size` only represents
 		// the size of the chunk data itself, so the +12 is to account for the other
 		// fields in the container.
 		self.offset += u64::from(size) + 12;
 
-		// TODO: check the hash? is it worth it? I'd need to relock the stream for that...
-
 		Ok(chunk)
 	}
 }
@@ -103,7 +182,8 @@ impl Iterator for ChunkIterator {
 
 		let chunk = self.read_chunk();
 
-		if let Ok(Chunk::EndOfFile) = chunk {
+		// Chunks following an invalid chunk can't be located, stop iteration.
+		if matches!(chunk, Ok(Chunk::EndOfFile) | Err(_)) {
 			self.complete = true;
 		}
 
@@ -1,10 +1,19 @@
-use std::io::{Read, Seek};
+use std::{
+	collections::BTreeSet,
+	io::{Cursor, Read, Seek},
+	sync::Arc,
+};
 
 use derivative::Derivative;
+use getset::{CopyGetters, Getters};
 
+#[cfg(feature = "async")]
+use crate::async_resource::{AsyncResource, BlockingResource, BoxFuture};
 use crate::{
+	cache::{CacheStats, FileCache, ResourceKey},
 	error::{Error, ErrorValue, Result},
 	file::File,
+	utility::crc32,
 };
 
 /// Representation of a file stream read from a resource.
@@ -23,6 +32,93 @@ pub trait Resource: Send + Sync + 'static {
 	/// `Err(Error::NotFound(ErrorValue::Path(_)))` will result in lookups
 	/// continuing to the next resource.
 	fn file(&self, path: &str) -> Result<Box<dyn FileStream>>;
+
+	/// Check if this resource provides the file at `path`. The default
+	/// implementation attempts to read the file; resources that can answer more
+	/// cheaply should override it.
+	fn exists(&self, path: &str) -> Result<bool> {
+		match self.file(path) {
+			Ok(_) => Ok(true),
+			Err(Error::NotFound(ErrorValue::Path(_))) => Ok(false),
+			Err(error) => Err(error),
+		}
+	}
+
+	/// List the files provided by this resource with paths beginning with
+	/// `prefix`. A return value of `Err(Error::NotFound(ErrorValue::Path(_)))`
+	/// indicates that the resource is unable to enumerate the requested prefix,
+	/// and is the default for resources that do not support listing.
+	fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		Err(Error::NotFound(ErrorValue::Path(prefix.into())))
+	}
+
+	/// Human-readable name of this resource, used when reporting which resource
+	/// served a file. Defaults to the type name of the resource.
+	fn name(&self) -> String {
+		std::any::type_name::<Self>().into()
+	}
+}
+
+/// Entry in a listing of files provided by resources.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum ListEntry {
+	/// File with a fully known path.
+	Path(String),
+
+	/// File within a known directory, for which only a hash of the file name is
+	/// known. SqPack indexes do not store file names, and will typically return
+	/// entries of this kind.
+	Hashed {
+		/// Path of the directory containing the file, without a trailing `/`.
+		directory: String,
+		/// Hash of the file name, as used by SqPack index files.
+		file_hash: u32,
+	},
+}
+
+/// Descriptor of the resource that served a file.
+#[derive(Debug, Clone, Getters, CopyGetters)]
+pub struct Source {
+	/// Index of the resource, in the order resources were added to ironworks.
+	#[get_copy = "pub"]
+	index: usize,
+
+	/// Name of the resource, as reported by [`Resource::name`].
+	#[get = "pub"]
+	name: String,
+
+	/// Version string reported by the resource for the file, if any.
+	#[get = "pub"]
+	version: Option<String>,
+}
+
+/// A single resource consulted while tracing a file lookup.
+#[derive(Debug, Getters, CopyGetters)]
+pub struct TraceStep {
+	/// Index of the resource, in the order resources were added to ironworks.
+	#[get_copy = "pub"]
+	index: usize,
+
+	/// Name of the resource, as reported by [`Resource::name`].
+	#[get = "pub"]
+	name: String,
+
+	/// Result of consulting the resource.
+	#[get = "pub"]
+	outcome: TraceOutcome,
+}
+
+/// Result of consulting a resource while tracing a file lookup.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TraceOutcome {
+	/// The resource provides the file, and will serve the lookup.
+	Found,
+	/// The resource does not provide the file; the lookup continues to the next resource.
+	NotFound,
+	/// The resource failed while checking for the file, ending the lookup.
+	Failed(Error),
 }
 
 /// Core ironworks struct. Add one or more resources to query files.
@@ -30,8 +126,14 @@ pub trait Resource: Send + Sync + 'static {
 #[derivative(Debug)]
 pub struct Ironworks {
 	#[derivative(Debug = "ignore")]
-	resources: Vec<Box<dyn Resource>>,
-	// todo: does this own the file cache, then?
+	resources: Vec<Arc<dyn Resource>>,
+	// All resources, in the order they were added, for use by asynchronous
+	// lookups. Blocking resources are wrapped to run on the blocking thread pool.
+	#[cfg(feature = "async")]
+	#[derivative(Debug = "ignore")]
+	async_resources: Vec<(ResourceKey, Box<dyn AsyncResource>)>,
+
+	cache: Option<FileCache>,
 }
 
 impl Default for Ironworks {
@@ -45,6 +147,10 @@ impl Ironworks {
 	pub fn new() -> Self {
 		Self {
 			resources: Default::default(),
+			#[cfg(feature = "async")]
+			async_resources: Default::default(),
+
+			cache: None,
 		}
 	}
 
@@ -52,7 +158,15 @@ impl Ironworks {
 	/// last resource added to ironworks that provides a requested path will be
 	/// the resource that is utilised.
 	pub fn add_resource(&mut self, resource: impl Resource) {
-		self.resources.push(Box::new(resource));
+		let resource = Arc::new(resource);
+
+		#[cfg(feature = "async")]
+		self.async_resources.push((
+			ResourceKey::Blocking(self.resources.len()),
+			Box::new(BlockingResource::from_arc(resource.clone())),
+		));
+
+		self.resources.push(resource);
 	}
 
 	/// Add a resource to search for files. Resources are searched last-first; the
@@ -60,31 +174,451 @@ impl Ironworks {
 	/// the resource that is utilised.
 	#[must_use]
 	pub fn with_resource(mut self, resource: impl Resource) -> Self {
-		self.resources.push(Box::new(resource));
+		self.add_resource(resource);
 		self
 	}
 
+	/// Add a resource to search for files when using asynchronous lookups.
+	/// Resources are searched last-first alongside those added with
+	/// [`Ironworks::add_resource`], in the order all resources were added.
+	#[cfg(feature = "async")]
+	pub fn add_async_resource(&mut self, resource: impl AsyncResource) {
+		self.async_resources.push((
+			ResourceKey::Async(self.async_resources.len()),
+			Box::new(resource),
+		));
+	}
+
+	/// Add a resource to search for files when using asynchronous lookups.
+	/// Resources are searched last-first alongside those added with
+	/// [`Ironworks::add_resource`], in the order all resources were added.
+	#[cfg(feature = "async")]
+	#[must_use]
+	pub fn with_async_resource(mut self, resource: impl AsyncResource) -> Self {
+		self.add_async_resource(resource);
+		self
+	}
+
+	/// Set a cache to retain file data read from resources. Cached files are keyed
+	/// by both their path and the resource that provided them.
+	#[must_use]
+	pub fn with_file_cache(mut self, cache: FileCache) -> Self {
+		self.set_file_cache(cache);
+		self
+	}
+
+	/// Set a cache to retain file data read from resources. Cached files are keyed
+	/// by both their path and the resource that provided them.
+	pub fn set_file_cache(&mut self, cache: FileCache) {
+		self.cache = Some(cache);
+	}
+
+	/// Get the file cache registered with this instance, if any.
+	pub fn file_cache(&self) -> Option<&FileCache> {
+		self.cache.as_ref()
+	}
+
+	/// Get a snapshot of the file cache statistics, if a cache is registered.
+	pub fn cache_stats(&self) -> Option<CacheStats> {
+		self.cache.as_ref().map(FileCache::stats)
+	}
+
 	/// Get the version string for the file at `path`.
 	pub fn version(&self, path: &str) -> Result<String> {
-		self.find_first(path, |resource| resource.version(path))
+		self.find_first(path, |_index, resource| resource.version(path))
+			.map(|(_index, version)| version)
 	}
 
 	/// Read the file at `path`, using file type F to parse. To retrieve the file
 	/// as raw bytes, pass `Vec<u8>` to F.
 	pub fn file<F: File>(&self, path: &str) -> Result<F> {
-		let stream = self.find_first(path, |resource| resource.file(path))?;
-		F::read(stream)
+		self.file_indexed(path).map(|(_index, file)| file)
+	}
+
+	/// Read the file at `path`, using file type F to parse, alongside a
+	/// descriptor of the resource that served it.
+	pub fn file_with_source<F: File>(&self, path: &str) -> Result<(F, Source)> {
+		let (index, file) = self.file_indexed(path)?;
+		Ok((file, self.source(index, path)?))
+	}
+
+	/// Get a descriptor of the resource that would serve the file at `path`,
+	/// without reading the file.
+	pub fn resolve(&self, path: &str) -> Result<Source> {
+		let (index, _) =
+			self.find_first(path, |_index, resource| match resource.exists(path)? {
+				true => Ok(()),
+				false => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+			})?;
+		self.source(index, path)
+	}
+
+	/// Trace a lookup of the file at `path`, recording each resource consulted
+	/// and its result, in the order they were consulted.
+	pub fn trace(&self, path: &str) -> Vec<TraceStep> {
+		let mut steps = Vec::new();
+
+		for (index, resource) in self.resources.iter().enumerate().rev() {
+			let outcome = match resource.exists(path) {
+				Ok(true) => TraceOutcome::Found,
+				Ok(false) => TraceOutcome::NotFound,
+				Err(error) => TraceOutcome::Failed(error),
+			};
+
+			let finished = !matches!(outcome, TraceOutcome::NotFound);
+			steps.push(TraceStep {
+				index,
+				name: resource.name(),
+				outcome,
+			});
+
+			if finished {
+				break;
+			}
+		}
+
+		steps
+	}
+
+	/// Check if any resource provides the file at `path`.
+	pub fn exists(&self, path: &str) -> Result<bool> {
+		for resource in self.resources.iter().rev() {
+			if resource.exists(path)? {
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+
+	/// List the files with paths beginning with `prefix`, merged across all
+	/// resources. Hashed entries are omitted where another resource provides the
+	/// same file with a full path. If no resource is able to enumerate the prefix,
+	/// a `NotFound` error will be returned.
+	pub fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		let mut entries = BTreeSet::new();
+		let mut listed = false;
+
+		for resource in self.resources.iter().rev() {
+			match resource.list(prefix) {
+				Err(Error::NotFound(ErrorValue::Path(_))) => continue,
+				Err(error) => return Err(error),
+				Ok(resource_entries) => {
+					listed = true;
+					entries.extend(resource_entries);
+				}
+			}
+		}
+
+		if !listed {
+			return Err(Error::NotFound(ErrorValue::Path(prefix.into())));
+		}
+
+		let known_hashes = entries
+			.iter()
+			.filter_map(|entry| match entry {
+				ListEntry::Path(path) => path.rsplit_once('/'),
+				_ => None,
+			})
+			.map(|(directory, file)| (directory.to_string(), crc32(file.as_bytes())))
+			.collect::<BTreeSet<_>>();
+
+		Ok(entries
+			.into_iter()
+			.filter(|entry| match entry {
+				ListEntry::Hashed {
+					directory,
+					file_hash,
+				} => !known_hashes.contains(&(directory.clone(), *file_hash)),
+				_ => true,
+			})
+			.collect())
+	}
+
+	/// Asynchronously get the version string for the file at `path`. Blocking
+	/// resources are consulted on tokio's blocking thread pool, and as such this
+	/// must be called from within a tokio runtime if any have been added.
+	#[cfg(feature = "async")]
+	pub async fn version_async(&self, path: &str) -> Result<String> {
+		self.find_first_async(path, |_key, resource| resource.version(path))
+			.await
 	}
 
-	fn find_first<F, O>(&self, path: &str, f: F) -> Result<O>
+	/// Asynchronously read the file at `path`, using file type F to parse.
+	/// Blocking resources are consulted on tokio's blocking thread pool, and as
+	/// such this must be called from within a tokio runtime if any have been added.
+	#[cfg(feature = "async")]
+	pub async fn file_async<F: File>(&self, path: &str) -> Result<F> {
+		let data = self
+			.find_first_async(path, |key, resource| {
+				Box::pin(async move {
+					let cache = self.cache.as_ref();
+					if let Some(data) = cache.and_then(|cache| cache.get(key, path)) {
+						return Ok(data);
+					}
+
+					let data = Arc::<[u8]>::from(resource.file(path).await?);
+					if let Some(cache) = cache {
+						cache.insert(key, path, data.clone());
+					}
+					Ok(data)
+				})
+			})
+			.await?;
+		F::read(Cursor::new(data))
+	}
+
+	fn file_indexed<F: File>(&self, path: &str) -> Result<(usize, F)> {
+		let (index, file) = match &self.cache {
+			Some(cache) => {
+				let (index, data) = self.cached_file(cache, path)?;
+				(index, F::read(Cursor::new(data))?)
+			}
+			None => {
+				let (index, stream) =
+					self.find_first(path, |_index, resource| resource.file(path))?;
+				(index, F::read(stream)?)
+			}
+		};
+		Ok((index, file))
+	}
+
+	fn source(&self, index: usize, path: &str) -> Result<Source> {
+		let resource = &self.resources[index];
+
+		let version = match resource.version(path) {
+			Ok(version) => Some(version),
+			Err(Error::NotFound(ErrorValue::Path(_))) => None,
+			Err(error) => return Err(error),
+		};
+
+		Ok(Source {
+			index,
+			name: resource.name(),
+			version,
+		})
+	}
+
+	fn cached_file(&self, cache: &FileCache, path: &str) -> Result<(usize, Arc<[u8]>)> {
+		self.find_first(path, |index, resource| {
+			let key = ResourceKey::Blocking(index);
+			if let Some(data) = cache.get(key, path) {
+				return Ok(data);
+			}
+
+			let mut buffer = Vec::new();
+			resource.file(path)?.read_to_end(&mut buffer)?;
+
+			let data = Arc::<[u8]>::from(buffer);
+			cache.insert(key, path, data.clone());
+			Ok(data)
+		})
+	}
+
+	fn find_first<F, O>(&self, path: &str, f: F) -> Result<(usize, O)>
 	where
-		F: Fn(&Box<dyn Resource>) -> Result<O>,
+		F: Fn(usize, &dyn Resource) -> Result<O>,
 	{
 		self.resources
 			.iter()
+			.enumerate()
 			.rev()
-			.map(f)
+			.map(|(index, resource)| f(index, resource.as_ref()).map(|value| (index, value)))
 			.find(|result| !matches!(result, Err(Error::NotFound(ErrorValue::Path(_)))))
 			.unwrap_or_else(|| Err(Error::NotFound(ErrorValue::Path(path.into()))))
 	}
+
+	#[cfg(feature = "async")]
+	async fn find_first_async<'a, F, O>(&'a self, path: &str, f: F) -> Result<O>
+	where
+		F: Fn(ResourceKey, &'a dyn AsyncResource) -> BoxFuture<'a, Result<O>>,
+	{
+		for (key, resource) in self.async_resources.iter().rev() {
+			match f(*key, resource.as_ref()).await {
+				Err(Error::NotFound(ErrorValue::Path(_))) => continue,
+				result => return result,
+			}
+		}
+
+		Err(Error::NotFound(ErrorValue::Path(path.into())))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		error::{Error, ErrorValue, Result},
+		utility::crc32,
+	};
+
+	use super::{FileStream, Ironworks, ListEntry, Resource, TraceOutcome};
+
+	struct Listing(Vec<ListEntry>);
+
+	impl Resource for Listing {
+		fn version(&self, path: &str) -> Result<String> {
+			Err(Error::NotFound(ErrorValue::Path(path.into())))
+		}
+
+		fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+			Err(Error::NotFound(ErrorValue::Path(path.into())))
+		}
+
+		fn list(&self, _prefix: &str) -> Result<Vec<ListEntry>> {
+			Ok(self.0.clone())
+		}
+	}
+
+	struct Unlisted;
+
+	impl Resource for Unlisted {
+		fn version(&self, path: &str) -> Result<String> {
+			Err(Error::NotFound(ErrorValue::Path(path.into())))
+		}
+
+		fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+			Err(Error::NotFound(ErrorValue::Path(path.into())))
+		}
+	}
+
+	#[test]
+	fn list_merges_layers() {
+		let hashed = |name: &str| ListEntry::Hashed {
+			directory: "a/b".into(),
+			file_hash: crc32(name.as_bytes()),
+		};
+
+		let ironworks = Ironworks::new()
+			.with_resource(Listing(vec![hashed("one.bin"), hashed("two.bin")]))
+			.with_resource(Unlisted)
+			.with_resource(Listing(vec![ListEntry::Path("a/b/one.bin".into())]));
+
+		assert_eq!(
+			ironworks.list("a/b").unwrap(),
+			vec![ListEntry::Path("a/b/one.bin".into()), hashed("two.bin")]
+		);
+	}
+
+	#[cfg(all(feature = "loose", feature = "sqpack"))]
+	#[test]
+	fn list_merges_sqpack_and_directory() {
+		use std::fs;
+
+		use crate::{
+			loose::Directory,
+			sqpack::{FileKind, Install, PathDictionary, SqPack, Writer},
+			utility::TempDir,
+		};
+
+		let root = TempDir::new("list");
+		let loose = root.join("loose/chara/test");
+		fs::create_dir_all(&loose).unwrap();
+		fs::write(loose.join("file.bin"), b"loose").unwrap();
+
+		let mut writer = Writer::new();
+		for path in [
+			"chara/test/packed.bin",
+			"chara/deep/nested.bin",
+			"exd/root.exl",
+		] {
+			writer
+				.add_file(path, b"packed".to_vec(), FileKind::Standard)
+				.unwrap();
+		}
+		writer.write(&root.join("install/game/sqpack")).unwrap();
+
+		let mut dictionary = PathDictionary::new();
+		dictionary.add_path("chara/deep/nested.bin");
+		dictionary.add_path("exd/root.exl");
+
+		let ironworks = Ironworks::new()
+			.with_resource(
+				SqPack::new(Install::at(&root.join("install"))).with_dictionary(dictionary),
+			)
+			.with_resource(Directory::at(&root.join("loose")));
+
+		let hashed = |directory: &str, name: &str| ListEntry::Hashed {
+			directory: directory.into(),
+			file_hash: crc32(name.as_bytes()),
+		};
+		assert_eq!(
+			ironworks.list("chara/").unwrap(),
+			vec![
+				ListEntry::Path("chara/test/file.bin".into()),
+				hashed("chara/deep", "nested.bin")
+			]
+		);
+		assert_eq!(
+			ironworks.list("chara/test").unwrap(),
+			vec![
+				ListEntry::Path("chara/test/file.bin".into()),
+				hashed("chara/test", "packed.bin")
+			]
+		);
+		assert_eq!(
+			ironworks.list("exd/ro").unwrap(),
+			vec![hashed("exd", "root.exl")]
+		);
+	}
+
+	#[test]
+	fn list_unsupported() {
+		let ironworks = Ironworks::new().with_resource(Unlisted);
+		assert!(matches!(
+			ironworks.list("a/b"),
+			Err(Error::NotFound(ErrorValue::Path(_)))
+		));
+	}
+
+	struct Fixed(&'static str, &'static [u8]);
+
+	impl Resource for Fixed {
+		fn version(&self, _path: &str) -> Result<String> {
+			Ok(self.0.into())
+		}
+
+		fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+			match path {
+				"a/b/file.bin" => Ok(Box::new(std::io::Cursor::new(self.1))),
+				_ => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+			}
+		}
+
+		fn name(&self) -> String {
+			format!("fixed {}", self.0)
+		}
+	}
+
+	#[test]
+	fn file_with_source() {
+		let ironworks = Ironworks::new()
+			.with_resource(Fixed("base", b"base"))
+			.with_resource(Unlisted)
+			.with_resource(Fixed("override", b"override"));
+
+		let (file, source) = ironworks
+			.file_with_source::<Vec<u8>>("a/b/file.bin")
+			.unwrap();
+		assert_eq!(file, b"override");
+		assert_eq!(source.index(), 2);
+		assert_eq!(source.name(), "fixed override");
+		assert_eq!(source.version().as_deref(), Some("override"));
+
+		assert_eq!(ironworks.resolve("a/b/file.bin").unwrap().index(), 2);
+		assert!(ironworks.resolve("a/b/missing.bin").is_err());
+	}
+
+	#[test]
+	fn trace() {
+		let ironworks = Ironworks::new()
+			.with_resource(Fixed("base", b"base"))
+			.with_resource(Unlisted);
+
+		let steps = ironworks.trace("a/b/file.bin");
+		assert_eq!(steps.len(), 2);
+		assert_eq!(steps[0].index(), 1);
+		assert!(matches!(steps[0].outcome(), TraceOutcome::NotFound));
+		assert_eq!(steps[1].index(), 0);
+		assert!(matches!(steps[1].outcome(), TraceOutcome::Found));
+	}
 }
@@ -6,13 +6,22 @@
 // Doc config
 #![cfg_attr(docsrs, feature(doc_auto_cfg, doc_cfg))]
 
+#[cfg(feature = "async")]
+mod async_resource;
+mod cache;
 mod error;
 mod ironworks;
 mod utility;
+mod version;
 
 #[cfg(feature = "excel")]
 pub mod excel;
 pub mod file;
+#[cfg(feature = "loose")]
+pub mod loose;
+#[cfg(feature = "modpack")]
+pub mod modpack;
+pub mod path;
 #[cfg(feature = "sestring")]
 pub mod sestring;
 #[cfg(feature = "sqpack")]
@@ -21,10 +30,17 @@ pub mod sqpack;
 pub mod zipatch;
 
 pub use {
-	crate::ironworks::{FileStream, Ironworks, Resource},
+	crate::ironworks::{
+		FileStream, Ironworks, ListEntry, Resource, Source, TraceOutcome, TraceStep,
+	},
+	cache::{CacheStats, FileCache},
 	error::{Error, ErrorValue},
+	version::GameVersion,
 };
 
+#[cfg(feature = "async")]
+pub use async_resource::{AsyncResource, BlockingResource, BoxFuture};
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -34,6 +50,7 @@ mod test {
 		fn assert_send<T: Send>() {}
 		assert_send::<Ironworks>();
 		assert_send::<Error>();
+		assert_send::<FileCache>();
 		assert_send::<ErrorValue>();
 	}
 
@@ -42,6 +59,7 @@ mod test {
 		fn assert_sync<T: Sync>() {}
 		assert_sync::<Ironworks>();
 		assert_sync::<Error>();
+		assert_sync::<FileCache>();
 		assert_sync::<ErrorValue>();
 	}
 }
@@ -0,0 +1,251 @@
+use std::{
+	fs,
+	io::{self, BufReader},
+	path::{Path, PathBuf},
+};
+
+#[cfg(feature = "async")]
+use crate::async_resource::{AsyncResource, BoxFuture};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	ironworks::{FileStream, ListEntry, Resource},
+};
+
+/// Resource serving game paths from a directory of loose files on disk.
+///
+/// Paths are resolved relative to the directory root, following the same
+/// lower-casing rules as SqPack, i.e. `chara/equipment/e0001/model/c0101e0001_top.mdl`
+/// will be read from `<root>/chara/equipment/e0001/model/c0101e0001_top.mdl`.
+/// Registering a directory after another resource will cause any files it
+/// contains to override those provided by the earlier resource.
+#[derive(Debug)]
+pub struct Directory {
+	root: PathBuf,
+	version: Option<String>,
+}
+
+impl Directory {
+	/// Configure a resource instance with a directory of loose files at the specified path.
+	pub fn at(path: &Path) -> Self {
+		Self {
+			root: path.to_owned(),
+			version: None,
+		}
+	}
+
+	/// Set the version string reported for files within this directory. If no
+	/// version is set, version lookups will continue to the next resource.
+	pub fn with_version(mut self, version: impl Into<String>) -> Self {
+		self.set_version(version);
+		self
+	}
+
+	/// Set the version string reported for files within this directory. If no
+	/// version is set, version lookups will continue to the next resource.
+	pub fn set_version(&mut self, version: impl Into<String>) {
+		self.version = Some(version.into());
+	}
+
+	fn file_path(&self, path: &str) -> Result<PathBuf> {
+		// Game paths are always lower case.
+		let path = path.to_lowercase();
+
+		let mut file_path = self.root.clone();
+		for segment in path.split('/') {
+			// Refuse anything that could escape the root directory.
+			if matches!(segment, "" | "." | "..") || segment.contains('\\') {
+				return Err(Error::Invalid(
+					ErrorValue::Path(path.clone()),
+					format!("invalid path segment {segment:?}"),
+				));
+			}
+			file_path.push(segment);
+		}
+
+		Ok(file_path)
+	}
+
+	fn list_paths(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		let prefix = prefix.to_lowercase();
+
+		// Only the directory containing the prefix needs to be walked.
+		let directory = match prefix.rsplit_once('/') {
+			Some(("", _)) | None => self.root.clone(),
+			Some((directory, _)) => self.file_path(directory)?,
+		};
+
+		let mut entries = Vec::new();
+		let mut pending = vec![directory];
+		while let Some(directory) = pending.pop() {
+			let read_dir = match fs::read_dir(&directory) {
+				Ok(read_dir) => read_dir,
+				Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+				Err(error) => return Err(Error::Resource(error.into())),
+			};
+
+			for entry in read_dir {
+				let entry_path = entry?.path();
+				if entry_path.is_dir() {
+					pending.push(entry_path);
+					continue;
+				}
+
+				let Ok(relative) = entry_path.strip_prefix(&self.root) else {
+					continue;
+				};
+
+				let game_path = relative
+					.components()
+					.map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+					.collect::<Vec<_>>()
+					.join("/");
+
+				if game_path.starts_with(&prefix) {
+					entries.push(ListEntry::Path(game_path));
+				}
+			}
+		}
+
+		entries.sort();
+		Ok(entries)
+	}
+
+	fn version_for(&self, path: &str) -> Result<String> {
+		self.version
+			.clone()
+			.ok_or_else(|| Error::NotFound(ErrorValue::Path(path.into())))
+	}
+}
+
+impl Resource for Directory {
+	fn version(&self, path: &str) -> Result<String> {
+		match self.file_path(path)?.is_file() {
+			true => self.version_for(path),
+			false => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+		}
+	}
+
+	fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+		let file =
+			fs::File::open(self.file_path(path)?).map_err(|error| file_error(path, error))?;
+		Ok(Box::new(BufReader::new(file)))
+	}
+
+	fn exists(&self, path: &str) -> Result<bool> {
+		Ok(self.file_path(path)?.is_file())
+	}
+
+	fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		self.list_paths(prefix)
+	}
+
+	fn name(&self) -> String {
+		format!("loose directory {}", self.root.display())
+	}
+}
+
+#[cfg(feature = "async")]
+impl AsyncResource for Directory {
+	fn version<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<String>> {
+		Box::pin(async move {
+			match tokio::fs::metadata(self.file_path(path)?).await {
+				Ok(metadata) if metadata.is_file() => self.version_for(path),
+				_ => Err(Error::NotFound(ErrorValue::Path(path.into()))),
+			}
+		})
+	}
+
+	fn file<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+		Box::pin(async move {
+			tokio::fs::read(self.file_path(path)?)
+				.await
+				.map_err(|error| file_error(path, error))
+		})
+	}
+}
+
+fn file_error(path: &str, error: io::Error) -> Error {
+	match error.kind() {
+		io::ErrorKind::NotFound => Error::NotFound(ErrorValue::Path(path.into())),
+		_ => Error::Resource(error.into()),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, path::PathBuf};
+
+	use crate::{
+		error::{Error, ErrorValue},
+		ironworks::{Ironworks, ListEntry},
+		utility::TempDir,
+	};
+
+	use super::Directory;
+
+	fn fixture(name: &str) -> TempDir {
+		let root = TempDir::new(&format!("loose-{name}"));
+		let folder = root.join("chara").join("test");
+		fs::create_dir_all(&folder).unwrap();
+		fs::write(folder.join("file.bin"), b"loose").unwrap();
+		root
+	}
+
+	#[test]
+	fn reads_lowercased_path() {
+		let root = fixture("read");
+		let ironworks = Ironworks::new().with_resource(Directory::at(&root));
+		let file = ironworks.file::<Vec<u8>>("chara/Test/FILE.bin").unwrap();
+		assert_eq!(file, b"loose");
+	}
+
+	#[test]
+	fn missing_file() {
+		let root = fixture("missing");
+		let ironworks = Ironworks::new().with_resource(Directory::at(&root));
+		let file = ironworks.file::<Vec<u8>>("chara/test/missing.bin");
+		assert!(matches!(file, Err(Error::NotFound(ErrorValue::Path(_)))));
+	}
+
+	#[test]
+	fn rejects_traversal() {
+		let directory = Directory::at(&PathBuf::from("root"));
+		assert!(matches!(
+			directory.file_path("chara/../../secret"),
+			Err(Error::Invalid(..))
+		));
+	}
+
+	#[test]
+	fn configured_version() {
+		let root = fixture("version");
+		let ironworks =
+			Ironworks::new().with_resource(Directory::at(&root).with_version("2024.01.01"));
+		assert_eq!(
+			ironworks.version("chara/test/file.bin").unwrap(),
+			"2024.01.01"
+		);
+		assert!(ironworks.version("chara/test/missing.bin").is_err());
+	}
+
+	#[test]
+	fn exists_and_list() {
+		let root = fixture("list");
+		fs::write(root.join("chara").join("other.bin"), b"other").unwrap();
+		let ironworks = Ironworks::new().with_resource(Directory::at(&root));
+
+		assert!(ironworks.exists("chara/test/file.bin").unwrap());
+		assert!(!ironworks.exists("chara/test/missing.bin").unwrap());
+		assert_eq!(
+			ironworks.list("chara/").unwrap(),
+			vec![
+				ListEntry::Path("chara/other.bin".into()),
+				ListEntry::Path("chara/test/file.bin".into()),
+			]
+		);
+		assert_eq!(
+			ironworks.list("chara/te").unwrap(),
+			vec![ListEntry::Path("chara/test/file.bin".into())]
+		);
+	}
+}
@@ -0,0 +1,22 @@
+//! Resources for reading game data from loose files on disk.
+
+mod directory;
+
+pub use directory::Directory;
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_send() {
+		fn assert_send<T: Send>() {}
+		assert_send::<Directory>();
+	}
+
+	#[test]
+	fn test_sync() {
+		fn assert_sync<T: Sync>() {}
+		assert_sync::<Directory>();
+	}
+}
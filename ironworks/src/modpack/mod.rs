@@ -0,0 +1,28 @@
+//! Resources for reading game data out of Penumbra and TexTools mod packs.
+
+mod modpack;
+mod option;
+mod penumbra;
+mod textools;
+
+pub use {
+	modpack::{Format, ModPack},
+	option::{GroupKind, ModOption, OptionGroup},
+};
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_send() {
+		fn assert_send<T: Send>() {}
+		assert_send::<ModPack>();
+	}
+
+	#[test]
+	fn test_sync() {
+		fn assert_sync<T: Sync>() {}
+		assert_sync::<ModPack>();
+	}
+}
@@ -0,0 +1,575 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::{BufReader, Cursor, Read, Seek, SeekFrom},
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
+
+use derivative::Derivative;
+use serde::de::DeserializeOwned;
+use zip::{result::ZipError, CompressionMethod, ZipArchive};
+
+use crate::{
+	error::{Error, ErrorValue, Result},
+	ironworks::{FileStream, ListEntry, Resource},
+	sqpack,
+	utility::{OptionCache, OptionCacheExt},
+};
+
+use super::{
+	option::{FileSource, GroupKind, ModOption, OptionGroup},
+	penumbra, textools,
+};
+
+/// Format of a mod pack archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// Penumbra `.pmp` mod pack.
+	Penumbra,
+	/// TexTools `.ttmp2` mod pack.
+	TexTools,
+}
+
+/// Resource serving game paths redirected by a Penumbra or TexTools mod pack.
+///
+/// Mod packs are opened with their default options selected. Further options
+/// can be selected with [`ModPack::set_option`] before registering the mod pack
+/// as a resource.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct ModPack {
+	format: Format,
+	name: String,
+	author: String,
+	version: String,
+
+	default: ModOption,
+	groups: Vec<OptionGroup>,
+	selection: Vec<Vec<bool>>,
+
+	redirects: HashMap<String, FileSource>,
+	swaps: HashMap<String, String>,
+
+	#[derivative(Debug = "ignore")]
+	archive: Mutex<Archive>,
+	packed: Option<String>,
+	#[derivative(Debug = "ignore")]
+	packed_data: OptionCache<PackedData>,
+}
+
+impl ModPack {
+	/// Open the mod pack archive at the specified path. The format of the mod
+	/// pack is detected from the archive's contents.
+	pub fn open(path: &Path) -> Result<Self> {
+		let mut archive = Archive::open(path)?;
+
+		let manifest = if penumbra::is_penumbra(&archive) {
+			penumbra::read(&mut archive)?
+		} else if textools::is_textools(&archive) {
+			textools::read(&mut archive)?
+		} else {
+			return Err(Error::Invalid(
+				ErrorValue::Other(format!("mod pack {path:?}")),
+				"unrecognised mod pack format".into(),
+			));
+		};
+
+		let selection = manifest
+			.groups
+			.iter()
+			.map(|group| group.default_selection.clone())
+			.collect();
+
+		let mut mod_pack = Self {
+			format: manifest.format,
+			name: manifest.name,
+			author: manifest.author,
+			version: manifest.version,
+
+			default: manifest.default,
+			groups: manifest.groups,
+			selection,
+
+			redirects: Default::default(),
+			swaps: Default::default(),
+
+			archive: archive.into(),
+			packed: manifest.packed,
+			packed_data: Default::default(),
+		};
+		mod_pack.resolve();
+
+		Ok(mod_pack)
+	}
+
+	/// Format of the mod pack.
+	pub fn format(&self) -> Format {
+		self.format
+	}
+
+	/// Name of the mod pack.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Author of the mod pack.
+	pub fn author(&self) -> &str {
+		&self.author
+	}
+
+	/// Version of the mod pack, as specified by its author. This is unrelated to
+	/// the game version, which mod packs do not record.
+	pub fn mod_version(&self) -> &str {
+		&self.version
+	}
+
+	/// Files that are always applied by the mod pack, regardless of selected options.
+	pub fn default_option(&self) -> &ModOption {
+		&self.default
+	}
+
+	/// Option groups available in the mod pack.
+	pub fn groups(&self) -> &[OptionGroup] {
+		&self.groups
+	}
+
+	/// Select an option within a group. Selecting an option in a single-select
+	/// group will deselect any other option in that group.
+	pub fn with_option(mut self, group: &str, option: &str) -> Result<Self> {
+		self.set_option(group, option)?;
+		Ok(self)
+	}
+
+	/// Select an option within a group. Selecting an option in a single-select
+	/// group will deselect any other option in that group.
+	pub fn set_option(&mut self, group: &str, option: &str) -> Result<()> {
+		let group_index = self.group_index(group)?;
+		let group = &self.groups[group_index];
+
+		let option_index = group
+			.options
+			.iter()
+			.position(|candidate| candidate.name == option)
+			.ok_or_else(|| {
+				Error::NotFound(ErrorValue::Other(format!(
+					"mod pack option {:?}/{option:?}",
+					group.name
+				)))
+			})?;
+
+		let selection = &mut self.selection[group_index];
+		if group.kind == GroupKind::Single {
+			selection.fill(false);
+		}
+		selection[option_index] = true;
+
+		self.resolve();
+		Ok(())
+	}
+
+	/// Deselect all options within a group.
+	pub fn clear_group(&mut self, group: &str) -> Result<()> {
+		let group_index = self.group_index(group)?;
+		self.selection[group_index].fill(false);
+		self.resolve();
+		Ok(())
+	}
+
+	fn group_index(&self, group: &str) -> Result<usize> {
+		self.groups
+			.iter()
+			.position(|candidate| candidate.name == group)
+			.ok_or_else(|| Error::NotFound(ErrorValue::Other(format!("mod pack group {group:?}"))))
+	}
+
+	fn resolve(&mut self) {
+		let mut redirects = self.default.files.clone();
+		let mut swaps = self.default.swaps.clone();
+
+		// Apply groups in ascending priority, such that higher priorities take precedence.
+		let mut group_order = (0..self.groups.len()).collect::<Vec<_>>();
+		group_order.sort_by_key(|&index| self.groups[index].priority);
+
+		for group_index in group_order {
+			let group = &self.groups[group_index];
+			let mut options = group
+				.options
+				.iter()
+				.zip(&self.selection[group_index])
+				.filter_map(|(option, selected)| selected.then_some(option))
+				.collect::<Vec<_>>();
+			options.sort_by_key(|option| option.priority);
+
+			for option in options {
+				redirects.extend(option.files.clone());
+				swaps.extend(option.swaps.clone());
+			}
+		}
+
+		self.redirects = redirects;
+		self.swaps = swaps;
+	}
+
+	fn source(&self, path: &str) -> Option<&FileSource> {
+		// File swaps can only be resolved against files provided by this mod pack.
+		self.redirects.get(path).or_else(|| {
+			self.swaps
+				.get(path)
+				.and_then(|target| self.redirects.get(target))
+		})
+	}
+
+	fn read_source(&self, source: &FileSource) -> Result<Box<dyn FileStream>> {
+		match source {
+			FileSource::Archive(name) => {
+				let buffer = self.archive.lock().unwrap().read(name)?;
+				Ok(Box::new(Cursor::new(buffer)))
+			}
+
+			FileSource::Packed { offset, size } => {
+				let buffer = self.read_packed(*offset, *size)?;
+				Ok(Box::new(sqpack::File::new(Cursor::new(buffer))?))
+			}
+		}
+	}
+
+	fn read_packed(&self, offset: u64, size: u64) -> Result<Vec<u8>> {
+		let packed_data = self.packed_data.try_get_or_insert(|| {
+			let name = self.packed.as_deref().ok_or_else(|| {
+				Error::Invalid(
+					ErrorValue::Other(format!("mod pack {:?}", self.name)),
+					"missing packed data".into(),
+				)
+			})?;
+
+			// Stored (uncompressed) data can be read directly out of the archive
+			// file, avoiding holding potentially large blobs in memory.
+			let mut archive = self.archive.lock().unwrap();
+			match archive.stored_offset(name)? {
+				Some(data_start) => Ok(PackedData::Stored {
+					path: archive.path.clone(),
+					data_start,
+				}),
+				None => archive.read(name).map(PackedData::Loaded),
+			}
+		})?;
+
+		// Offsets and sizes come from the manifest, and can't be trusted.
+		let invalid = || {
+			Error::Invalid(
+				ErrorValue::Other(format!("mod pack {:?}", self.name)),
+				format!("packed data range {offset}+{size} is out of bounds"),
+			)
+		};
+		let end = offset.checked_add(size).ok_or_else(invalid)?;
+
+		// Data is read through a bounded reader rather than pre-allocated, so
+		// oversized entries fail when the data runs out.
+		let mut buffer = Vec::new();
+		match &*packed_data {
+			PackedData::Stored { path, data_start } => {
+				let start = data_start.checked_add(offset).ok_or_else(invalid)?;
+				let mut file = fs::File::open(path)?;
+				file.seek(SeekFrom::Start(start))?;
+				file.take(size).read_to_end(&mut buffer)?;
+			}
+			PackedData::Loaded(data) => {
+				let clamp = |value: u64| {
+					usize::try_from(value).map_or(data.len(), |value| value.min(data.len()))
+				};
+				buffer.extend_from_slice(&data[clamp(offset)..clamp(end)]);
+			}
+		}
+
+		if u64::try_from(buffer.len()).unwrap() != size {
+			return Err(Error::Resource(
+				format!(
+					"packed mod data truncated: expected {size} bytes, got {}",
+					buffer.len()
+				)
+				.into(),
+			));
+		}
+
+		Ok(buffer)
+	}
+}
+
+impl Resource for ModPack {
+	fn version(&self, path: &str) -> Result<String> {
+		// Mod packs only record the author's version of the pack, not the version
+		// of the game data they target.
+		Err(Error::NotFound(ErrorValue::Path(path.into())))
+	}
+
+	fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
+		let source = self
+			.source(&path.to_lowercase())
+			.ok_or_else(|| Error::NotFound(ErrorValue::Path(path.into())))?;
+		self.read_source(source)
+	}
+
+	fn exists(&self, path: &str) -> Result<bool> {
+		Ok(self.source(&path.to_lowercase()).is_some())
+	}
+
+	fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		let prefix = prefix.to_lowercase();
+
+		let mut paths = self
+			.redirects
+			.keys()
+			.chain(
+				self.swaps
+					.iter()
+					.filter(|(_, target)| self.redirects.contains_key(*target))
+					.map(|(path, _)| path),
+			)
+			.filter(|path| path.starts_with(&prefix))
+			.cloned()
+			.collect::<Vec<_>>();
+		paths.sort();
+		paths.dedup();
+
+		Ok(paths.into_iter().map(ListEntry::Path).collect())
+	}
+
+	fn name(&self) -> String {
+		format!("mod pack {:?}", self.name)
+	}
+}
+
+pub struct Manifest {
+	pub format: Format,
+	pub name: String,
+	pub author: String,
+	pub version: String,
+	pub default: ModOption,
+	pub groups: Vec<OptionGroup>,
+	pub packed: Option<String>,
+}
+
+enum PackedData {
+	Stored { path: PathBuf, data_start: u64 },
+	Loaded(Vec<u8>),
+}
+
+/// Zip archive with case- and separator-insensitive entry lookup.
+pub struct Archive {
+	path: PathBuf,
+	zip: ZipArchive<BufReader<fs::File>>,
+	entries: HashMap<String, String>,
+}
+
+impl Archive {
+	fn open(path: &Path) -> Result<Self> {
+		let zip = ZipArchive::new(BufReader::new(fs::File::open(path)?)).map_err(zip_error)?;
+		let entries = zip
+			.file_names()
+			.map(|name| (normalize(name), name.to_string()))
+			.collect();
+
+		Ok(Self {
+			path: path.to_owned(),
+			zip,
+			entries,
+		})
+	}
+
+	/// Get the real name of an entry in the archive, if it exists.
+	pub fn entry(&self, name: &str) -> Option<&str> {
+		self.entries.get(&normalize(name)).map(String::as_str)
+	}
+
+	/// Iterate over the normalized names of all entries in the archive.
+	pub fn entries(&self) -> impl Iterator<Item = &str> {
+		self.entries.keys().map(String::as_str)
+	}
+
+	fn read(&mut self, name: &str) -> Result<Vec<u8>> {
+		let real_name = self.entry(name).unwrap_or(name).to_string();
+		let mut file = self.zip.by_name(&real_name).map_err(zip_error)?;
+		// Entry sizes are read from the archive and may be bogus, avoid pre-allocating.
+		let size = file.size();
+		let mut buffer = Vec::new();
+		(&mut file).take(size).read_to_end(&mut buffer)?;
+		Ok(buffer)
+	}
+
+	fn stored_offset(&mut self, name: &str) -> Result<Option<u64>> {
+		let real_name = self.entry(name).unwrap_or(name).to_string();
+		let file = self.zip.by_name(&real_name).map_err(zip_error)?;
+		Ok(match file.compression() {
+			CompressionMethod::Stored => Some(file.data_start()),
+			_ => None,
+		})
+	}
+}
+
+pub fn read_json<T: DeserializeOwned>(archive: &mut Archive, name: &str) -> Result<T> {
+	let buffer = archive.read(name)?;
+	// Manifests are frequently written with a byte order mark.
+	let json = buffer.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&buffer);
+	serde_json::from_slice(json).map_err(|error| {
+		Error::Invalid(
+			ErrorValue::Other(format!("mod pack manifest {name:?}")),
+			error.to_string(),
+		)
+	})
+}
+
+fn normalize(name: &str) -> String {
+	name.replace('\\', "/").to_lowercase()
+}
+
+fn zip_error(error: ZipError) -> Error {
+	match error {
+		ZipError::Io(error) => error.into(),
+		ZipError::FileNotFound => {
+			Error::NotFound(ErrorValue::Other("mod pack archive entry".into()))
+		}
+		other => Error::Resource(other.into()),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		fs,
+		io::{Cursor, Write},
+		path::{Path, PathBuf},
+	};
+
+	use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+	use crate::{ironworks::Ironworks, utility::TempDir};
+
+	use super::{Format, ModPack};
+
+	fn write_archive(
+		directory: &Path,
+		name: &str,
+		entries: &[(&str, &[u8])],
+		method: CompressionMethod,
+	) -> PathBuf {
+		let path = directory.join(name);
+		let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+		for (entry, data) in entries {
+			writer
+				.start_file(*entry, FileOptions::default().compression_method(method))
+				.unwrap();
+			writer.write_all(data).unwrap();
+		}
+		fs::write(&path, writer.finish().unwrap().into_inner()).unwrap();
+		path
+	}
+
+	// Minimal SqPack standard file containing a single uncompressed block.
+	fn sqpack_file(data: &[u8]) -> Vec<u8> {
+		let size = u32::try_from(data.len()).unwrap();
+		let mut file = Vec::new();
+		for value in [128u32, 2, size, 0, 0, 1] {
+			file.extend_from_slice(&value.to_le_bytes());
+		}
+		file.extend_from_slice(&0u32.to_le_bytes());
+		file.extend_from_slice(&u16::try_from(size + 16).unwrap().to_le_bytes());
+		file.extend_from_slice(&u16::try_from(size).unwrap().to_le_bytes());
+		file.resize(128, 0);
+		for value in [16u32, 0, 32_000, size] {
+			file.extend_from_slice(&value.to_le_bytes());
+		}
+		file.extend_from_slice(data);
+		file
+	}
+
+	#[test]
+	fn penumbra() {
+		let root = TempDir::new("penumbra");
+		let path = write_archive(
+			&root,
+			"penumbra.pmp",
+			&[
+				("meta.json", br#"{"Name":"Test","Version":"1.0"}"#),
+				(
+					"default_mod.json",
+					br#"{"Files":{"chara/a.tex":"files\\a.tex"},"FileSwaps":{"chara/c.tex":"chara/a.tex"}}"#,
+				),
+				(
+					"group_001_colour.json",
+					br#"{"Name":"Colour","Type":"Single","DefaultSettings":0,"Options":[
+						{"Name":"Red","Files":{"chara/b.tex":"red\\b.tex"}},
+						{"Name":"Blue","Files":{"chara/b.tex":"blue\\b.tex"}}
+					]}"#,
+				),
+				("files/a.tex", b"a"),
+				("red/b.tex", b"red"),
+				("blue/b.tex", b"blue"),
+			],
+			CompressionMethod::Deflated,
+		);
+
+		let mod_pack = ModPack::open(&path).unwrap();
+		assert_eq!(mod_pack.format(), Format::Penumbra);
+		assert_eq!(mod_pack.groups().len(), 1);
+		assert_eq!(mod_pack.mod_version(), "1.0");
+
+		let ironworks = Ironworks::new().with_resource(mod_pack);
+		assert_eq!(ironworks.file::<Vec<u8>>("chara/a.tex").unwrap(), b"a");
+		assert_eq!(ironworks.file::<Vec<u8>>("chara/b.tex").unwrap(), b"red");
+		assert_eq!(ironworks.file::<Vec<u8>>("chara/c.tex").unwrap(), b"a");
+		assert!(ironworks.version("chara/a.tex").is_err());
+		assert!(ironworks.file::<Vec<u8>>("chara/d.tex").is_err());
+
+		let mod_pack = ModPack::open(&path)
+			.unwrap()
+			.with_option("Colour", "Blue")
+			.unwrap();
+		let ironworks = Ironworks::new().with_resource(mod_pack);
+		assert_eq!(ironworks.file::<Vec<u8>>("chara/b.tex").unwrap(), b"blue");
+	}
+
+	#[test]
+	fn textools() {
+		let first = sqpack_file(b"first");
+		let second = sqpack_file(b"second");
+		let mut packed = first.clone();
+		packed.extend_from_slice(&second);
+
+		let manifest = format!(
+			r#"{{"Name":"Test","ModPackPages":[{{"ModGroups":[{{
+				"GroupName":"Group","SelectionType":"Multi","OptionList":[
+					{{"Name":"One","IsChecked":true,"ModsJsons":[
+						{{"FullPath":"chara/a.tex","ModOffset":0,"ModSize":{}}},
+						{{"FullPath":"chara/c.tex","ModOffset":{},"ModSize":{}}}
+					]}},
+					{{"Name":"Two","IsChecked":false,"ModsJsons":[{{"FullPath":"chara/b.tex","ModOffset":{},"ModSize":{}}}]}}
+				]
+			}}]}}]}}"#,
+			first.len(),
+			u64::MAX,
+			u64::MAX,
+			first.len(),
+			second.len()
+		);
+
+		let root = TempDir::new("textools");
+		for method in [CompressionMethod::Stored, CompressionMethod::Deflated] {
+			let path = write_archive(
+				&root,
+				&format!("textools-{method:?}.ttmp2"),
+				&[("TTMPL.mpl", manifest.as_bytes()), ("TTMPD.mpd", &packed)],
+				method,
+			);
+
+			let mod_pack = ModPack::open(&path).unwrap();
+			assert_eq!(mod_pack.format(), Format::TexTools);
+
+			let ironworks =
+				Ironworks::new().with_resource(mod_pack.with_option("Group", "Two").unwrap());
+			assert_eq!(ironworks.file::<Vec<u8>>("chara/a.tex").unwrap(), b"first");
+			assert_eq!(ironworks.file::<Vec<u8>>("chara/b.tex").unwrap(), b"second");
+			assert!(ironworks.file::<Vec<u8>>("chara/c.tex").is_err());
+		}
+	}
+}
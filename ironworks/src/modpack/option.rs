@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use getset::{CopyGetters, Getters};
+
+/// A group of options within a mod pack, from which one or more options may be selected.
+#[derive(Debug, Getters, CopyGetters)]
+pub struct OptionGroup {
+	/// Name of the group.
+	#[get = "pub"]
+	pub(super) name: String,
+
+	/// Selection behavior of the group.
+	#[get_copy = "pub"]
+	pub(super) kind: GroupKind,
+
+	/// Priority of the group. Files provided by groups with a higher priority
+	/// take precedence over those provided by lower priority groups.
+	#[get_copy = "pub"]
+	pub(super) priority: i32,
+
+	/// Options available for selection within this group.
+	#[get = "pub"]
+	pub(super) options: Vec<ModOption>,
+
+	pub(super) default_selection: Vec<bool>,
+}
+
+/// Selection behavior of an option group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupKind {
+	/// Exactly one option may be selected at a time.
+	Single,
+	/// Any number of options may be selected at once.
+	Multi,
+}
+
+/// A single selectable option within a mod pack.
+#[derive(Debug, Default, Getters, CopyGetters)]
+pub struct ModOption {
+	/// Name of the option.
+	#[get = "pub"]
+	pub(super) name: String,
+
+	/// Description of the option, as provided by the mod author.
+	#[get = "pub"]
+	pub(super) description: String,
+
+	/// Priority of the option within its group. Only meaningful for multi-select groups.
+	#[get_copy = "pub"]
+	pub(super) priority: i32,
+
+	pub(super) files: HashMap<String, FileSource>,
+	pub(super) swaps: HashMap<String, String>,
+}
+
+impl ModOption {
+	/// Iterate over the game paths this option provides file data for.
+	pub fn files(&self) -> impl Iterator<Item = &str> {
+		self.files.keys().map(String::as_str)
+	}
+
+	/// Iterate over the file swaps declared by this option, as pairs of
+	/// `(game path, target game path)`.
+	pub fn file_swaps(&self) -> impl Iterator<Item = (&str, &str)> {
+		self.swaps
+			.iter()
+			.map(|(path, target)| (path.as_str(), target.as_str()))
+	}
+}
+
+/// Location of the data for a redirected file within the mod pack archive.
+#[derive(Debug, Clone)]
+pub enum FileSource {
+	/// Raw file stored as an entry in the archive.
+	Archive(String),
+	/// SqPack-encoded file stored within the archive's packed data blob.
+	Packed { offset: u64, size: u64 },
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::error::Result;
+
+use super::{
+	modpack::{read_json, Archive, Format, Manifest},
+	option::{FileSource, GroupKind, ModOption, OptionGroup},
+};
+
+const META: &str = "meta.json";
+const DEFAULT_MOD: &str = "default_mod.json";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Meta {
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	author: String,
+	#[serde(default)]
+	version: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Group {
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	priority: i32,
+	#[serde(rename = "Type", default)]
+	kind: String,
+	#[serde(default)]
+	default_settings: u64,
+	#[serde(default)]
+	options: Vec<OptionData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OptionData {
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	description: Option<String>,
+	#[serde(default)]
+	priority: i32,
+	#[serde(default)]
+	files: HashMap<String, String>,
+	#[serde(default)]
+	file_swaps: HashMap<String, String>,
+}
+
+pub fn is_penumbra(archive: &Archive) -> bool {
+	archive.entry(META).is_some()
+}
+
+pub fn read(archive: &mut Archive) -> Result<Manifest> {
+	let meta = read_json::<Meta>(archive, META)?;
+
+	let default = match archive.entry(DEFAULT_MOD) {
+		Some(_) => {
+			let option = read_json::<OptionData>(archive, DEFAULT_MOD)?;
+			build_option(archive, option)
+		}
+		None => ModOption::default(),
+	};
+
+	// Group files are named `group_NNN_name.json`, and are ordered by that number.
+	let mut group_names = archive
+		.entries()
+		.filter(|name| name.starts_with("group_") && name.ends_with(".json"))
+		.map(str::to_string)
+		.collect::<Vec<_>>();
+	group_names.sort();
+
+	let groups = group_names
+		.iter()
+		.map(|name| -> Result<_> {
+			let group = read_json::<Group>(archive, name)?;
+			Ok(build_group(archive, group))
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	Ok(Manifest {
+		format: Format::Penumbra,
+		name: meta.name,
+		author: meta.author,
+		version: meta.version,
+		default,
+		groups,
+		packed: None,
+	})
+}
+
+fn build_group(archive: &Archive, group: Group) -> OptionGroup {
+	// Penumbra has a few specialised group kinds - the ones that carry files
+	// behave like multi-select groups for our purposes.
+	let kind = match group.kind.as_str() {
+		"Single" => GroupKind::Single,
+		_ => GroupKind::Multi,
+	};
+
+	// Default settings are an index for single-select groups, and a bitmask for multi.
+	let default_selection = (0..group.options.len())
+		.map(|index| match kind {
+			GroupKind::Single => group.default_settings == index as u64,
+			GroupKind::Multi => index < 64 && (group.default_settings >> index) & 1 == 1,
+		})
+		.collect();
+
+	OptionGroup {
+		name: group.name,
+		kind,
+		priority: group.priority,
+		options: group
+			.options
+			.into_iter()
+			.map(|option| build_option(archive, option))
+			.collect(),
+		default_selection,
+	}
+}
+
+fn build_option(archive: &Archive, option: OptionData) -> ModOption {
+	let files = option
+		.files
+		.into_iter()
+		.map(|(path, file)| {
+			let entry = archive
+				.entry(&file)
+				.map(str::to_string)
+				.unwrap_or_else(|| file.replace('\\', "/"));
+			(path.to_lowercase(), FileSource::Archive(entry))
+		})
+		.collect();
+
+	let swaps = option
+		.file_swaps
+		.into_iter()
+		.map(|(path, target)| (path.to_lowercase(), target.to_lowercase()))
+		.collect();
+
+	ModOption {
+		name: option.name,
+		description: option.description.unwrap_or_default(),
+		priority: option.priority,
+		files,
+		swaps,
+	}
+}
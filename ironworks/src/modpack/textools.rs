@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::error::Result;
+
+use super::{
+	modpack::{read_json, Archive, Format, Manifest},
+	option::{FileSource, GroupKind, ModOption, OptionGroup},
+};
+
+const MANIFEST: &str = "TTMPL.mpl";
+const PACKED_DATA: &str = "TTMPD.mpd";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TexToolsManifest {
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	author: String,
+	#[serde(default)]
+	version: String,
+	#[serde(default)]
+	mod_pack_pages: Option<Vec<Page>>,
+	#[serde(default)]
+	simple_mods_list: Option<Vec<ModEntry>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Page {
+	#[serde(default)]
+	mod_groups: Vec<Group>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Group {
+	#[serde(default)]
+	group_name: String,
+	#[serde(default)]
+	selection_type: String,
+	#[serde(default)]
+	option_list: Vec<OptionData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OptionData {
+	#[serde(default)]
+	name: String,
+	#[serde(default)]
+	description: Option<String>,
+	#[serde(default)]
+	mods_jsons: Vec<ModEntry>,
+	#[serde(default)]
+	is_checked: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ModEntry {
+	full_path: String,
+	mod_offset: u64,
+	mod_size: u64,
+}
+
+pub fn is_textools(archive: &Archive) -> bool {
+	archive.entry(MANIFEST).is_some()
+}
+
+pub fn read(archive: &mut Archive) -> Result<Manifest> {
+	let manifest = read_json::<TexToolsManifest>(archive, MANIFEST)?;
+
+	// Simple mod packs have no options, and are represented as the default option.
+	let default = ModOption {
+		files: build_files(manifest.simple_mods_list.unwrap_or_default()),
+		..Default::default()
+	};
+
+	// Wizard mod packs split their groups across pages, which have no meaning for us.
+	let groups = manifest
+		.mod_pack_pages
+		.unwrap_or_default()
+		.into_iter()
+		.flat_map(|page| page.mod_groups)
+		.map(build_group)
+		.collect();
+
+	Ok(Manifest {
+		format: Format::TexTools,
+		name: manifest.name,
+		author: manifest.author,
+		version: manifest.version,
+		default,
+		groups,
+		packed: archive.entry(PACKED_DATA).map(str::to_string),
+	})
+}
+
+fn build_group(group: Group) -> OptionGroup {
+	let kind = match group.selection_type.as_str() {
+		"Multi" => GroupKind::Multi,
+		_ => GroupKind::Single,
+	};
+
+	let mut default_selection = group
+		.option_list
+		.iter()
+		.map(|option| option.is_checked)
+		.collect::<Vec<_>>();
+
+	// TexTools selects the first option of a single-select group if none are checked.
+	if kind == GroupKind::Single && !default_selection.contains(&true) {
+		if let Some(first) = default_selection.first_mut() {
+			*first = true;
+		}
+	}
+
+	OptionGroup {
+		name: group.group_name,
+		kind,
+		priority: 0,
+		options: group
+			.option_list
+			.into_iter()
+			.map(|option| ModOption {
+				name: option.name,
+				description: option.description.unwrap_or_default(),
+				priority: 0,
+				files: build_files(option.mods_jsons),
+				swaps: Default::default(),
+			})
+			.collect(),
+		default_selection,
+	}
+}
+
+fn build_files(entries: Vec<ModEntry>) -> HashMap<String, FileSource> {
+	entries
+		.into_iter()
+		.map(|entry| {
+			(
+				entry.full_path.to_lowercase(),
+				FileSource::Packed {
+					offset: entry.mod_offset,
+					size: entry.mod_size,
+				},
+			)
+		})
+		.collect()
+}
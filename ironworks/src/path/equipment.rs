@@ -0,0 +1,476 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+
+use super::parser::{parse, Parser};
+
+/// Slot of a piece of equipment, as used in equipment and demihuman paths.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EquipmentSlot {
+	Head,
+	Body,
+	Hands,
+	Legs,
+	Feet,
+}
+
+impl EquipmentSlot {
+	const SUFFIXES: &'static [(&'static str, Self)] = &[
+		("met", Self::Head),
+		("top", Self::Body),
+		("glv", Self::Hands),
+		("dwn", Self::Legs),
+		("sho", Self::Feet),
+	];
+
+	/// Suffix used for the slot in file names, i.e. `top` for [`EquipmentSlot::Body`].
+	pub fn suffix(&self) -> &'static str {
+		suffix(Self::SUFFIXES, *self)
+	}
+}
+
+/// Slot of an accessory.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessorySlot {
+	Ears,
+	Neck,
+	Wrists,
+	RightRing,
+	LeftRing,
+}
+
+impl AccessorySlot {
+	const SUFFIXES: &'static [(&'static str, Self)] = &[
+		("ear", Self::Ears),
+		("nek", Self::Neck),
+		("wrs", Self::Wrists),
+		("rir", Self::RightRing),
+		("ril", Self::LeftRing),
+	];
+
+	/// Suffix used for the slot in file names, i.e. `rir` for [`AccessorySlot::RightRing`].
+	pub fn suffix(&self) -> &'static str {
+		suffix(Self::SUFFIXES, *self)
+	}
+}
+
+fn suffix<T: PartialEq>(suffixes: &[(&'static str, T)], slot: T) -> &'static str {
+	suffixes
+		.iter()
+		.find(|(_, value)| *value == slot)
+		.map(|(suffix, _)| *suffix)
+		.unwrap()
+}
+
+/// Path to a file belonging to a piece of equipment, i.e.
+/// `chara/equipment/e0001/model/c0101e0001_top.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Equipment {
+	/// Model of the equipment for a race.
+	Model {
+		/// Equipment set ID.
+		set: u16,
+		/// Race code the model is built for, i.e. `101` for `c0101`.
+		race: u16,
+		/// Slot of the equipment.
+		slot: EquipmentSlot,
+	},
+
+	/// Material used by the equipment's model.
+	Material {
+		/// Equipment set ID.
+		set: u16,
+		/// Race code the material is built for.
+		race: u16,
+		/// Slot of the equipment.
+		slot: EquipmentSlot,
+		/// Material variant ID.
+		variant: u16,
+		/// Material name suffix, i.e. `a`.
+		suffix: String,
+	},
+
+	/// Image change data for the equipment set.
+	Imc {
+		/// Equipment set ID.
+		set: u16,
+	},
+}
+
+impl fmt::Display for Equipment {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Model { set, race, slot } => write!(
+				formatter,
+				"chara/equipment/e{set:04}/model/c{race:04}e{set:04}_{}.mdl",
+				slot.suffix()
+			),
+			Self::Material {
+				set,
+				race,
+				slot,
+				variant,
+				suffix,
+			} => write!(
+				formatter,
+				"chara/equipment/e{set:04}/material/v{variant:04}/mt_c{race:04}e{set:04}_{}_{suffix}.mtrl",
+				slot.suffix()
+			),
+			Self::Imc { set } => write!(formatter, "chara/equipment/e{set:04}/e{set:04}.imc"),
+		}
+	}
+}
+
+impl FromStr for Equipment {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "equipment", |parser| {
+			parser.tag("chara/equipment/e")?;
+			let set = parser.number(4)?;
+			parser.tag("/")?;
+
+			if parser.peek("model/") {
+				parser.tag("model/c")?;
+				let race = parser.number(4)?;
+				parser.tag("e")?;
+				set_id(parser, set)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(EquipmentSlot::SUFFIXES)?;
+				parser.tag(".mdl")?;
+				return Some(Self::Model { set, race, slot });
+			}
+
+			if parser.peek("material/") {
+				parser.tag("material/v")?;
+				let variant = parser.number(4)?;
+				parser.tag("/mt_c")?;
+				let race = parser.number(4)?;
+				parser.tag("e")?;
+				set_id(parser, set)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(EquipmentSlot::SUFFIXES)?;
+				parser.tag("_")?;
+				let suffix = parser.until(".mtrl")?.into();
+				parser.tag(".mtrl")?;
+				return Some(Self::Material {
+					set,
+					race,
+					slot,
+					variant,
+					suffix,
+				});
+			}
+
+			parser.tag("e")?;
+			set_id(parser, set)?;
+			parser.tag(".imc")?;
+			Some(Self::Imc { set })
+		})
+	}
+}
+
+/// Path to a file belonging to an accessory, i.e.
+/// `chara/accessory/a0001/model/c0101a0001_ear.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Accessory {
+	/// Model of the accessory for a race.
+	Model {
+		/// Accessory set ID.
+		set: u16,
+		/// Race code the model is built for, i.e. `101` for `c0101`.
+		race: u16,
+		/// Slot of the accessory.
+		slot: AccessorySlot,
+	},
+
+	/// Material used by the accessory's model.
+	Material {
+		/// Accessory set ID.
+		set: u16,
+		/// Race code the material is built for.
+		race: u16,
+		/// Slot of the accessory.
+		slot: AccessorySlot,
+		/// Material variant ID.
+		variant: u16,
+		/// Material name suffix, i.e. `a`.
+		suffix: String,
+	},
+
+	/// Image change data for the accessory set.
+	Imc {
+		/// Accessory set ID.
+		set: u16,
+	},
+}
+
+impl fmt::Display for Accessory {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Model { set, race, slot } => write!(
+				formatter,
+				"chara/accessory/a{set:04}/model/c{race:04}a{set:04}_{}.mdl",
+				slot.suffix()
+			),
+			Self::Material {
+				set,
+				race,
+				slot,
+				variant,
+				suffix,
+			} => write!(
+				formatter,
+				"chara/accessory/a{set:04}/material/v{variant:04}/mt_c{race:04}a{set:04}_{}_{suffix}.mtrl",
+				slot.suffix()
+			),
+			Self::Imc { set } => write!(formatter, "chara/accessory/a{set:04}/a{set:04}.imc"),
+		}
+	}
+}
+
+impl FromStr for Accessory {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "accessory", |parser| {
+			parser.tag("chara/accessory/a")?;
+			let set = parser.number(4)?;
+			parser.tag("/")?;
+
+			if parser.peek("model/") {
+				parser.tag("model/c")?;
+				let race = parser.number(4)?;
+				parser.tag("a")?;
+				set_id(parser, set)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(AccessorySlot::SUFFIXES)?;
+				parser.tag(".mdl")?;
+				return Some(Self::Model { set, race, slot });
+			}
+
+			if parser.peek("material/") {
+				parser.tag("material/v")?;
+				let variant = parser.number(4)?;
+				parser.tag("/mt_c")?;
+				let race = parser.number(4)?;
+				parser.tag("a")?;
+				set_id(parser, set)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(AccessorySlot::SUFFIXES)?;
+				parser.tag("_")?;
+				let suffix = parser.until(".mtrl")?.into();
+				parser.tag(".mtrl")?;
+				return Some(Self::Material {
+					set,
+					race,
+					slot,
+					variant,
+					suffix,
+				});
+			}
+
+			parser.tag("a")?;
+			set_id(parser, set)?;
+			parser.tag(".imc")?;
+			Some(Self::Imc { set })
+		})
+	}
+}
+
+/// Path to a file belonging to a demihuman's equipment, i.e.
+/// `chara/demihuman/d1001/obj/equipment/e0001/model/d1001e0001_top.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Demihuman {
+	/// Model of a piece of the demihuman's equipment.
+	Model {
+		/// Demihuman ID.
+		id: u16,
+		/// Equipment set ID.
+		equipment: u16,
+		/// Slot of the equipment.
+		slot: EquipmentSlot,
+	},
+
+	/// Material used by a piece of the demihuman's equipment.
+	Material {
+		/// Demihuman ID.
+		id: u16,
+		/// Equipment set ID.
+		equipment: u16,
+		/// Slot of the equipment.
+		slot: EquipmentSlot,
+		/// Material variant ID.
+		variant: u16,
+		/// Material name suffix, i.e. `a`.
+		suffix: String,
+	},
+
+	/// Image change data for the demihuman's equipment set.
+	Imc {
+		/// Demihuman ID.
+		id: u16,
+		/// Equipment set ID.
+		equipment: u16,
+	},
+}
+
+impl fmt::Display for Demihuman {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Model {
+				id,
+				equipment,
+				slot,
+			} => write!(
+				formatter,
+				"chara/demihuman/d{id:04}/obj/equipment/e{equipment:04}/model/d{id:04}e{equipment:04}_{}.mdl",
+				slot.suffix()
+			),
+			Self::Material {
+				id,
+				equipment,
+				slot,
+				variant,
+				suffix,
+			} => write!(
+				formatter,
+				"chara/demihuman/d{id:04}/obj/equipment/e{equipment:04}/material/v{variant:04}/mt_d{id:04}e{equipment:04}_{}_{suffix}.mtrl",
+				slot.suffix()
+			),
+			Self::Imc { id, equipment } => write!(
+				formatter,
+				"chara/demihuman/d{id:04}/obj/equipment/e{equipment:04}/e{equipment:04}.imc"
+			),
+		}
+	}
+}
+
+impl FromStr for Demihuman {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "demihuman", |parser| {
+			parser.tag("chara/demihuman/d")?;
+			let id = parser.number(4)?;
+			parser.tag("/obj/equipment/e")?;
+			let equipment = parser.number(4)?;
+			parser.tag("/")?;
+
+			if parser.peek("model/") {
+				parser.tag("model/d")?;
+				set_id(parser, id)?;
+				parser.tag("e")?;
+				set_id(parser, equipment)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(EquipmentSlot::SUFFIXES)?;
+				parser.tag(".mdl")?;
+				return Some(Self::Model {
+					id,
+					equipment,
+					slot,
+				});
+			}
+
+			if parser.peek("material/") {
+				parser.tag("material/v")?;
+				let variant = parser.number(4)?;
+				parser.tag("/mt_d")?;
+				set_id(parser, id)?;
+				parser.tag("e")?;
+				set_id(parser, equipment)?;
+				parser.tag("_")?;
+				let slot = parser.one_of(EquipmentSlot::SUFFIXES)?;
+				parser.tag("_")?;
+				let suffix = parser.until(".mtrl")?.into();
+				parser.tag(".mtrl")?;
+				return Some(Self::Material {
+					id,
+					equipment,
+					slot,
+					variant,
+					suffix,
+				});
+			}
+
+			parser.tag("e")?;
+			set_id(parser, equipment)?;
+			parser.tag(".imc")?;
+			Some(Self::Imc { id, equipment })
+		})
+	}
+}
+
+/// Consume a repeated 4-digit ID, ensuring it matches the `expected` value
+/// already parsed from the directory.
+pub(super) fn set_id(parser: &mut Parser, expected: u16) -> Option<()> {
+	(parser.number::<u16>(4)? == expected).then_some(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Accessory, AccessorySlot, Demihuman, Equipment, EquipmentSlot};
+
+	#[test]
+	fn round_trip() {
+		let paths = [
+			"chara/equipment/e6024/model/c0201e6024_dwn.mdl",
+			"chara/equipment/e0001/material/v0002/mt_c0101e0001_top_a.mtrl",
+			"chara/equipment/e0001/e0001.imc",
+			"chara/accessory/a0012/model/c0101a0012_rir.mdl",
+			"chara/accessory/a0012/material/v0001/mt_c0101a0012_ear_b.mtrl",
+			"chara/demihuman/d1001/obj/equipment/e0003/model/d1001e0003_met.mdl",
+			"chara/demihuman/d1001/obj/equipment/e0003/e0003.imc",
+		];
+
+		for path in paths {
+			let built = match path.split('/').nth(1) {
+				Some("equipment") => path.parse::<Equipment>().unwrap().to_string(),
+				Some("accessory") => path.parse::<Accessory>().unwrap().to_string(),
+				_ => path.parse::<Demihuman>().unwrap().to_string(),
+			};
+			assert_eq!(built, path);
+		}
+
+		assert_eq!(
+			"chara/accessory/a0012/model/c0101a0012_rir.mdl"
+				.parse::<Accessory>()
+				.unwrap(),
+			Accessory::Model {
+				set: 12,
+				race: 101,
+				slot: AccessorySlot::RightRing
+			}
+		);
+		assert_eq!(
+			"CHARA/EQUIPMENT/E0001/MODEL/C0101E0001_SHO.MDL"
+				.parse::<Equipment>()
+				.unwrap(),
+			Equipment::Model {
+				set: 1,
+				race: 101,
+				slot: EquipmentSlot::Feet
+			}
+		);
+	}
+
+	#[test]
+	fn rejects_mismatched() {
+		assert!("chara/equipment/e0001/model/c0101e0002_top.mdl"
+			.parse::<Equipment>()
+			.is_err());
+		assert!("chara/equipment/e0001/model/c0101e0001_ear.mdl"
+			.parse::<Equipment>()
+			.is_err());
+		assert!("chara/equipment/e0001/model/c0101e0001_top.mdl.bak"
+			.parse::<Equipment>()
+			.is_err());
+		assert!(
+			"chara/demihuman/d1001/obj/equipment/e0003/model/d1001e0003_met.mdl"
+				.parse::<Equipment>()
+				.is_err()
+		);
+	}
+}
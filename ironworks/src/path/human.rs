@@ -0,0 +1,199 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+
+use super::{equipment::set_id, parser::parse};
+
+/// Part of a human character model.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HumanPart {
+	Body,
+	Face,
+	Hair,
+}
+
+impl HumanPart {
+	const DIRECTORIES: &'static [(&'static str, Self)] = &[
+		("body", Self::Body),
+		("face", Self::Face),
+		("hair", Self::Hair),
+	];
+
+	/// Name of the directory containing the part.
+	pub fn directory(&self) -> &'static str {
+		match self {
+			Self::Body => "body",
+			Self::Face => "face",
+			Self::Hair => "hair",
+		}
+	}
+
+	/// Prefix of the part's ID in file names, i.e. `h` for `h0001`.
+	pub fn prefix(&self) -> char {
+		match self {
+			Self::Body => 'b',
+			Self::Face => 'f',
+			Self::Hair => 'h',
+		}
+	}
+
+	/// Suffix used for the part's model file names, i.e. `hir`.
+	pub fn model_suffix(&self) -> &'static str {
+		match self {
+			Self::Body => "top",
+			Self::Face => "fac",
+			Self::Hair => "hir",
+		}
+	}
+}
+
+/// Path to a file belonging to a part of a human character, i.e.
+/// `chara/human/c0101/obj/hair/h0001/model/c0101h0001_hir.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Human {
+	/// Model of the part.
+	Model {
+		/// Race code, i.e. `101` for `c0101`.
+		race: u16,
+		/// Part of the character.
+		part: HumanPart,
+		/// ID of the part, i.e. the hair style.
+		id: u16,
+	},
+
+	/// Material used by the part's model.
+	Material {
+		/// Race code, i.e. `101` for `c0101`.
+		race: u16,
+		/// Part of the character.
+		part: HumanPart,
+		/// ID of the part, i.e. the hair style.
+		id: u16,
+		/// Material variant ID. Some parts, such as faces, store their materials
+		/// without a variant directory.
+		variant: Option<u16>,
+		/// Material name suffix, i.e. `hir_a`.
+		suffix: String,
+	},
+}
+
+impl fmt::Display for Human {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (race, part, id) = match self {
+			Self::Model { race, part, id } | Self::Material { race, part, id, .. } => {
+				(race, part, id)
+			}
+		};
+
+		let prefix = part.prefix();
+		write!(
+			formatter,
+			"chara/human/c{race:04}/obj/{}/{prefix}{id:04}/",
+			part.directory()
+		)?;
+
+		match self {
+			Self::Model { .. } => write!(
+				formatter,
+				"model/c{race:04}{prefix}{id:04}_{}.mdl",
+				part.model_suffix()
+			),
+			Self::Material {
+				variant, suffix, ..
+			} => {
+				formatter.write_str("material/")?;
+				if let Some(variant) = variant {
+					write!(formatter, "v{variant:04}/")?;
+				}
+				write!(formatter, "mt_c{race:04}{prefix}{id:04}_{suffix}.mtrl")
+			}
+		}
+	}
+}
+
+impl FromStr for Human {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "human", |parser| {
+			parser.tag("chara/human/c")?;
+			let race = parser.number(4)?;
+			parser.tag("/obj/")?;
+			let part = parser.one_of(HumanPart::DIRECTORIES)?;
+			let prefix = part.prefix().to_string();
+			parser.tag("/")?;
+			parser.tag(&prefix)?;
+			let id = parser.number(4)?;
+			parser.tag("/")?;
+
+			if parser.peek("model/") {
+				parser.tag("model/c")?;
+				set_id(parser, race)?;
+				parser.tag(&prefix)?;
+				set_id(parser, id)?;
+				parser.tag("_")?;
+				parser.tag(part.model_suffix())?;
+				parser.tag(".mdl")?;
+				return Some(Self::Model { race, part, id });
+			}
+
+			parser.tag("material/")?;
+			let variant = match parser.peek("v") {
+				true => {
+					parser.tag("v")?;
+					let variant = parser.number(4)?;
+					parser.tag("/")?;
+					Some(variant)
+				}
+				false => None,
+			};
+			parser.tag("mt_c")?;
+			set_id(parser, race)?;
+			parser.tag(&prefix)?;
+			set_id(parser, id)?;
+			parser.tag("_")?;
+			let suffix = parser.until(".mtrl")?.into();
+			parser.tag(".mtrl")?;
+
+			Some(Self::Material {
+				race,
+				part,
+				id,
+				variant,
+				suffix,
+			})
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Human, HumanPart};
+
+	#[test]
+	fn round_trip() {
+		let paths = [
+			"chara/human/c0101/obj/body/b0001/model/c0101b0001_top.mdl",
+			"chara/human/c1801/obj/hair/h0104/material/v0001/mt_c1801h0104_hir_a.mtrl",
+			"chara/human/c0201/obj/face/f0002/material/mt_c0201f0002_iri_a.mtrl",
+		];
+		for path in paths {
+			assert_eq!(path.parse::<Human>().unwrap().to_string(), path);
+		}
+
+		assert_eq!(
+			"chara/human/c0101/obj/face/f0001/model/c0101f0001_fac.mdl"
+				.parse::<Human>()
+				.unwrap(),
+			Human::Model {
+				race: 101,
+				part: HumanPart::Face,
+				id: 1
+			}
+		);
+		assert!("chara/human/c0101/obj/face/f0001/model/c0101f0001_hir.mdl"
+			.parse::<Human>()
+			.is_err());
+	}
+}
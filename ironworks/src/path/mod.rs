@@ -0,0 +1,26 @@
+//! Typed builders and parsers for game file paths.
+//!
+//! Each path family implements [`Display`](std::fmt::Display) to build the
+//! path of a file, and [`FromStr`](std::str::FromStr) to parse a path back
+//! into its components.
+//!
+//! ```
+//! use ironworks::path::{Equipment, EquipmentSlot};
+//!
+//! let path = Equipment::Model { set: 1, race: 101, slot: EquipmentSlot::Body };
+//! assert_eq!(path.to_string(), "chara/equipment/e0001/model/c0101e0001_top.mdl");
+//! assert_eq!("chara/equipment/e0001/model/c0101e0001_top.mdl".parse::<Equipment>().unwrap(), path);
+//! ```
+
+mod equipment;
+mod human;
+mod object;
+mod parser;
+mod ui;
+
+pub use {
+	equipment::{Accessory, AccessorySlot, Demihuman, Equipment, EquipmentSlot},
+	human::{Human, HumanPart},
+	object::{Monster, Weapon},
+	ui::{Icon, IconLanguage, MapTexture, MapTextureKind},
+};
@@ -0,0 +1,235 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+
+use super::{equipment::set_id, parser::parse};
+
+/// Path to a file belonging to a weapon, i.e.
+/// `chara/weapon/w0101/obj/body/b0001/model/w0101b0001.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Weapon {
+	/// Model of the weapon.
+	Model {
+		/// Weapon model ID.
+		id: u16,
+		/// Body ID of the weapon model.
+		body: u16,
+	},
+
+	/// Material used by the weapon's model.
+	Material {
+		/// Weapon model ID.
+		id: u16,
+		/// Body ID of the weapon model.
+		body: u16,
+		/// Material variant ID.
+		variant: u16,
+		/// Material name suffix, i.e. `a`.
+		suffix: String,
+	},
+
+	/// Image change data for the weapon body.
+	Imc {
+		/// Weapon model ID.
+		id: u16,
+		/// Body ID of the weapon model.
+		body: u16,
+	},
+}
+
+/// Path to a file belonging to a monster, i.e.
+/// `chara/monster/m0001/obj/body/b0001/model/m0001b0001.mdl`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Monster {
+	/// Model of the monster.
+	Model {
+		/// Monster model ID.
+		id: u16,
+		/// Body ID of the monster model.
+		body: u16,
+	},
+
+	/// Material used by the monster's model.
+	Material {
+		/// Monster model ID.
+		id: u16,
+		/// Body ID of the monster model.
+		body: u16,
+		/// Material variant ID.
+		variant: u16,
+		/// Material name suffix, i.e. `a`.
+		suffix: String,
+	},
+
+	/// Image change data for the monster body.
+	Imc {
+		/// Monster model ID.
+		id: u16,
+		/// Body ID of the monster model.
+		body: u16,
+	},
+}
+
+// Weapons and monsters share a layout, differing only in their directory and
+// ID prefix. Both are mapped through this to avoid duplicating path rules.
+enum BodyFile<'a> {
+	Model,
+	Material { variant: u16, suffix: &'a str },
+	Imc,
+}
+
+struct BodyPath<'a> {
+	id: u16,
+	body: u16,
+	file: BodyFile<'a>,
+}
+
+impl BodyPath<'_> {
+	fn write(&self, formatter: &mut fmt::Formatter<'_>, kind: &str, prefix: &str) -> fmt::Result {
+		let Self { id, body, file } = self;
+		let base = format!("chara/{kind}/{prefix}{id:04}/obj/body/b{body:04}");
+		match file {
+			BodyFile::Model => write!(formatter, "{base}/model/{prefix}{id:04}b{body:04}.mdl"),
+			BodyFile::Material { variant, suffix } => write!(
+				formatter,
+				"{base}/material/v{variant:04}/mt_{prefix}{id:04}b{body:04}_{suffix}.mtrl"
+			),
+			BodyFile::Imc => write!(formatter, "{base}/b{body:04}.imc"),
+		}
+	}
+}
+
+fn parse_body<T>(
+	path: &str,
+	kind: &str,
+	prefix: &str,
+	build: impl FnOnce(u16, u16, BodyFile) -> T,
+) -> Result<T> {
+	parse(path, kind, |parser| {
+		parser.tag("chara/")?;
+		parser.tag(kind)?;
+		parser.tag("/")?;
+		parser.tag(prefix)?;
+		let id = parser.number(4)?;
+		parser.tag("/obj/body/b")?;
+		let body = parser.number(4)?;
+		parser.tag("/")?;
+
+		let file = if parser.peek("model/") {
+			parser.tag("model/")?;
+			parser.tag(prefix)?;
+			set_id(parser, id)?;
+			parser.tag("b")?;
+			set_id(parser, body)?;
+			parser.tag(".mdl")?;
+			BodyFile::Model
+		} else if parser.peek("material/") {
+			parser.tag("material/v")?;
+			let variant = parser.number(4)?;
+			parser.tag("/mt_")?;
+			parser.tag(prefix)?;
+			set_id(parser, id)?;
+			parser.tag("b")?;
+			set_id(parser, body)?;
+			parser.tag("_")?;
+			let suffix = parser.until(".mtrl")?;
+			parser.tag(".mtrl")?;
+			BodyFile::Material { variant, suffix }
+		} else {
+			parser.tag("b")?;
+			set_id(parser, body)?;
+			parser.tag(".imc")?;
+			BodyFile::Imc
+		};
+
+		Some(build(id, body, file))
+	})
+}
+
+macro_rules! body_path {
+	($type:ident, $kind:literal, $prefix:literal) => {
+		impl fmt::Display for $type {
+			fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+				let path = match self {
+					Self::Model { id, body } => BodyPath {
+						id: *id,
+						body: *body,
+						file: BodyFile::Model,
+					},
+					Self::Material {
+						id,
+						body,
+						variant,
+						suffix,
+					} => BodyPath {
+						id: *id,
+						body: *body,
+						file: BodyFile::Material {
+							variant: *variant,
+							suffix,
+						},
+					},
+					Self::Imc { id, body } => BodyPath {
+						id: *id,
+						body: *body,
+						file: BodyFile::Imc,
+					},
+				};
+				path.write(formatter, $kind, $prefix)
+			}
+		}
+
+		impl FromStr for $type {
+			type Err = Error;
+
+			fn from_str(path: &str) -> Result<Self> {
+				parse_body(path, $kind, $prefix, |id, body, file| match file {
+					BodyFile::Model => Self::Model { id, body },
+					BodyFile::Material { variant, suffix } => Self::Material {
+						id,
+						body,
+						variant,
+						suffix: suffix.into(),
+					},
+					BodyFile::Imc => Self::Imc { id, body },
+				})
+			}
+		}
+	};
+}
+
+body_path!(Weapon, "weapon", "w");
+body_path!(Monster, "monster", "m");
+
+#[cfg(test)]
+mod test {
+	use super::{Monster, Weapon};
+
+	#[test]
+	fn round_trip() {
+		let weapon = "chara/weapon/w2101/obj/body/b0012/material/v0003/mt_w2101b0012_a.mtrl";
+		assert_eq!(
+			weapon.parse::<Weapon>().unwrap(),
+			Weapon::Material {
+				id: 2101,
+				body: 12,
+				variant: 3,
+				suffix: "a".into()
+			}
+		);
+		assert_eq!(weapon.parse::<Weapon>().unwrap().to_string(), weapon);
+
+		let monster = "chara/monster/m0405/obj/body/b0001/model/m0405b0001.mdl";
+		assert_eq!(
+			monster.parse::<Monster>().unwrap(),
+			Monster::Model { id: 405, body: 1 }
+		);
+		assert_eq!(monster.parse::<Monster>().unwrap().to_string(), monster);
+		assert_eq!(
+			Monster::Imc { id: 405, body: 1 }.to_string(),
+			"chara/monster/m0405/obj/body/b0001/b0001.imc"
+		);
+
+		assert!(monster.parse::<Weapon>().is_err());
+	}
+}
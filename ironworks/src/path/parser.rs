@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use crate::error::{Error, ErrorValue, Result};
+
+/// Parse `path` as a path of the `kind` family. The parse function must consume
+/// the full path for it to be considered valid.
+pub fn parse<T>(path: &str, kind: &str, parse: impl FnOnce(&mut Parser) -> Option<T>) -> Result<T> {
+	let lower = path.to_lowercase();
+	let mut parser = Parser { rest: &lower };
+
+	match parse(&mut parser) {
+		Some(value) if parser.rest.is_empty() => Ok(value),
+		_ => Err(Error::Invalid(
+			ErrorValue::Path(path.into()),
+			format!("not a {kind} path"),
+		)),
+	}
+}
+
+/// Minimal cursor over a path being parsed.
+#[derive(Debug)]
+pub struct Parser<'a> {
+	rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+	/// Consume `tag` from the start of the path.
+	pub fn tag(&mut self, tag: &str) -> Option<()> {
+		self.rest = self.rest.strip_prefix(tag)?;
+		Some(())
+	}
+
+	/// Consume a number of exactly `digits` digits.
+	pub fn number<T: FromStr>(&mut self, digits: usize) -> Option<T> {
+		let value = self.rest.get(..digits)?;
+		if !value.bytes().all(|byte| byte.is_ascii_digit()) {
+			return None;
+		}
+
+		self.rest = &self.rest[digits..];
+		value.parse().ok()
+	}
+
+	/// Consume a non-empty segment up to, but not including, `delimiter`.
+	/// Segments never cross directory boundaries.
+	pub fn until(&mut self, delimiter: &str) -> Option<&'a str> {
+		let end = self.rest.find(delimiter)?;
+		let value = &self.rest[..end];
+		if value.is_empty() || value.contains('/') {
+			return None;
+		}
+
+		self.rest = &self.rest[end..];
+		Some(value)
+	}
+
+	/// Consume the first of `options` that matches the start of the path,
+	/// returning its associated value.
+	pub fn one_of<T: Copy>(&mut self, options: &[(&str, T)]) -> Option<T> {
+		options
+			.iter()
+			.find(|(tag, _)| self.rest.starts_with(tag))
+			.map(|&(tag, value)| {
+				self.rest = &self.rest[tag.len()..];
+				value
+			})
+	}
+
+	/// Check whether the path continues with `tag`, without consuming it.
+	pub fn peek(&self, tag: &str) -> bool {
+		self.rest.starts_with(tag)
+	}
+}
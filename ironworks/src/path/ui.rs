@@ -0,0 +1,236 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+
+use super::parser::parse;
+
+/// Language of a localised icon.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconLanguage {
+	Japanese,
+	English,
+	German,
+	French,
+	ChineseSimplified,
+	Korean,
+}
+
+impl IconLanguage {
+	const DIRECTORIES: &'static [(&'static str, Self)] = &[
+		("ja/", Self::Japanese),
+		("en/", Self::English),
+		("de/", Self::German),
+		("fr/", Self::French),
+		("chs/", Self::ChineseSimplified),
+		("ko/", Self::Korean),
+	];
+
+	/// Name of the directory containing icons for the language.
+	pub fn directory(&self) -> &'static str {
+		match self {
+			Self::Japanese => "ja",
+			Self::English => "en",
+			Self::German => "de",
+			Self::French => "fr",
+			Self::ChineseSimplified => "chs",
+			Self::Korean => "ko",
+		}
+	}
+}
+
+/// Path to an icon texture, i.e. `ui/icon/060000/en/060001_hr1.tex`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Icon {
+	/// Icon ID.
+	pub id: u32,
+	/// Whether to use the high resolution variant of the icon.
+	pub high_resolution: bool,
+	/// Language of the icon, for icons containing localised text.
+	pub language: Option<IconLanguage>,
+}
+
+impl Icon {
+	/// Build a path to the standard resolution, unlocalised icon with the given ID.
+	pub fn new(id: u32) -> Self {
+		Self {
+			id,
+			high_resolution: false,
+			language: None,
+		}
+	}
+
+	/// Use the high resolution variant of the icon.
+	#[must_use]
+	pub fn with_high_resolution(mut self, high_resolution: bool) -> Self {
+		self.high_resolution = high_resolution;
+		self
+	}
+
+	/// Use the variant of the icon localised for `language`.
+	#[must_use]
+	pub fn with_language(mut self, language: IconLanguage) -> Self {
+		self.language = Some(language);
+		self
+	}
+}
+
+impl fmt::Display for Icon {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let id = self.id;
+		write!(formatter, "ui/icon/{:06}/", id / 1000 * 1000)?;
+		if let Some(language) = self.language {
+			write!(formatter, "{}/", language.directory())?;
+		}
+		write!(formatter, "{id:06}")?;
+		if self.high_resolution {
+			formatter.write_str("_hr1")?;
+		}
+		formatter.write_str(".tex")
+	}
+}
+
+impl FromStr for Icon {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "icon", |parser| {
+			parser.tag("ui/icon/")?;
+			let group = parser.number::<u32>(6)?;
+			parser.tag("/")?;
+			let language = parser.one_of(IconLanguage::DIRECTORIES);
+			let id = parser.number::<u32>(6)?;
+			if id / 1000 * 1000 != group {
+				return None;
+			}
+
+			let high_resolution = parser.peek("_hr1");
+			if high_resolution {
+				parser.tag("_hr1")?;
+			}
+			parser.tag(".tex")?;
+
+			Some(Self {
+				id,
+				high_resolution,
+				language,
+			})
+		})
+	}
+}
+
+/// Kind of map texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MapTextureKind {
+	/// Full size map image.
+	Medium,
+	/// Half size map image.
+	Small,
+}
+
+impl MapTextureKind {
+	const SUFFIXES: &'static [(&'static str, Self)] = &[("m", Self::Medium), ("s", Self::Small)];
+
+	/// Suffix used for the kind in file names, i.e. `m`.
+	pub fn suffix(&self) -> &'static str {
+		match self {
+			Self::Medium => "m",
+			Self::Small => "s",
+		}
+	}
+}
+
+/// Path to a map texture, i.e. `ui/map/s1d1/00/s1d100_m.tex`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MapTexture {
+	/// Territory identifier of the map, i.e. `s1d1`.
+	pub territory: String,
+	/// Index of the map within the territory.
+	pub index: u8,
+	/// Kind of texture.
+	pub kind: MapTextureKind,
+}
+
+impl fmt::Display for MapTexture {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let Self {
+			territory,
+			index,
+			kind,
+		} = self;
+		write!(
+			formatter,
+			"ui/map/{territory}/{index:02}/{territory}{index:02}_{}.tex",
+			kind.suffix()
+		)
+	}
+}
+
+impl FromStr for MapTexture {
+	type Err = Error;
+
+	fn from_str(path: &str) -> Result<Self> {
+		parse(path, "map texture", |parser| {
+			parser.tag("ui/map/")?;
+			let territory = parser.until("/")?;
+			parser.tag("/")?;
+			let index = parser.number::<u8>(2)?;
+			parser.tag("/")?;
+			parser.tag(territory)?;
+			if parser.number::<u8>(2)? != index {
+				return None;
+			}
+			parser.tag("_")?;
+			let kind = parser.one_of(MapTextureKind::SUFFIXES)?;
+			parser.tag(".tex")?;
+
+			Some(Self {
+				territory: territory.into(),
+				index,
+				kind,
+			})
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Icon, IconLanguage, MapTexture, MapTextureKind};
+
+	#[test]
+	fn icons() {
+		assert_eq!(Icon::new(1).to_string(), "ui/icon/000000/000001.tex");
+		assert_eq!(
+			Icon::new(60_001)
+				.with_high_resolution(true)
+				.with_language(IconLanguage::English)
+				.to_string(),
+			"ui/icon/060000/en/060001_hr1.tex"
+		);
+
+		let icon = "ui/icon/121000/chs/121561_hr1.tex".parse::<Icon>().unwrap();
+		assert_eq!(
+			icon,
+			Icon::new(121_561)
+				.with_high_resolution(true)
+				.with_language(IconLanguage::ChineseSimplified)
+		);
+		assert!("ui/icon/060000/061001.tex".parse::<Icon>().is_err());
+	}
+
+	#[test]
+	fn maps() {
+		let path = "ui/map/s1d1/00/s1d100_m.tex";
+		let map = path.parse::<MapTexture>().unwrap();
+		assert_eq!(
+			map,
+			MapTexture {
+				territory: "s1d1".into(),
+				index: 0,
+				kind: MapTextureKind::Medium
+			}
+		);
+		assert_eq!(map.to_string(), path);
+		assert!("ui/map/s1d1/00/s1d200_s.tex".parse::<MapTexture>().is_err());
+	}
+}
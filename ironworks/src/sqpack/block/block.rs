@@ -1,33 +1,155 @@
-use std::io::{self, Read, Seek, SeekFrom, Take};
+use std::io::{self, Read, Seek, SeekFrom, Take, Write};
 
-use binrw::{binread, BinRead};
+use binrw::{binread, BinRead, Endian};
 use either::Either;
-use flate2::read::DeflateDecoder;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+
+use super::stream::BlockMetadata;
 
 const MAX_COMPRESSED_BLOCK_SIZE: u32 = 16_000;
 
+// Maximum size, in bytes, of the decompressed payload of a single block.
+const MAX_BLOCK_SIZE: usize = 16_000;
+
+/// Alignment, in bytes, of blocks and files within SqPack dat files.
+pub const BLOCK_ALIGNMENT: usize = 128;
+
+// Compressed size recorded for blocks stored without compression. Any value
+// above the maximum compressed size is treated as uncompressed.
+const UNCOMPRESSED_BLOCK_SIZE: u32 = 32_000;
+
+/// Header preceding each block of data within a SqPack file.
 #[binread]
-#[derive(Debug)]
-#[br(little)]
+#[derive(Debug, Clone)]
 pub struct BlockHeader {
+	/// Size of the header, in bytes.
 	pub size: u32,
 	// unknown1: u32,
+	/// Size of the compressed payload, in bytes. Values above 16000 mark the
+	/// payload as stored without compression.
 	#[br(pad_before = 4)]
 	pub compressed_size: u32,
+	/// Size of the payload once decompressed, in bytes.
 	pub decompressed_size: u32,
 }
 
-pub fn read_block<R: Read + Seek>(reader: &mut R, offset: u32) -> io::Result<BlockPayload<R>> {
-	// Seek to the block and read its header so we know how much to expect in the rest of the block.
+impl BlockHeader {
+	const SIZE: u32 = 16;
+
+	/// Check if the block's payload is deflate-compressed.
+	pub fn is_compressed(&self) -> bool {
+		self.compressed_size <= MAX_COMPRESSED_BLOCK_SIZE
+	}
+
+	/// Size of the payload as stored, in bytes.
+	pub fn payload_size(&self) -> u32 {
+		match self.is_compressed() {
+			true => self.compressed_size,
+			false => self.decompressed_size,
+		}
+	}
+}
+
+/// Write `data` as a single block, including its header and trailing padding.
+/// Data is deflate-compressed, unless compression would not reduce its size.
+/// Returns the number of bytes written.
+fn write_block(writer: &mut impl Write, data: &[u8]) -> io::Result<usize> {
+	assert!(
+		data.len() <= MAX_BLOCK_SIZE,
+		"block payloads may not exceed {MAX_BLOCK_SIZE} bytes"
+	);
+
+	let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+	encoder.write_all(data)?;
+	let compressed = encoder.finish()?;
+
+	let (compressed_size, payload) = match compressed.len() < data.len() {
+		true => (u32::try_from(compressed.len()).unwrap(), &compressed[..]),
+		false => (UNCOMPRESSED_BLOCK_SIZE, data),
+	};
+
+	for value in [
+		BlockHeader::SIZE,
+		0,
+		compressed_size,
+		u32::try_from(data.len()).unwrap(),
+	] {
+		writer.write_all(&value.to_le_bytes())?;
+	}
+	writer.write_all(payload)?;
+
+	let size = usize::try_from(BlockHeader::SIZE).unwrap() + payload.len();
+	let padding = padding(size);
+	writer.write_all(&vec![0; padding])?;
+
+	Ok(size + padding)
+}
+
+/// Write `data` as a sequence of blocks. Returns the written size and payload
+/// size of each block, in order.
+pub fn write_blocks(writer: &mut impl Write, data: &[u8]) -> io::Result<Vec<(usize, usize)>> {
+	data.chunks(MAX_BLOCK_SIZE)
+		.map(|chunk| Ok((write_block(writer, chunk)?, chunk.len())))
+		.collect()
+}
+
+/// Number of bytes required to pad `size` to the block alignment.
+pub fn padding(size: usize) -> usize {
+	(BLOCK_ALIGNMENT - size % BLOCK_ALIGNMENT) % BLOCK_ALIGNMENT
+}
+
+fn read_header<R: Read + Seek>(
+	reader: &mut R,
+	offset: u32,
+	endian: Endian,
+) -> io::Result<BlockHeader> {
 	reader.seek(SeekFrom::Start(offset.into()))?;
-	let block_header =
-		BlockHeader::read(reader).map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
-
-	Ok(BlockPayload::new(
-		reader,
-		block_header.compressed_size,
-		block_header.decompressed_size,
-	))
+	BlockHeader::read_options(reader, endian, ())
+		.map_err(|error| io::Error::new(io::ErrorKind::Other, error))
+}
+
+/// Read the header of the block at `offset`, building metadata for a stream
+/// that outputs the block's payload at `output_offset`.
+pub fn read_block_metadata<R: Read + Seek>(
+	reader: &mut R,
+	offset: u32,
+	output_offset: usize,
+	endian: Endian,
+) -> io::Result<BlockMetadata> {
+	let block_header = read_header(reader, offset, endian)?;
+
+	Ok(BlockMetadata {
+		input_offset: usize::try_from(offset + block_header.size).unwrap(),
+		input_size: usize::try_from(block_header.compressed_size).unwrap(),
+		output_offset,
+		output_size: usize::try_from(block_header.decompressed_size).unwrap(),
+	})
+}
+
+/// Read the header and raw, still-compressed payload of the block at `offset`.
+pub fn read_raw_block<R: Read + Seek>(
+	reader: &mut R,
+	offset: u32,
+	endian: Endian,
+) -> io::Result<(BlockHeader, Vec<u8>)> {
+	let block_header = read_header(reader, offset, endian)?;
+
+	let payload_size = block_header.payload_size();
+	reader.seek(SeekFrom::Start((offset + block_header.size).into()))?;
+	let mut payload = Vec::with_capacity(payload_size.try_into().unwrap());
+	reader.take(payload_size.into()).read_to_end(&mut payload)?;
+
+	if payload.len() != usize::try_from(payload_size).unwrap() {
+		return Err(io::Error::new(
+			io::ErrorKind::UnexpectedEof,
+			format!(
+				"failed to read block: expected {payload_size} bytes, got {}",
+				payload.len()
+			),
+		));
+	}
+
+	Ok((block_header, payload))
 }
 
 /// Reader for a single potentially-compressed block payload.
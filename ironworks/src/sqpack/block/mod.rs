@@ -2,6 +2,9 @@ mod block;
 mod stream;
 
 pub use {
-	block::{read_block, BlockHeader, BlockPayload},
+	block::{
+		padding, read_block_metadata, read_raw_block, write_blocks, BlockHeader, BlockPayload,
+		BLOCK_ALIGNMENT,
+	},
 	stream::{BlockMetadata, BlockStream},
 };
@@ -24,6 +24,8 @@ pub struct BlockStream<R> {
 	origin: usize,
 	/// Metadata about the blocks comprising the file.
 	metadata: Vec<BlockMetadata>,
+	/// Unblocked data preceding the first block in the output.
+	prefix: Vec<u8>,
 
 	/// Stream's position within the sqpack file.
 	position: usize,
@@ -45,12 +47,32 @@ where
 			dat_reader,
 			origin,
 			metadata,
+			prefix: Vec::new(),
 
 			position: 0,
 			current_block: 0,
 			block_data: None,
 		}
 	}
+
+	/// Set data to be served, unmodified, at the start of the output stream. Any
+	/// gaps between the prefix and blocks will be read as zeroes.
+	#[must_use]
+	pub fn with_prefix(mut self, prefix: Vec<u8>) -> Self {
+		self.prefix = prefix;
+		self
+	}
+}
+
+impl<R> BlockStream<R> {
+	// Total size of the output stream, ignoring the origin.
+	fn output_size(&self) -> usize {
+		let blocks_end = self
+			.metadata
+			.last()
+			.map_or(0, |meta| meta.output_offset + meta.output_size);
+		blocks_end.max(self.prefix.len())
+	}
 }
 
 impl<R> Read for BlockStream<R>
@@ -58,43 +80,49 @@ where
 	R: Read + Seek,
 {
 	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-		// If there is no metadata at all, signal EOF. This can occur when an index
-		// contains an entry for a file with no blocks - seemingly used for files
-		// that exist in other regional distributions?
-		if self.metadata.is_empty() {
-			return Ok(0);
-		}
-
-		// Get a ref to the expected current block metadata.
-		let mut meta = &self.metadata[self.current_block];
-
 		// The actual read position within the blocks needs to be offset by the origin.
 		let position = self.position + self.origin;
 
-		// If we've reached the end of the last block, signal EOF.
-		if position == meta.output_offset + meta.output_size
-			&& self.current_block == self.metadata.len() - 1
-		{
+		// Prefix data is held in memory, and can be read directly.
+		if position < self.prefix.len() {
+			let bytes_read = (&self.prefix[position..]).read(buf)?;
+			self.position += bytes_read;
+			return Ok(bytes_read);
+		}
+
+		// If we've reached the end of the stream, signal EOF. This also covers
+		// streams with no metadata at all, which can occur when an index contains
+		// an entry for a file with no blocks - seemingly used for files that exist
+		// in other regional distributions?
+		let output_size = self.output_size();
+		if position >= output_size {
 			return Ok(0);
 		}
 
+		// Get a ref to the expected current block metadata.
+		let mut meta = &self.metadata[self.current_block];
+
 		// If the position has moved outside of the current block, update to a block
 		// that contains the expected position.
 		if position < meta.output_offset || position >= meta.output_offset + meta.output_size {
-			let (new_index, new_meta) = self
-				.metadata
-				.iter()
-				.enumerate()
-				.find(|(_index, meta)| {
-					position >= meta.output_offset
-						&& position < meta.output_offset + meta.output_size
-				})
-				.ok_or_else(|| {
-					io::Error::new(
-						io::ErrorKind::InvalidInput,
-						"reader position outside known range",
-					)
-				})?;
+			let found = self.metadata.iter().enumerate().find(|(_index, meta)| {
+				position >= meta.output_offset && position < meta.output_offset + meta.output_size
+			});
+
+			// Positions between blocks are padding, and read as zeroes up until the
+			// start of the following block.
+			let Some((new_index, new_meta)) = found else {
+				let gap_end = self
+					.metadata
+					.iter()
+					.map(|meta| meta.output_offset)
+					.find(|offset| *offset > position)
+					.unwrap_or(output_size);
+				let bytes_read = buf.len().min(gap_end - position);
+				buf[..bytes_read].fill(0);
+				self.position += bytes_read;
+				return Ok(bytes_read);
+			};
 
 			self.current_block = new_index;
 			meta = new_meta;
@@ -154,13 +182,7 @@ impl<R> Seek for BlockStream<R> {
 				return Ok(position);
 			}
 			SeekFrom::Current(position) => (self.position, position),
-			SeekFrom::End(position) => {
-				let base = match self.metadata.last() {
-					Some(meta) => meta.output_offset + meta.output_size,
-					None => 0,
-				};
-				(base, position)
-			}
+			SeekFrom::End(position) => (self.output_size(), position),
 		};
 
 		let Some(position) = base.checked_add_signed(offset.try_into().unwrap()) else {
@@ -0,0 +1,137 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::{BufRead, BufReader},
+	path::Path,
+};
+
+use crate::{error::Result, utility::crc32};
+
+use super::index::IndexHash;
+
+/// Dictionary of known game paths, used to recover the names of files from
+/// the hashes stored in SqPack indexes.
+///
+/// Path lists can be loaded from ResLogger-style text files, containing one
+/// path per line. CSV exports, with the path as the final column, are also
+/// accepted.
+#[derive(Debug, Default)]
+pub struct PathDictionary {
+	directories: HashMap<u32, String>,
+	files: HashMap<(u32, u32), String>,
+	paths: HashMap<u32, String>,
+}
+
+impl PathDictionary {
+	/// Build a new, empty dictionary.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Load a dictionary from the path list file at `path`.
+	pub fn load(path: &Path) -> Result<Self> {
+		Self::from_reader(BufReader::new(fs::File::open(path)?))
+	}
+
+	/// Load a dictionary from a reader over a path list.
+	pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+		let mut dictionary = Self::new();
+		for line in reader.lines() {
+			let line = line?;
+			// CSV exports list the path as the last column.
+			let path = line.rsplit(',').next().unwrap_or_default().trim();
+			// Skip blank lines, headers, and other non-path content.
+			if path.contains('/') {
+				dictionary.add_path(path);
+			}
+		}
+		Ok(dictionary)
+	}
+
+	/// Add a path to the dictionary.
+	pub fn add_path(&mut self, path: &str) {
+		// SqPack paths are always lower case.
+		let path = path.to_lowercase();
+
+		if let Some((directory, file)) = path.rsplit_once('/') {
+			let directory_hash = crc32(directory.as_bytes());
+			self.directories
+				.entry(directory_hash)
+				.or_insert_with(|| directory.to_string());
+			self.files
+				.entry((directory_hash, crc32(file.as_bytes())))
+				.or_insert_with(|| path.clone());
+		}
+
+		self.paths.entry(crc32(path.as_bytes())).or_insert(path);
+	}
+
+	/// Number of unique paths in the dictionary.
+	pub fn len(&self) -> usize {
+		self.paths.len()
+	}
+
+	/// Check if the dictionary contains no paths.
+	pub fn is_empty(&self) -> bool {
+		self.paths.is_empty()
+	}
+
+	/// Get the path matching an index hash, if known.
+	pub fn path(&self, hash: IndexHash) -> Option<&str> {
+		match hash {
+			IndexHash::Path { directory, file } => self.files.get(&(directory, file)),
+			IndexHash::Full(hash) => self.paths.get(&hash),
+		}
+		.map(String::as_str)
+	}
+
+	/// Get the directory of the file matching an index hash, if known. Index1
+	/// hashes can be resolved to a directory even if the file name is unknown.
+	pub fn directory(&self, hash: IndexHash) -> Option<&str> {
+		match hash {
+			IndexHash::Path { directory, .. } => {
+				self.directories.get(&directory).map(String::as_str)
+			}
+			IndexHash::Full(_) => self
+				.path(hash)
+				.and_then(|path| path.rsplit_once('/'))
+				.map(|(directory, _)| directory),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::Cursor;
+
+	use crate::{sqpack::IndexHash, utility::crc32};
+
+	use super::PathDictionary;
+
+	#[test]
+	fn resolves_hashes() {
+		let list = "IndexId,FolderHash,FileHash,FullHash,Path\n\
+			0,1,2,3,chara/test/file.bin\n\
+			\n\
+			Chara/Test/Other.bin\n";
+		let dictionary = PathDictionary::from_reader(Cursor::new(list)).unwrap();
+		assert_eq!(dictionary.len(), 2);
+
+		let index1 = IndexHash::Path {
+			directory: crc32(b"chara/test"),
+			file: crc32(b"other.bin"),
+		};
+		assert_eq!(dictionary.path(index1), Some("chara/test/other.bin"));
+
+		let index2 = IndexHash::Full(crc32(b"chara/test/file.bin"));
+		assert_eq!(dictionary.path(index2), Some("chara/test/file.bin"));
+		assert_eq!(dictionary.directory(index2), Some("chara/test"));
+
+		let unknown_file = IndexHash::Path {
+			directory: crc32(b"chara/test"),
+			file: crc32(b"missing.bin"),
+		};
+		assert_eq!(dictionary.path(unknown_file), None);
+		assert_eq!(dictionary.directory(unknown_file), Some("chara/test"));
+	}
+}
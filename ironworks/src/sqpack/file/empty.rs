@@ -1,22 +1,125 @@
-use std::io::{Empty, Read, Seek};
+use std::{
+	collections::HashMap,
+	fmt,
+	io::{Cursor, Read, Seek},
+	sync::Arc,
+};
 
-use crate::error::{Error, ErrorValue, Result};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	sqpack::block::padding,
+};
 
-use super::shared::Header;
+use super::shared::{FileKind, Header};
 
-pub fn read(reader: impl Read + Seek, header: Header) -> Result<Empty> {
+const RSF_KEY_SIZE: usize = 64;
+
+/// Content supplied for RSF placeholders, keyed by the placeholder's key.
+pub(crate) type RsfTable = HashMap<RsfKey, Arc<[u8]>>;
+
+/// Key identifying the content of an RSF placeholder entry. The key is stored
+/// twice in succession at the start of the placeholder's payload.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RsfKey(pub [u8; RSF_KEY_SIZE]);
+
+impl fmt::Debug for RsfKey {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(formatter, "RsfKey({self})")
+	}
+}
+
+impl fmt::Display for RsfKey {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		self.0
+			.iter()
+			.try_for_each(|byte| write!(formatter, "{byte:02x}"))
+	}
+}
+
+/// A placeholder stored in a SqPack archive in place of a file's content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Placeholder {
+	/// The entry has no content.
+	Empty,
+
+	/// The content of the entry is stored in an RSF, and must be supplied
+	/// separately before the file can be read.
+	Rsf(RsfKey),
+
+	/// The entry's payload was not recognised. The raw payload is included.
+	Unknown(Vec<u8>),
+}
+
+impl Placeholder {
+	/// Classify the payload stored within an empty entry.
+	pub fn from_data(data: &[u8]) -> Self {
+		if data.iter().all(|&byte| byte == 0) {
+			return Self::Empty;
+		}
+
+		if let (Some(first), Some(second)) = (
+			data.get(..RSF_KEY_SIZE),
+			data.get(RSF_KEY_SIZE..RSF_KEY_SIZE * 2),
+		) {
+			if first == second {
+				return Self::Rsf(RsfKey(first.try_into().unwrap()));
+			}
+		}
+
+		Self::Unknown(data.to_vec())
+	}
+}
+
+impl fmt::Display for Placeholder {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Empty => write!(formatter, "empty placeholder"),
+			Self::Rsf(key) => write!(formatter, "RSF placeholder {key}"),
+			Self::Unknown(data) => write!(formatter, "unknown placeholder ({} bytes)", data.len()),
+		}
+	}
+}
+
+/// Read the data stored directly after the header of an empty entry.
+pub fn read_data(reader: impl Read + Seek, header: &Header) -> Result<Vec<u8>> {
 	let mut buf = Vec::with_capacity(header.raw_file_size.try_into().unwrap());
 	reader
 		.take(header.raw_file_size.into())
 		.read_to_end(&mut buf)?;
+	Ok(buf)
+}
 
-	// TODO: if type 1 and first 64 == second 64, RSF
-	//       if type 1 and first 64 == [0..], empty
+pub fn read(reader: impl Read + Seek, header: Header, rsf: &RsfTable) -> Result<Cursor<Arc<[u8]>>> {
+	let placeholder = Placeholder::from_data(&read_data(reader, &header)?);
+
+	// RSF placeholders can be read if their content has been supplied - all
+	// other placeholders are either entirely invalid, or need further processing
+	// that doesn't belong in sqpack specifically.
+	let message = match &placeholder {
+		Placeholder::Rsf(key) => match rsf.get(key) {
+			Some(data) => return Ok(Cursor::new(data.clone())),
+			None => "RSF content has not been supplied",
+		},
+		Placeholder::Empty => "Empty file",
+		Placeholder::Unknown(_) => "Unrecognised placeholder",
+	};
 
-	// Empty files can't be read as-is - they're either entirely invalid, or need
-	// further processing that doesn't belong in sqpack specifically.
 	Err(Error::Invalid(
-		ErrorValue::File(buf),
-		String::from("Empty file"),
+		ErrorValue::Placeholder(placeholder),
+		String::from(message),
 	))
 }
+
+pub fn write(data: &[u8]) -> Result<Vec<u8>> {
+	// Empty entries store their (typically absent) payload directly after the header fields.
+	let size = Header::SIZE + data.len();
+	let header_size = size + padding(size);
+
+	let mut buffer = Vec::with_capacity(header_size);
+	Header::write(&mut buffer, FileKind::Empty, header_size, data.len(), 0, 0);
+	buffer.extend_from_slice(data);
+	buffer.resize(header_size, 0);
+
+	Ok(buffer)
+}
@@ -1,11 +1,18 @@
-use std::io::{Cursor, Empty, Read, Seek, SeekFrom};
+use std::{
+	io::{Cursor, Read, Seek, SeekFrom},
+	sync::Arc,
+};
 
 use binrw::BinRead;
 
-use crate::{error::Result, sqpack::block::BlockStream};
+use crate::{
+	error::Result,
+	sqpack::{block::BlockStream, platform::Platform},
+};
 
 use super::{
-	empty, model,
+	empty::{self, RsfTable},
+	model,
 	shared::{FileKind, Header},
 	standard, texture,
 };
@@ -19,35 +26,59 @@ pub struct File<R> {
 
 impl<R: Read + Seek> File<R> {
 	/// Create a new File which which will translate SqPack stored data in the given stream.
-	pub fn new(mut reader: R) -> Result<Self> {
+	pub fn new(reader: R) -> Result<Self> {
+		Self::for_platform(reader, Platform::Win32)
+	}
+
+	/// Create a new File which will translate SqPack stored data built for the
+	/// specified platform in the given stream.
+	pub fn for_platform(reader: R, platform: Platform) -> Result<Self> {
+		Self::with_rsf(reader, platform, &RsfTable::default())
+	}
+
+	/// Create a new File, resolving RSF placeholders using the content in `rsf`.
+	pub(crate) fn with_rsf(mut reader: R, platform: Platform, rsf: &RsfTable) -> Result<Self> {
 		// Read in the header.
-		let header = Header::read(&mut reader)?;
+		let endian = platform.endian();
+		let header = Header::read_options(&mut reader, endian, ())?;
 
 		use FileStreamKind as FSK;
+		let size = header.size;
 		let file_stream = match &header.kind {
-			FileKind::Empty => FSK::Empty(empty::read(reader, header)?),
-			FileKind::Standard => FSK::Standard(standard::read(reader, header.size, header)?),
-			FileKind::Model => FSK::Model(model::read(reader, header.size, header)?),
-			FileKind::Texture => FSK::Texture(texture::read(reader, header.size, header)?),
+			FileKind::Empty => FSK::Rsf(empty::read(reader, header, rsf)?),
+			FileKind::Standard => FSK::Standard(standard::read(reader, size, header, endian)?),
+			FileKind::Model => FSK::Model(model::read(reader, size, header, endian)?),
+			FileKind::Texture => FSK::Texture(texture::read(reader, size, header, endian)?),
 		};
 
 		Ok(File { inner: file_stream })
 	}
 }
 
+/// Encode `data` as a SqPack file of the specified kind, ready to be stored
+/// within a dat archive.
+pub fn encode(data: &[u8], kind: FileKind) -> Result<Vec<u8>> {
+	match kind {
+		FileKind::Empty => empty::write(data),
+		FileKind::Standard => standard::write(data),
+		FileKind::Model => model::write(data),
+		FileKind::Texture => texture::write(data),
+	}
+}
+
 #[derive(Debug)]
 enum FileStreamKind<R> {
-	Empty(Empty),
+	Rsf(Cursor<Arc<[u8]>>),
 	Standard(BlockStream<R>),
-	Model(Cursor<Vec<u8>>),
-	Texture(Cursor<Vec<u8>>),
+	Model(BlockStream<R>),
+	Texture(BlockStream<R>),
 }
 
 impl<R: Read + Seek> Read for File<R> {
 	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
 		use FileStreamKind as FSK;
 		match &mut self.inner {
-			FSK::Empty(stream) => stream.read(buf),
+			FSK::Rsf(stream) => stream.read(buf),
 			FSK::Standard(stream) => stream.read(buf),
 			FSK::Model(stream) => stream.read(buf),
 			FSK::Texture(stream) => stream.read(buf),
@@ -59,10 +90,207 @@ impl<R: Read + Seek> Seek for File<R> {
 	fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
 		use FileStreamKind as FSK;
 		match &mut self.inner {
-			FSK::Empty(stream) => stream.seek(pos),
+			FSK::Rsf(stream) => stream.seek(pos),
 			FSK::Standard(stream) => stream.seek(pos),
 			FSK::Model(stream) => stream.seek(pos),
 			FSK::Texture(stream) => stream.seek(pos),
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::io::{Cursor, Read, Seek, SeekFrom};
+
+	use crate::{
+		error::{Error, ErrorValue},
+		sqpack::{
+			block::BlockPayload,
+			file::{empty::RsfTable, raw::RawFile, Placeholder, RsfKey},
+			Platform,
+		},
+	};
+
+	use super::{encode, File, FileKind};
+
+	fn round_trip(data: &[u8], kind: FileKind) -> Vec<u8> {
+		let encoded = encode(data, kind).unwrap();
+		let mut decoded = Vec::new();
+		File::new(Cursor::new(encoded))
+			.unwrap()
+			.read_to_end(&mut decoded)
+			.unwrap();
+		decoded
+	}
+
+	// Mix of compressible and incompressible data, spanning multiple blocks.
+	fn payload(size: usize) -> Vec<u8> {
+		(0..size)
+			.map(|index| match index % 3000 < 1500 {
+				true => 0,
+				false => (index.wrapping_mul(2_654_435_761) >> 13) as u8,
+			})
+			.collect()
+	}
+
+	fn texture(attribute: u32, array_size: u8, mip_sizes: &[usize]) -> Vec<u8> {
+		let mut data = Vec::new();
+		data.extend_from_slice(&attribute.to_le_bytes());
+		data.extend_from_slice(&[0; 10]);
+		data.push(mip_sizes.len() as u8);
+		data.push(array_size);
+		data.extend_from_slice(&[0; 12]);
+
+		let mut offset = 80u32;
+		for index in 0..13 {
+			let value = match mip_sizes.get(index) {
+				Some(size) => {
+					let current = offset;
+					offset += *size as u32;
+					current
+				}
+				None => 0,
+			};
+			data.extend_from_slice(&value.to_le_bytes());
+		}
+
+		data.extend(payload(mip_sizes.iter().sum()));
+		data
+	}
+
+	#[test]
+	fn standard() {
+		for data in [Vec::new(), b"small".to_vec(), payload(50_000)] {
+			assert_eq!(round_trip(&data, FileKind::Standard), data);
+		}
+	}
+
+	#[test]
+	fn texture_mips() {
+		let data = texture(0, 0, &[40_000, 10_000, 2_500]);
+		assert_eq!(round_trip(&data, FileKind::Texture), data);
+	}
+
+	fn raw_contents(data: &[u8], kind: FileKind) -> Vec<u8> {
+		let mut raw = RawFile::new(Cursor::new(encode(data, kind).unwrap())).unwrap();
+		assert_eq!(raw.kind(), kind);
+
+		let mut contents = raw.prefix().to_vec();
+		for block in raw.blocks() {
+			let (header, payload) = block.unwrap();
+			assert_eq!(header.payload_size() as usize, payload.len());
+			let size = contents.len();
+			BlockPayload::new(
+				&mut Cursor::new(payload),
+				header.compressed_size,
+				header.decompressed_size,
+			)
+			.read_to_end(&mut contents)
+			.unwrap();
+			assert_eq!(contents.len() - size, header.decompressed_size as usize);
+		}
+		contents
+	}
+
+	#[test]
+	fn placeholders() {
+		let placeholder =
+			|data: &[u8]| match File::new(Cursor::new(encode(data, FileKind::Empty).unwrap())) {
+				Err(Error::Invalid(ErrorValue::Placeholder(placeholder), _)) => placeholder,
+				other => panic!("expected placeholder, got {other:?}"),
+			};
+
+		assert_eq!(placeholder(&[0; 128]), Placeholder::Empty);
+		assert!(matches!(placeholder(&[1, 2, 3]), Placeholder::Unknown(_)));
+
+		let key = RsfKey([7; 64]);
+		let data = [key.0, key.0].concat();
+		assert_eq!(placeholder(&data), Placeholder::Rsf(key));
+
+		let mut rsf = RsfTable::default();
+		rsf.insert(key, b"resolved".as_slice().into());
+		let mut decoded = Vec::new();
+		File::with_rsf(
+			Cursor::new(encode(&data, FileKind::Empty).unwrap()),
+			Platform::Win32,
+			&rsf,
+		)
+		.unwrap()
+		.read_to_end(&mut decoded)
+		.unwrap();
+		assert_eq!(decoded, b"resolved");
+	}
+
+	#[test]
+	fn raw_blocks() {
+		let data = payload(50_000);
+		assert_eq!(raw_contents(&data, FileKind::Standard), data);
+
+		let data = texture(0, 0, &[40_000, 10_000, 2_500]);
+		assert_eq!(raw_contents(&data, FileKind::Texture), data);
+	}
+
+	#[test]
+	fn texture_seek_mip() {
+		let data = texture(0, 0, &[40_000, 10_000, 2_500]);
+		let mut file = File::new(Cursor::new(encode(&data, FileKind::Texture).unwrap())).unwrap();
+
+		let smallest = 80 + 40_000 + 10_000;
+		file.seek(SeekFrom::Start(smallest)).unwrap();
+		let mut mip = Vec::new();
+		file.read_to_end(&mut mip).unwrap();
+		assert_eq!(mip, &data[smallest as usize..]);
+
+		file.rewind().unwrap();
+		let mut header = [0; 80];
+		file.read_exact(&mut header).unwrap();
+		assert_eq!(header, data[..80]);
+	}
+
+	#[test]
+	fn texture_cube() {
+		let data = texture(1 << 25, 0, &[6 * 4_000, 6 * 1_000]);
+		assert_eq!(round_trip(&data, FileKind::Texture), data);
+	}
+
+	#[test]
+	fn model() {
+		let (stack, runtime) = (100u32, 20_000u32);
+		let vertex_sizes = [3_000u32, 500, 0];
+		let index_sizes = [17_000u32, 200, 0];
+
+		let mut offset = 0x44 + stack + runtime;
+		let mut vertex_offsets = [0u32; 3];
+		let mut index_offsets = [0u32; 3];
+		for lod in 0..2 {
+			vertex_offsets[lod] = offset;
+			offset += vertex_sizes[lod];
+			index_offsets[lod] = offset;
+			offset += index_sizes[lod];
+		}
+
+		let mut data = Vec::new();
+		for value in [5, stack, runtime] {
+			data.extend_from_slice(&value.to_le_bytes());
+		}
+		data.extend_from_slice(&3u16.to_le_bytes());
+		data.extend_from_slice(&2u16.to_le_bytes());
+		for values in [vertex_offsets, index_offsets, vertex_sizes, index_sizes] {
+			for value in values {
+				data.extend_from_slice(&value.to_le_bytes());
+			}
+		}
+		data.extend_from_slice(&[2, 1, 0, 0]);
+		data.extend(payload((offset - 0x44) as usize));
+
+		assert_eq!(round_trip(&data, FileKind::Model), data);
+	}
+
+	#[test]
+	fn model_gap() {
+		let mut data = vec![0; 0x44];
+		data[4] = 4;
+		data.extend_from_slice(&[0; 8]);
+		assert!(encode(&data, FileKind::Model).is_err());
+	}
+}
@@ -1,8 +1,16 @@
 mod empty;
 mod file;
 mod model;
+mod raw;
 mod shared;
 mod standard;
 mod texture;
 
-pub use file::File;
+pub(crate) use empty::RsfTable;
+
+pub use {
+	empty::{Placeholder, RsfKey},
+	file::{encode, File},
+	raw::{RawBlocks, RawFile},
+	shared::{FileKind, Header},
+};
@@ -1,16 +1,21 @@
-use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
+use std::io::{Cursor, Read, Seek};
 
-use binrw::{binread, BinRead, BinWriterExt, VecArgs};
+use binrw::{binread, BinRead, BinWriterExt, Endian, VecArgs};
 
-use crate::{error::Result, sqpack::block::read_block};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	sqpack::block::{padding, read_block_metadata, write_blocks, BlockMetadata, BlockStream},
+};
 
-use super::shared::Header;
+use super::shared::{FileKind, Header};
 
 const MAX_LODS: usize = 3;
+const MDL_HEADER_SIZE: usize = 0x44;
+// Stack, runtime, and vertex, edge geometry, and index buffers for each LOD.
+const SECTION_COUNT: usize = 2 + MAX_LODS * 3;
 
 #[binread]
 #[derive(Debug)]
-#[br(little)]
 struct ModelHeader {
 	_size: SectionInfo<u32>,
 	_compressed_size: SectionInfo<u32>,
@@ -27,7 +32,6 @@ struct ModelHeader {
 
 #[binread]
 #[derive(Debug)]
-#[br(little)]
 struct SectionInfo<T: for<'a> BinRead<Args<'a> = ()> + 'static> {
 	stack: T,
 	runtime: T,
@@ -36,8 +40,29 @@ struct SectionInfo<T: for<'a> BinRead<Args<'a> = ()> + 'static> {
 	index_buffer: [T; MAX_LODS],
 }
 
-pub fn read(mut reader: impl Read + Seek, offset: u32, header: Header) -> Result<Cursor<Vec<u8>>> {
-	let model_header = ModelHeader::read(&mut reader)?;
+#[binread]
+#[derive(Debug)]
+#[br(little)]
+struct MdlHeader {
+	version: u32,
+	stack_size: u32,
+	runtime_size: u32,
+	vertex_declaration_count: u16,
+	material_count: u16,
+	vertex_offsets: [u32; MAX_LODS],
+	index_offsets: [u32; MAX_LODS],
+	vertex_buffer_sizes: [u32; MAX_LODS],
+	index_buffer_sizes: [u32; MAX_LODS],
+	lod_count: u8,
+	index_buffer_streaming_enabled: u8,
+	edge_geometry_enabled: u8,
+}
+
+fn read_block_table(
+	reader: &mut (impl Read + Seek),
+	endian: Endian,
+) -> Result<(ModelHeader, Vec<u16>)> {
+	let model_header = ModelHeader::read_options(reader, endian, ())?;
 
 	// Model header is followed by an array of block sizes.
 	let block_counts = &model_header.block_count;
@@ -48,40 +73,54 @@ pub fn read(mut reader: impl Read + Seek, offset: u32, header: Header) -> Result
 		+ block_counts.index_buffer.iter().sum::<u16>();
 
 	// TODO: i should probably make an impl for this it's pretty repetetive
-	let block_sizes = <Vec<u16>>::read_le_args(
-		&mut reader,
+	let block_sizes = <Vec<u16>>::read_options(
+		reader,
+		endian,
 		VecArgs {
 			count: total_blocks.try_into().unwrap(),
 			inner: (),
 		},
 	)?;
 
-	// Build a writer for the output file.
-	let mut writer = Cursor::new(Vec::<u8>::with_capacity(
-		header.raw_file_size.try_into().unwrap(),
-	));
+	Ok((model_header, block_sizes))
+}
+
+pub fn read<R: Read + Seek>(
+	mut reader: R,
+	offset: u32,
+	header: Header,
+	endian: Endian,
+) -> Result<BlockStream<R>> {
+	let (model_header, block_sizes) = read_block_table(&mut reader, endian)?;
 
-	// First 0x44 is the header, which will be filled at the end
-	writer.seek(SeekFrom::Start(0x44))?;
+	// Only the block headers are read here - payloads are decompressed lazily, so
+	// consumers can seek to a single LOD's buffers without inflating the rest.
+	// The first 0x44 bytes of output are the header, which is built at the end.
+	let mut metadata = Vec::with_capacity(block_sizes.len());
+	let mut read_section = |block_count: u16, block_index: u16, section_offset: u32| {
+		read_section(
+			block_count,
+			block_index,
+			offset + section_offset,
+			&block_sizes,
+			&mut reader,
+			&mut metadata,
+			endian,
+		)
+	};
 
 	// Stack
-	let stack_size = read_blocks(
+	let (_, stack_size) = read_section(
 		model_header.block_count.stack,
 		model_header.block_index.stack,
-		offset + model_header.offset.stack,
-		&block_sizes,
-		&mut reader,
-		&mut writer,
+		model_header.offset.stack,
 	)?;
 
 	// Runtime
-	let runtime_size = read_blocks(
+	let (_, runtime_size) = read_section(
 		model_header.block_count.runtime,
 		model_header.block_index.runtime,
-		offset + model_header.offset.runtime,
-		&block_sizes,
-		&mut reader,
-		&mut writer,
+		model_header.offset.runtime,
 	)?;
 
 	// LOD level data
@@ -95,93 +134,239 @@ pub fn read(mut reader: impl Read + Seek, offset: u32, header: Header) -> Result
 		// Vertex buffer
 		let block_count = model_header.block_count.vertex_buffer[lod_index];
 		if block_count != 0 {
-			if lod_index == 0 || block_count > 0 {
-				vertex_data_offsets[lod_index] = writer.position().try_into().unwrap();
-			}
-
-			vertex_buffer_sizes[lod_index] = read_blocks(
+			(
+				vertex_data_offsets[lod_index],
+				vertex_buffer_sizes[lod_index],
+			) = read_section(
 				block_count,
 				model_header.block_index.vertex_buffer[lod_index],
-				offset + model_header.offset.vertex_buffer[lod_index],
-				&block_sizes,
-				&mut reader,
-				&mut writer,
+				model_header.offset.vertex_buffer[lod_index],
 			)?;
 		}
 
 		// Edge geometry vertex buffer
 		let block_count = model_header.block_count.edge_geometry_vertex_buffer[lod_index];
 		if block_count != 0 {
-			read_blocks(
+			read_section(
 				block_count,
 				model_header.block_index.edge_geometry_vertex_buffer[lod_index],
-				offset + model_header.offset.edge_geometry_vertex_buffer[lod_index],
-				&block_sizes,
-				&mut reader,
-				&mut writer,
+				model_header.offset.edge_geometry_vertex_buffer[lod_index],
 			)?;
 		}
 
 		// Index buffer
 		let block_count = model_header.block_count.index_buffer[lod_index];
 		if block_count != 0 {
-			if lod_index == 0 || block_count > 0 {
-				index_data_offsets[lod_index] = writer.position().try_into().unwrap();
-			}
-
-			index_buffer_sizes[lod_index] = read_blocks(
+			(index_data_offsets[lod_index], index_buffer_sizes[lod_index]) = read_section(
 				block_count,
 				model_header.block_index.index_buffer[lod_index],
-				offset + model_header.offset.index_buffer[lod_index],
-				&block_sizes,
-				&mut reader,
-				&mut writer,
+				model_header.offset.index_buffer[lod_index],
 			)?;
 		}
 	}
 
 	// Write out the header now we've collected the info for it.
 	// TODO: While these values do work, it's technically not a match with the game's own format - the `_size` property in the header has the correct final values, but they're 0-padded, leading to larger sizes than we get with this method. Look into fixing this up to get as close to 1:1 as possible.
-	writer.rewind()?;
-	writer.write_le(&header.block_count)?; // version
-	writer.write_le(&stack_size)?;
-	writer.write_le(&runtime_size)?;
-	writer.write_le(&model_header.vertex_declaration_count)?;
-	writer.write_le(&model_header.material_count)?;
-	writer.write_le(&vertex_data_offsets)?;
-	writer.write_le(&index_data_offsets)?;
-	writer.write_le(&vertex_buffer_sizes)?;
-	writer.write_le(&index_buffer_sizes)?;
-	writer.write_le(&model_header.lod_count)?;
-	writer.write_le(&model_header.index_buffer_streaming_enabled)?;
-	writer.write_le(&model_header.edge_geometry_enabled)?;
-	writer.write_le(&0u8)?;
-
-	// TODO: Look into lazy reading this. Can probably read LODs lazily?
-	writer.rewind()?;
-	Ok(writer)
+	let mut writer = Cursor::new(Vec::<u8>::with_capacity(MDL_HEADER_SIZE));
+	writer.write_type(&header.block_count, endian)?; // version
+	writer.write_type(&stack_size, endian)?;
+	writer.write_type(&runtime_size, endian)?;
+	writer.write_type(&model_header.vertex_declaration_count, endian)?;
+	writer.write_type(&model_header.material_count, endian)?;
+	writer.write_type(&vertex_data_offsets, endian)?;
+	writer.write_type(&index_data_offsets, endian)?;
+	writer.write_type(&vertex_buffer_sizes, endian)?;
+	writer.write_type(&index_buffer_sizes, endian)?;
+	writer.write_type(&model_header.lod_count, endian)?;
+	writer.write_type(&model_header.index_buffer_streaming_enabled, endian)?;
+	writer.write_type(&model_header.edge_geometry_enabled, endian)?;
+	writer.write_type(&0u8, endian)?;
+
+	Ok(BlockStream::new(reader, 0, metadata).with_prefix(writer.into_inner()))
 }
 
-fn read_blocks(
+// Read the block headers for a section, appending their metadata directly
+// after any existing blocks. Returns the output offset and size of the section.
+fn read_section(
 	block_count: u16,
 	block_index: u16,
 	section_offset: u32,
 	block_sizes: &[u16],
 	reader: &mut (impl Read + Seek),
-	writer: &mut impl Write,
-) -> Result<u32> {
-	let size = (0..block_count)
-		// Calculate the offsets for the blocks.
-		.scan(section_offset, |offset, index| {
-			let current_offset = *offset;
-			*offset += u32::from(block_sizes[usize::from(block_index + index)]);
-			Some(current_offset)
-		})
-		// Read the blocks into the cursor, recording the read byte count.
-		.try_fold(0u32, |size, offset| -> Result<u32> {
-			let bytes_read = io::copy(&mut read_block(reader, offset)?, writer)?;
-			Ok(size + u32::try_from(bytes_read).unwrap())
-		})?;
-
-	Ok(size)
+	metadata: &mut Vec<BlockMetadata>,
+	endian: Endian,
+) -> Result<(u32, u32)> {
+	let start = metadata.last().map_or(MDL_HEADER_SIZE, |meta| {
+		meta.output_offset + meta.output_size
+	});
+
+	let mut block_offset = section_offset;
+	let mut output_offset = start;
+	for index in 0..block_count {
+		let block_metadata = read_block_metadata(reader, block_offset, output_offset, endian)?;
+		output_offset += block_metadata.output_size;
+		metadata.push(block_metadata);
+		block_offset += u32::from(block_sizes[usize::from(block_index + index)]);
+	}
+
+	Ok((
+		u32::try_from(start).unwrap(),
+		u32::try_from(output_offset - start).unwrap(),
+	))
+}
+
+/// Get the offsets of the headers of each block in the file.
+pub fn block_offsets(
+	mut reader: impl Read + Seek,
+	offset: u32,
+	endian: Endian,
+) -> Result<Vec<u32>> {
+	let (model_header, block_sizes) = read_block_table(&mut reader, endian)?;
+
+	let sections = [model_header.offset.stack, model_header.offset.runtime]
+		.into_iter()
+		.chain(model_header.offset.vertex_buffer)
+		.chain(model_header.offset.edge_geometry_vertex_buffer)
+		.chain(model_header.offset.index_buffer);
+
+	let block_info = |info: &SectionInfo<u16>| {
+		[info.stack, info.runtime]
+			.into_iter()
+			.chain(info.vertex_buffer)
+			.chain(info.edge_geometry_vertex_buffer)
+			.chain(info.index_buffer)
+			.collect::<Vec<_>>()
+	};
+	let block_indexes = block_info(&model_header.block_index);
+	let block_counts = block_info(&model_header.block_count);
+
+	let mut offsets = Vec::with_capacity(block_sizes.len());
+	for (section_offset, (block_index, block_count)) in
+		sections.zip(block_indexes.into_iter().zip(block_counts))
+	{
+		let mut block_offset = offset + section_offset;
+		for index in block_index..block_index + block_count {
+			offsets.push(block_offset);
+			block_offset += u32::from(block_sizes[usize::from(index)]);
+		}
+	}
+
+	Ok(offsets)
+}
+
+pub fn write(data: &[u8]) -> Result<Vec<u8>> {
+	let invalid = |reason: &str| {
+		Error::Invalid(
+			ErrorValue::Other("model".into()),
+			format!("cannot encode model: {reason}"),
+		)
+	};
+
+	if data.len() < MDL_HEADER_SIZE {
+		return Err(invalid("file is smaller than the model header"));
+	}
+	let mdl_header = MdlHeader::read(&mut Cursor::new(data))?;
+
+	// Sections are rebuilt back-to-back when reading, so must be contiguous in
+	// the source file. Edge geometry has no recorded size, and is not supported.
+	// Offsets recorded for empty buffers are not preserved.
+	let mut position = MDL_HEADER_SIZE;
+	let mut take = |offset: Option<u32>, size: u32| -> Result<&[u8]> {
+		let size = usize::try_from(size).unwrap();
+		if size == 0 {
+			return Ok(&[]);
+		}
+		if offset.is_some_and(|offset| usize::try_from(offset).unwrap() != position) {
+			return Err(invalid("sections are not contiguous"));
+		}
+		let section = data
+			.get(position..position + size)
+			.ok_or_else(|| invalid("section extends past the end of the file"))?;
+		position += size;
+		Ok(section)
+	};
+
+	let mut sections = [&[][..]; SECTION_COUNT];
+	sections[0] = take(None, mdl_header.stack_size)?;
+	sections[1] = take(None, mdl_header.runtime_size)?;
+	for lod in 0..MAX_LODS {
+		sections[2 + lod] = take(
+			Some(mdl_header.vertex_offsets[lod]),
+			mdl_header.vertex_buffer_sizes[lod],
+		)?;
+		sections[2 + MAX_LODS * 2 + lod] = take(
+			Some(mdl_header.index_offsets[lod]),
+			mdl_header.index_buffer_sizes[lod],
+		)?;
+	}
+
+	if position != data.len() {
+		return Err(invalid("file contains data outside known sections"));
+	}
+
+	// Write each section's blocks, recording their layout for the header.
+	let mut block_data = Vec::new();
+	let mut block_sizes = Vec::<u16>::new();
+	let mut sizes = [0u32; SECTION_COUNT];
+	let mut compressed_sizes = [0u32; SECTION_COUNT];
+	let mut offsets = [0u32; SECTION_COUNT];
+	let mut block_indexes = [0u16; SECTION_COUNT];
+	let mut block_counts = [0u16; SECTION_COUNT];
+
+	for (index, section) in sections.iter().enumerate() {
+		let offset = block_data.len();
+		let blocks = write_blocks(&mut block_data, section)?;
+
+		sizes[index] = u32::try_from(section.len()).unwrap();
+		compressed_sizes[index] = u32::try_from(block_data.len() - offset).unwrap();
+		offsets[index] = u32::try_from(offset).unwrap();
+		block_indexes[index] = u16::try_from(block_sizes.len()).unwrap();
+		block_counts[index] = u16::try_from(blocks.len()).unwrap();
+		block_sizes.extend(
+			blocks
+				.into_iter()
+				.map(|(size, _)| u16::try_from(size).unwrap()),
+		);
+	}
+
+	let table_size = Header::SIZE + SECTION_COUNT * (4 * 3 + 2 * 2) + 8 + block_sizes.len() * 2;
+	let header_size = table_size + padding(table_size);
+
+	// Models record their version in place of the block count.
+	let mut buffer = Vec::with_capacity(header_size + block_data.len());
+	Header::write(
+		&mut buffer,
+		FileKind::Model,
+		header_size,
+		data.len(),
+		block_data.len(),
+		usize::try_from(mdl_header.version).unwrap(),
+	);
+
+	for values in [sizes, compressed_sizes, offsets] {
+		for value in values {
+			buffer.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+	for values in [block_indexes, block_counts] {
+		for value in values {
+			buffer.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+	buffer.extend_from_slice(&mdl_header.vertex_declaration_count.to_le_bytes());
+	buffer.extend_from_slice(&mdl_header.material_count.to_le_bytes());
+	buffer.extend_from_slice(&[
+		mdl_header.lod_count,
+		mdl_header.index_buffer_streaming_enabled,
+		mdl_header.edge_geometry_enabled,
+		0,
+	]);
+	for size in block_sizes {
+		buffer.extend_from_slice(&size.to_le_bytes());
+	}
+
+	buffer.resize(header_size, 0);
+	buffer.extend_from_slice(&block_data);
+
+	Ok(buffer)
 }
@@ -0,0 +1,116 @@
+use std::{
+	io::{Read, Seek},
+	slice,
+};
+
+use binrw::{BinRead, Endian};
+
+use crate::{
+	error::Result,
+	sqpack::{
+		block::{read_raw_block, BlockHeader},
+		platform::Platform,
+	},
+};
+
+use super::{
+	empty, model,
+	shared::{FileKind, Header},
+	standard, texture,
+};
+
+/// A file read from a SqPack dat archive, without decompressing its blocks.
+///
+/// Raw files are suited to copying data between archives, or into other
+/// formats using SqPack blocks, without the cost of inflating and re-deflating
+/// the payloads.
+#[derive(Debug)]
+pub struct RawFile<R> {
+	reader: R,
+	endian: Endian,
+	header: Header,
+	prefix: Vec<u8>,
+	block_offsets: Vec<u32>,
+}
+
+impl<R: Read + Seek> RawFile<R> {
+	/// Create a new raw file from the SqPack stored data in the given stream.
+	pub fn new(reader: R) -> Result<Self> {
+		Self::for_platform(reader, Platform::Win32)
+	}
+
+	/// Create a new raw file from the SqPack stored data built for the
+	/// specified platform in the given stream.
+	pub fn for_platform(mut reader: R, platform: Platform) -> Result<Self> {
+		let endian = platform.endian();
+		let header = Header::read_options(&mut reader, endian, ())?;
+
+		let (prefix, block_offsets) = match header.kind {
+			FileKind::Empty => (empty::read_data(&mut reader, &header)?, Vec::new()),
+			FileKind::Standard => (
+				Vec::new(),
+				standard::block_offsets(&mut reader, header.size, &header, endian)?,
+			),
+			FileKind::Model => (
+				Vec::new(),
+				model::block_offsets(&mut reader, header.size, endian)?,
+			),
+			FileKind::Texture => texture::block_offsets(&mut reader, header.size, &header, endian)?,
+		};
+
+		Ok(Self {
+			reader,
+			endian,
+			header,
+			prefix,
+			block_offsets,
+		})
+	}
+
+	/// Header of the file.
+	pub fn header(&self) -> &Header {
+		&self.header
+	}
+
+	/// Kind of the file, determining how its blocks are laid out.
+	pub fn kind(&self) -> FileKind {
+		self.header.kind
+	}
+
+	/// Uncompressed data stored outside the file's blocks. This contains the
+	/// `.tex` header of textures, and the payload of empty entries.
+	pub fn prefix(&self) -> &[u8] {
+		&self.prefix
+	}
+
+	/// Iterate over the file's blocks, in the order they are stored. Each item
+	/// contains the block's header, and its payload as stored.
+	pub fn blocks(&mut self) -> RawBlocks<'_, R> {
+		RawBlocks {
+			reader: &mut self.reader,
+			endian: self.endian,
+			offsets: self.block_offsets.iter(),
+		}
+	}
+}
+
+/// Iterator over the raw blocks of a [`RawFile`].
+#[derive(Debug)]
+pub struct RawBlocks<'a, R> {
+	reader: &'a mut R,
+	endian: Endian,
+	offsets: slice::Iter<'a, u32>,
+}
+
+impl<R: Read + Seek> Iterator for RawBlocks<'_, R> {
+	type Item = Result<(BlockHeader, Vec<u8>)>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let offset = *self.offsets.next()?;
+		Some(read_raw_block(self.reader, offset, self.endian).map_err(Into::into))
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		self.offsets.size_hint()
+	}
+}
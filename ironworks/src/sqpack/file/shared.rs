@@ -1,24 +1,66 @@
-use binrw::binread;
+use binrw::{binread, binrw};
 
+use crate::sqpack::block::BLOCK_ALIGNMENT;
+
+/// Header of a file stored within a SqPack dat archive.
 #[binread]
-#[derive(Debug)]
-#[br(little)]
+#[derive(Debug, Clone)]
 pub struct Header {
+	/// Size of the header, including any trailing block tables, in bytes. File
+	/// block data begins at this offset.
 	pub size: u32,
+	/// Kind of the file.
 	pub kind: FileKind,
+	/// Size of the file once decoded, in bytes.
 	pub raw_file_size: u32,
 	// num_blocks: u32,
 	// block_buffer_size: u32,
+	/// Number of blocks, or block groups, in the file. Models store their
+	/// version in this field.
 	#[br(pad_before = 8)]
 	pub block_count: u32,
 }
 
-#[binread]
-#[derive(Debug)]
-#[br(little, repr = u32)]
+/// Kind of a file stored within a SqPack dat archive, determining how its
+/// data is laid out.
+#[binrw]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[brw(repr = u32)]
 pub enum FileKind {
+	/// Placeholder entry with no readable data.
 	Empty = 1,
+	/// Generic file, stored as a sequence of blocks.
 	Standard,
+	/// Model (`.mdl`) file, with sections stored per level of detail.
 	Model,
+	/// Texture (`.tex`) file, with blocks stored per mip level.
 	Texture,
 }
+
+impl Header {
+	pub(crate) const SIZE: usize = 24;
+
+	/// Write a file header to `buffer`. `header_size` is the full size of the
+	/// header, including any trailing tables and padding.
+	pub fn write(
+		buffer: &mut Vec<u8>,
+		kind: FileKind,
+		header_size: usize,
+		raw_file_size: usize,
+		block_data_size: usize,
+		block_count: usize,
+	) {
+		// Block buffer sizes are recorded in units of the block alignment.
+		let block_units = u32::try_from(block_data_size / BLOCK_ALIGNMENT).unwrap();
+		for value in [
+			u32::try_from(header_size).unwrap(),
+			kind as u32,
+			u32::try_from(raw_file_size).unwrap(),
+			block_units,
+			block_units,
+			u32::try_from(block_count).unwrap(),
+		] {
+			buffer.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+}
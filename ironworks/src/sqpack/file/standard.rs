@@ -1,32 +1,45 @@
 use std::io::{Read, Seek, SeekFrom};
 
-use binrw::{binread, BinRead, VecArgs};
+use binrw::{binread, BinRead, Endian, VecArgs};
 
 use crate::{
 	error::Result,
-	sqpack::block::{BlockHeader, BlockMetadata, BlockStream},
+	sqpack::block::{padding, write_blocks, BlockHeader, BlockMetadata, BlockStream},
 };
 
-use super::shared::Header;
+use super::shared::{FileKind, Header};
 
 #[binread]
 #[derive(Debug)]
-#[br(little)]
 struct BlockInfo {
 	offset: u32,
 	_input_size: u16,
 	output_size: u16,
 }
 
-pub fn read<R: Read + Seek>(mut reader: R, offset: u32, header: Header) -> Result<BlockStream<R>> {
-	// Eagerly read the block info.
-	let blocks = <Vec<BlockInfo>>::read_args(
-		&mut reader,
+fn read_block_info(
+	reader: &mut (impl Read + Seek),
+	header: &Header,
+	endian: Endian,
+) -> Result<Vec<BlockInfo>> {
+	Ok(<Vec<BlockInfo>>::read_options(
+		reader,
+		endian,
 		VecArgs {
 			count: header.block_count.try_into().unwrap(),
 			inner: (),
 		},
-	)?;
+	)?)
+}
+
+pub fn read<R: Read + Seek>(
+	mut reader: R,
+	offset: u32,
+	header: Header,
+	endian: Endian,
+) -> Result<BlockStream<R>> {
+	// Eagerly read the block info.
+	let blocks = read_block_info(&mut reader, &header, endian)?;
 
 	// Closure for subsequent scan to allow cleaner error handling.
 	let mut read_block_metadata = |previous: &mut usize, info: &BlockInfo| -> Result<_> {
@@ -35,7 +48,7 @@ pub fn read<R: Read + Seek>(mut reader: R, offset: u32, header: Header) -> Resul
 
 		let header_offset = offset + info.offset;
 		reader.seek(SeekFrom::Start(header_offset.into()))?;
-		let header = BlockHeader::read(&mut reader)?;
+		let header = BlockHeader::read_options(&mut reader, endian, ())?;
 
 		Ok(BlockMetadata {
 			input_offset: (header_offset + header.size).try_into().unwrap(),
@@ -55,3 +68,46 @@ pub fn read<R: Read + Seek>(mut reader: R, offset: u32, header: Header) -> Resul
 
 	Ok(BlockStream::new(reader, 0, metadata))
 }
+
+/// Get the offsets of the headers of each block in the file.
+pub fn block_offsets(
+	mut reader: impl Read + Seek,
+	offset: u32,
+	header: &Header,
+	endian: Endian,
+) -> Result<Vec<u32>> {
+	let blocks = read_block_info(&mut reader, header, endian)?;
+	Ok(blocks.iter().map(|info| offset + info.offset).collect())
+}
+
+pub fn write(data: &[u8]) -> Result<Vec<u8>> {
+	let mut block_data = Vec::new();
+	let blocks = write_blocks(&mut block_data, data)?;
+
+	// Header is followed by the block info table, padded out to alignment.
+	let table_size = Header::SIZE + blocks.len() * 8;
+	let header_size = table_size + padding(table_size);
+
+	let mut buffer = Vec::with_capacity(header_size + block_data.len());
+	Header::write(
+		&mut buffer,
+		FileKind::Standard,
+		header_size,
+		data.len(),
+		block_data.len(),
+		blocks.len(),
+	);
+
+	let mut offset = 0;
+	for (size, output_size) in blocks {
+		buffer.extend_from_slice(&u32::try_from(offset).unwrap().to_le_bytes());
+		buffer.extend_from_slice(&u16::try_from(size).unwrap().to_le_bytes());
+		buffer.extend_from_slice(&u16::try_from(output_size).unwrap().to_le_bytes());
+		offset += size;
+	}
+
+	buffer.resize(header_size, 0);
+	buffer.extend_from_slice(&block_data);
+
+	Ok(buffer)
+}
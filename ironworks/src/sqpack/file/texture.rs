@@ -1,13 +1,18 @@
-use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 
-use binrw::{binread, BinRead, VecArgs};
+use binrw::{binread, BinRead, Endian, VecArgs};
 
-use crate::{error::Result, sqpack::block::read_block};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	sqpack::block::{padding, read_block_metadata, write_blocks, BlockStream},
+};
 
-use super::shared::Header;
+use super::shared::{FileKind, Header};
+
+const TEX_HEADER_SIZE: usize = 80;
+const MAX_SURFACES: usize = 13;
 
 #[binread]
-#[br(little)]
 #[derive(Debug)]
 struct SurfaceBlockInfo {
 	compressed_offset: u32,
@@ -18,7 +23,6 @@ struct SurfaceBlockInfo {
 }
 
 #[binread]
-#[br(little)]
 #[derive(Debug)]
 struct TexHeader {
 	attribute: u32,
@@ -26,18 +30,39 @@ struct TexHeader {
 	// width: u16,
 	// height: u16,
 	// depth: u16,
-	// mip_levels: u8,
-	#[br(pad_before = 11)]
+	#[br(pad_before = 10)]
+	mip_levels: u8,
 	array_size: u8,
 	// lod_offsets: [u32; 3],
 	#[br(pad_before = 12)]
-	surface_offsets: [u32; 13],
+	surface_offsets: [u32; MAX_SURFACES],
+}
+
+impl TexHeader {
+	// Each entry in an array of surfaces has a seperate top-level surface block
+	// for each defined mip level. Check `file/tex` for the full definition of the
+	// bitset being queried in this block.
+	fn array_size(&self) -> usize {
+		match self.attribute {
+			// Cube textures always have precisely 6 array items.
+			attribute if (attribute >> 25) & 1 == 1 => 6,
+			// 2D texture arrays have N array items, as specified by the header.
+			attribute if (attribute >> 28) & 1 == 1 => usize::from(self.array_size),
+			// All other texture kinds do not utilise arrays (have 1 entry).
+			_ => 1,
+		}
+	}
 }
 
-pub fn read(mut reader: impl Read + Seek, offset: u32, header: Header) -> Result<Cursor<Vec<u8>>> {
+fn read_block_table(
+	reader: &mut (impl Read + Seek),
+	header: &Header,
+	endian: Endian,
+) -> Result<(Vec<SurfaceBlockInfo>, Vec<u16>)> {
 	// Eagerly read the block info.
-	let blocks = <Vec<SurfaceBlockInfo>>::read_args(
-		&mut reader,
+	let blocks = <Vec<SurfaceBlockInfo>>::read_options(
+		reader,
+		endian,
 		VecArgs {
 			count: header.block_count.try_into().unwrap(),
 			inner: (),
@@ -49,76 +74,202 @@ pub fn read(mut reader: impl Read + Seek, offset: u32, header: Header) -> Result
 		.iter()
 		.fold(0, |total, block| total + block.block_count);
 
-	let sub_block_offsets = <Vec<u16>>::read_le_args(
-		&mut reader,
+	let sub_block_offsets = <Vec<u16>>::read_options(
+		reader,
+		endian,
 		VecArgs {
 			count: sub_block_count.try_into().unwrap(),
 			inner: (),
 		},
 	)?;
 
-	// Create a writer with capacity for the full file.
-	let mut writer = Cursor::new(Vec::<u8>::with_capacity(
-		header.raw_file_size.try_into().unwrap(),
-	));
+	Ok((blocks, sub_block_offsets))
+}
 
-	// If the first block has an offset, it's likely that there's a .tex header
-	// outside the compressed blocks - read it in for further info, and pass it
-	// over into the writer.
-	let mut texture_header = None::<TexHeader>;
-	let raw_header_size = blocks[0].compressed_offset;
+// If the first block has an offset, it's likely that there's a .tex header
+// outside the compressed blocks - read it in, uncompressed.
+fn read_raw_header(
+	reader: &mut (impl Read + Seek),
+	offset: u32,
+	blocks: &[SurfaceBlockInfo],
+) -> Result<Vec<u8>> {
+	let mut raw_header = Vec::new();
+	let raw_header_size = blocks.first().map_or(0, |block| block.compressed_offset);
 	if raw_header_size > 0 {
 		reader.seek(SeekFrom::Start(offset.into()))?;
-		texture_header = Some(TexHeader::read(&mut reader)?);
-
-		reader.seek(SeekFrom::Start(offset.into()))?;
-		io::copy(
-			&mut reader.by_ref().take(raw_header_size.into()),
-			&mut writer,
-		)?;
+		reader
+			.take(raw_header_size.into())
+			.read_to_end(&mut raw_header)?;
 	}
+	Ok(raw_header)
+}
 
-	// Each entry in an array of surfaces has a seperate top-level surface block
-	// for each defined mip level, which means we need to know how many entries
-	// are in the array to accurately distribute the blocks across the expected
-	// mip level offsets. Check `file/tex` for the full definition of the bitset
-	// being queried in this block.
-	let array_size = match texture_header {
-		// Cube textures always have precisely 6 array items.
-		Some(TexHeader { attribute, .. }) if (attribute >> 25) & 1 == 1 => 6,
-
-		// 2D texture arrays have N array items, as specified by the header.
-		Some(TexHeader {
-			attribute,
-			array_size,
-			..
-		}) if (attribute >> 28) & 1 == 1 => usize::from(array_size),
-
-		// All other texture kinds do not utilise arrays (have 1 entry).
-		_ => 1,
+pub fn read<R: Read + Seek>(
+	mut reader: R,
+	offset: u32,
+	header: Header,
+	endian: Endian,
+) -> Result<BlockStream<R>> {
+	let (blocks, sub_block_offsets) = read_block_table(&mut reader, &header, endian)?;
+	let sub_block_count = sub_block_offsets.len();
+
+	// The raw header, if any, is kept to serve as the start of the stream, and
+	// parsed for further info.
+	let raw_header = read_raw_header(&mut reader, offset, &blocks)?;
+	let texture_header = match raw_header.is_empty() {
+		true => None,
+		false => Some(TexHeader::read_options(
+			&mut Cursor::new(&raw_header),
+			endian,
+			(),
+		)?),
 	};
 
+	// We need to know how many entries are in the array to accurately distribute
+	// the blocks across the expected mip level offsets.
+	let array_size = texture_header.as_ref().map_or(1, TexHeader::array_size);
+
+	// Only the block headers are read here - payloads are decompressed lazily, so
+	// consumers can seek to a single mip level without inflating the rest.
+	let mut metadata = Vec::with_capacity(sub_block_count);
+	let mut output_offset = raw_header.len();
 	for (index, block) in blocks.iter().enumerate() {
 		// Move to the expected start position of the block.
 		if let Some(ref header) = texture_header {
 			if index % array_size == 0 {
-				writer.set_position(header.surface_offsets[index / array_size].into());
+				output_offset = header.surface_offsets[index / array_size]
+					.try_into()
+					.unwrap();
 			}
 		}
 
-		// Read each sub-block into the writer.
 		let mut data_offset = block.compressed_offset + offset;
 		for sub_block_offset in sub_block_offsets
 			.iter()
 			.skip(usize::try_from(block.block_offset).unwrap())
 			.take(usize::try_from(block.block_count).unwrap())
 		{
-			io::copy(&mut read_block(&mut reader, data_offset)?, &mut writer)?;
+			let block_metadata =
+				read_block_metadata(&mut reader, data_offset, output_offset, endian)?;
+			output_offset += block_metadata.output_size;
+			metadata.push(block_metadata);
+			data_offset += u32::from(*sub_block_offset);
+		}
+	}
+
+	Ok(BlockStream::new(reader, 0, metadata).with_prefix(raw_header))
+}
+
+/// Get the uncompressed texture header preceding the blocks, and the offsets
+/// of the headers of each block in the file.
+pub fn block_offsets(
+	mut reader: impl Read + Seek,
+	offset: u32,
+	header: &Header,
+	endian: Endian,
+) -> Result<(Vec<u8>, Vec<u32>)> {
+	let (blocks, sub_block_offsets) = read_block_table(&mut reader, header, endian)?;
+	let raw_header = read_raw_header(&mut reader, offset, &blocks)?;
+
+	let mut offsets = Vec::with_capacity(sub_block_offsets.len());
+	for block in &blocks {
+		let mut data_offset = block.compressed_offset + offset;
+		for sub_block_offset in sub_block_offsets
+			.iter()
+			.skip(usize::try_from(block.block_offset).unwrap())
+			.take(usize::try_from(block.block_count).unwrap())
+		{
+			offsets.push(data_offset);
 			data_offset += u32::from(*sub_block_offset);
 		}
 	}
 
-	// TODO: is there a reasonable way to lazy read a texture?
-	writer.rewind()?;
-	Ok(writer)
+	Ok((raw_header, offsets))
+}
+
+pub fn write(data: &[u8]) -> Result<Vec<u8>> {
+	let invalid = |reason: &str| {
+		Error::Invalid(
+			ErrorValue::Other("texture".into()),
+			format!("cannot encode texture: {reason}"),
+		)
+	};
+
+	if data.len() < TEX_HEADER_SIZE {
+		return Err(invalid("file is smaller than the texture header"));
+	}
+	let texture_header = TexHeader::read_le(&mut Cursor::new(data))?;
+
+	let mip_levels = usize::from(texture_header.mip_levels).clamp(1, MAX_SURFACES);
+	let array_size = texture_header.array_size().max(1);
+
+	// The raw texture header precedes the compressed surface blocks. Surfaces
+	// must directly follow it, as padding is not preserved.
+	let first_surface = usize::try_from(texture_header.surface_offsets[0]).unwrap();
+	if first_surface != TEX_HEADER_SIZE {
+		return Err(invalid("surface data does not directly follow the header"));
+	}
+
+	// Build the list of surfaces, in mip-major order. Each mip level extends to
+	// the start of the next, and is shared evenly between the array's entries.
+	let mut surfaces = Vec::with_capacity(mip_levels * array_size);
+	for mip in 0..mip_levels {
+		let start = usize::try_from(texture_header.surface_offsets[mip]).unwrap();
+		let end = match mip + 1 < mip_levels {
+			true => usize::try_from(texture_header.surface_offsets[mip + 1]).unwrap(),
+			false => data.len(),
+		};
+
+		if start < TEX_HEADER_SIZE || start > end || end > data.len() {
+			return Err(invalid("surface offsets are out of order or bounds"));
+		}
+		if (end - start) % array_size != 0 {
+			return Err(invalid("mip level size is not divisible by the array size"));
+		}
+
+		let surface_size = (end - start) / array_size;
+		surfaces.extend(
+			(0..array_size).map(|index| &data[start + index * surface_size..][..surface_size]),
+		);
+	}
+
+	let mut block_data = data[..TEX_HEADER_SIZE].to_vec();
+	let mut surface_infos = Vec::with_capacity(surfaces.len());
+	let mut sub_block_sizes = Vec::new();
+	for surface in surfaces {
+		let compressed_offset = block_data.len();
+		let blocks = write_blocks(&mut block_data, surface)?;
+		surface_infos.push([
+			u32::try_from(compressed_offset).unwrap(),
+			u32::try_from(block_data.len() - compressed_offset).unwrap(),
+			u32::try_from(surface.len()).unwrap(),
+			u32::try_from(sub_block_sizes.len()).unwrap(),
+			u32::try_from(blocks.len()).unwrap(),
+		]);
+		sub_block_sizes.extend(blocks.into_iter().map(|(size, _)| size));
+	}
+
+	let table_size = Header::SIZE + surface_infos.len() * 20 + sub_block_sizes.len() * 2;
+	let header_size = table_size + padding(table_size);
+
+	let mut buffer = Vec::with_capacity(header_size + block_data.len());
+	Header::write(
+		&mut buffer,
+		FileKind::Texture,
+		header_size,
+		data.len(),
+		block_data.len(),
+		surface_infos.len(),
+	);
+	for value in surface_infos.into_iter().flatten() {
+		buffer.extend_from_slice(&value.to_le_bytes());
+	}
+	for size in sub_block_sizes {
+		buffer.extend_from_slice(&u16::try_from(size).unwrap().to_le_bytes());
+	}
+
+	buffer.resize(header_size, 0);
+	buffer.extend_from_slice(&block_data);
+
+	Ok(buffer)
 }
@@ -1,17 +1,26 @@
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::io::Cursor;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use binrw::BinRead;
 use getset::CopyGetters;
 
+#[cfg(feature = "async")]
+use crate::sqpack::AsyncResource;
 use crate::{
 	error::{Error, ErrorValue, Result},
-	sqpack::Resource,
+	sqpack::{Platform, Resource},
 };
 
-use super::{index1::Index1, index2::Index2, shared::FileMetadata};
+use super::{
+	index1::Index1,
+	index2::Index2,
+	shared::FileMetadata,
+	table::{IndexTable, PassThroughMap},
+};
 
 /// Specifier of a file location within a SqPack category.
-#[derive(Debug, CopyGetters)]
+#[derive(Debug, Clone, CopyGetters)]
 #[get_copy = "pub"]
 pub struct Location {
 	/// SqPack chunk the file is in, i.e. `0000XX.win32.dat1`.
@@ -23,6 +32,55 @@ pub struct Location {
 	/// Estimated size of the target file, if known. This will typically err on
 	/// the larger side, as files commonly have some amount of padding at the end.
 	size: Option<u32>,
+	/// Platform the file was built for, as recorded by the index.
+	platform: Platform,
+}
+
+impl Location {
+	fn new(chunk: u8, platform: Platform, metadata: FileMetadata, size: Option<u32>) -> Self {
+		Self {
+			chunk,
+			data_file: metadata.data_file_id,
+			offset: metadata.offset,
+			size,
+			platform,
+		}
+	}
+}
+
+/// Hash identifying a file within a SqPack index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum IndexHash {
+	/// Index1 hash, formed of separate hashes of the directory and file name.
+	Path {
+		/// Hash of the directory path, i.e. `chara/equipment/e0001/model`.
+		directory: u32,
+		/// Hash of the file name, i.e. `c0101e0001_top.mdl`.
+		file: u32,
+	},
+	/// Index2 hash of the full file path.
+	Full(u32),
+}
+
+/// Raw entry read from a SqPack index.
+#[derive(Debug, Clone, CopyGetters)]
+#[get_copy = "pub"]
+pub struct IndexEntry {
+	/// Hash identifying the file.
+	hash: IndexHash,
+	/// Whether the hash collides with another file. Synonym entries do not
+	/// point to valid file data, and must be disambiguated by full path.
+	is_synonym: bool,
+	/// Location of the file's data.
+	#[getset(skip)]
+	location: Location,
+}
+
+impl IndexEntry {
+	/// Location of the file's data.
+	pub fn location(&self) -> &Location {
+		&self.location
+	}
 }
 
 #[derive(Debug)]
@@ -33,9 +91,10 @@ pub struct Index<R> {
 	resource: Arc<R>,
 	max_chunk: Mutex<Option<u16>>,
 	chunks: Mutex<Vec<Arc<IndexChunk>>>,
+	lookup: OnceLock<ChunkLookup>,
 }
 
-impl<R: Resource> Index<R> {
+impl<R> Index<R> {
 	pub fn new(repository: u8, category: u8, resource: Arc<R>) -> Result<Self> {
 		Ok(Self {
 			repository,
@@ -43,32 +102,50 @@ impl<R: Resource> Index<R> {
 			resource,
 			max_chunk: None.into(),
 			chunks: Vec::new().into(),
+			lookup: OnceLock::new(),
 		})
 	}
 
+	fn cache_chunk(&self, index: usize, chunk: IndexChunk) -> Arc<IndexChunk> {
+		let mut guard = self.chunks.lock().unwrap();
+		// Another caller may have loaded this chunk while we were building it.
+		if let Some(existing) = guard.get(index) {
+			return existing.clone();
+		}
+		guard.insert(index, chunk.into());
+		guard[index].clone()
+	}
+}
+
+impl<R: Resource> Index<R> {
 	pub fn find(&self, path: &str) -> Result<Location> {
-		let location = self.chunks().find_map(|chunk| {
-			let (index, chunk) = match chunk {
-				Ok(value) => value,
-				Err(error) => return Some(Err(error)),
-			};
+		if let Some(lookup) = self.lookup.get() {
+			return lookup.find(path);
+		}
 
-			match chunk.find(path) {
-				Err(Error::NotFound(_)) => None,
-				Err(error) => Some(Err(error)),
-				Ok((meta, size)) => Some(Ok(Location {
-					chunk: index,
-					data_file: meta.data_file_id,
-					offset: meta.offset,
-					size,
-				})),
-			}
-		});
+		// Lookups are routed across all chunks at once, so they all need to be loaded.
+		let chunks = self
+			.chunks()
+			.map(|chunk| chunk.map(|(_, chunk)| chunk))
+			.collect::<Result<Vec<_>>>()?;
+
+		self.lookup
+			.get_or_init(|| ChunkLookup::new(chunks))
+			.find(path)
+	}
 
-		match location {
-			None => Err(Error::NotFound(ErrorValue::Path(path.into()))),
-			Some(result) => result,
+	/// Get every entry in the index, across all chunks.
+	pub fn entries(&self) -> Result<Vec<IndexEntry>> {
+		let mut entries = Vec::new();
+		for chunk in self.chunks() {
+			let (index, chunk) = chunk?;
+			entries.extend(chunk.entries().map(|(hash, metadata, size)| IndexEntry {
+				hash,
+				is_synonym: metadata.is_synonym,
+				location: Location::new(index, chunk.platform(), metadata, size),
+			}));
 		}
+		Ok(entries)
 	}
 
 	fn chunks(&self) -> impl Iterator<Item = Result<(u8, Arc<IndexChunk>)>> + '_ {
@@ -98,11 +175,7 @@ impl<R: Resource> Index<R> {
 
 			match chunk {
 				// Found an index - save it out to the cache.
-				Ok(chunk) => {
-					let mut guard = self.chunks.lock().unwrap();
-					guard.insert(index_usize, chunk.into());
-					Some(Ok((index_u8, guard[index_usize].clone())))
-				}
+				Ok(chunk) => Some(Ok((index_u8, self.cache_chunk(index_usize, chunk)))),
 
 				// No index was found for this chunk - mark index as the max chunk point so we don't do that again.
 				Err(Error::NotFound(_)) => {
@@ -117,6 +190,122 @@ impl<R: Resource> Index<R> {
 	}
 }
 
+#[cfg(feature = "async")]
+impl<R: AsyncResource> Index<R> {
+	pub async fn find_async(&self, path: &str) -> Result<Location> {
+		if let Some(lookup) = self.lookup.get() {
+			return lookup.find(path);
+		}
+
+		// This mirrors the chunk iteration in the blocking implementation - see
+		// `chunks` for details.
+		let max_chunk = self.max_chunk.lock().unwrap().unwrap_or(256);
+
+		let mut chunks = Vec::new();
+		for index in 0u16..max_chunk {
+			let index_usize = usize::from(index);
+			let index_u8 = u8::try_from(index).unwrap();
+
+			let cached = self.chunks.lock().unwrap().get(index_usize).cloned();
+			let chunk = match cached {
+				Some(chunk) => chunk,
+				None => {
+					let chunk = IndexChunk::new_async(
+						self.repository,
+						self.category,
+						index_u8,
+						&*self.resource,
+					)
+					.await;
+
+					match chunk {
+						Ok(chunk) => self.cache_chunk(index_usize, chunk),
+						Err(Error::NotFound(_)) => {
+							*self.max_chunk.lock().unwrap() = Some(index);
+							break;
+						}
+						Err(error) => return Err(error),
+					}
+				}
+			};
+
+			chunks.push(chunk);
+		}
+
+		self.lookup
+			.get_or_init(|| ChunkLookup::new(chunks))
+			.find(path)
+	}
+}
+
+// Routing table from hash keys to the chunk containing them, built once all of
+// an index's chunks have been loaded.
+#[derive(Debug)]
+struct ChunkLookup {
+	chunks: Vec<Arc<IndexChunk>>,
+	index1: PassThroughMap<u8>,
+	index2: PassThroughMap<u8>,
+}
+
+impl ChunkLookup {
+	fn new(chunks: Vec<Arc<IndexChunk>>) -> Self {
+		let mut index1 = PassThroughMap::default();
+		let mut index2 = PassThroughMap::default();
+
+		for (index, chunk) in chunks.iter().enumerate() {
+			let chunk_id = u8::try_from(index).unwrap();
+			let map = match **chunk {
+				IndexChunk::Index1(_) => &mut index1,
+				IndexChunk::Index2(_) => &mut index2,
+			};
+
+			// Earlier chunks take precedence for any keys present in multiple chunks.
+			for key in chunk.table().keys() {
+				map.entry(key).or_insert(chunk_id);
+			}
+		}
+
+		Self {
+			chunks,
+			index1,
+			index2,
+		}
+	}
+
+	fn find(&self, path: &str) -> Result<Location> {
+		let index1 = match self.index1.is_empty() {
+			true => None,
+			false => {
+				let key = Index1::key(path)?;
+				self.index1.get(&key).map(|chunk| (*chunk, key))
+			}
+		};
+
+		let index2 = match self.index2.is_empty() {
+			true => None,
+			false => {
+				let key = Index2::key(path);
+				self.index2.get(&key).map(|chunk| (*chunk, key))
+			}
+		};
+
+		let (chunk, key) = match (index1, index2) {
+			(Some(index1), Some(index2)) => index1.min(index2),
+			(found, None) | (None, found) => {
+				found.ok_or_else(|| Error::NotFound(ErrorValue::Path(path.into())))?
+			}
+		};
+
+		let index_chunk = &self.chunks[usize::from(chunk)];
+		let (metadata, size) = index_chunk
+			.table()
+			.get(key)
+			.expect("routed keys should always exist in their chunk");
+
+		Ok(Location::new(chunk, index_chunk.platform(), metadata, size))
+	}
+}
+
 #[derive(Debug)]
 enum IndexChunk {
 	Index1(Index1),
@@ -141,10 +330,45 @@ impl IndexChunk {
 			})
 	}
 
-	fn find(&self, path: &str) -> Result<(FileMetadata, Option<u32>)> {
+	#[cfg(feature = "async")]
+	async fn new_async<R: AsyncResource>(
+		repository: u8,
+		category: u8,
+		chunk: u8,
+		resource: &R,
+	) -> Result<Self> {
+		let index1 = match resource.index(repository, category, chunk).await {
+			Ok(buffer) => Index1::read(&mut Cursor::new(buffer)).map_err(Error::from),
+			Err(error) => Err(error),
+		};
+
+		if let Ok(file) = index1 {
+			return Ok(IndexChunk::Index1(file));
+		}
+
+		let buffer = resource.index2(repository, category, chunk).await?;
+		let file = Index2::read(&mut Cursor::new(buffer))?;
+		Ok(IndexChunk::Index2(file))
+	}
+
+	fn entries(&self) -> Box<dyn Iterator<Item = (IndexHash, FileMetadata, Option<u32>)> + '_> {
+		match self {
+			Self::Index1(index) => Box::new(index.entries()),
+			Self::Index2(index) => Box::new(index.entries()),
+		}
+	}
+
+	fn platform(&self) -> Platform {
+		match self {
+			Self::Index1(index) => index.platform(),
+			Self::Index2(index) => index.platform(),
+		}
+	}
+
+	fn table(&self) -> &IndexTable {
 		match self {
-			Self::Index1(index) => index.find(path),
-			Self::Index2(index) => index.find(path),
+			Self::Index1(index) => index.table(),
+			Self::Index2(index) => index.table(),
 		}
 	}
 }
@@ -1,17 +1,21 @@
-use std::{collections::BTreeSet, io::SeekFrom};
+use std::io::SeekFrom;
 
-use binrw::binread;
+use binrw::{binread, Endian};
 
-use crate::error::{Error, ErrorValue, Result};
+use crate::{
+	error::{Error, ErrorValue, Result},
+	sqpack::platform::Platform,
+	utility::crc32,
+};
 
 use super::{
-	crc::crc32,
+	index::IndexHash,
 	shared::{FileMetadata, IndexHeader, SqPackHeader},
+	table::IndexTable,
 };
 
 #[binread]
 #[derive(Debug)]
-#[br(little)]
 struct Entry {
 	hash: u64,
 	#[br(pad_after = 4)]
@@ -30,64 +34,62 @@ pub struct Index1 {
 	#[br(temp)]
 	sqpack_header: SqPackHeader,
 
-	#[br(temp, seek_before = SeekFrom::Start(sqpack_header.size.into()))]
+	#[br(calc = sqpack_header.platform)]
+	platform: Platform,
+
+	#[br(
+		temp,
+		is_big = platform.endian() == Endian::Big,
+		seek_before = SeekFrom::Start(sqpack_header.size.into()),
+	)]
 	index_header: IndexHeader,
 
 	#[br(
+		temp,
+		is_big = platform.endian() == Endian::Big,
 		seek_before = SeekFrom::Start(index_header.index_data.offset.into()),
 		count = index_header.index_data.size / Entry::SIZE,
 	)]
 	indexes: Vec<Entry>,
 
-	#[br(calc = indexes.iter().map(|entry| (
-		entry.file_metadata.data_file_id,
-		entry.file_metadata.offset
-	)).collect())]
-	offsets: BTreeSet<(u8, u32)>,
+	#[br(calc = IndexTable::new(
+		indexes
+			.into_iter()
+			.map(|entry| (entry.hash, entry.file_metadata))
+			.collect()
+	))]
+	table: IndexTable,
 }
 
 impl Index1 {
-	pub fn find(&self, path: &str) -> Result<(FileMetadata, Option<u32>)> {
-		// Calculate the Index1 hash of the path
-		let hashed_segments = path
-			.rsplitn(2, '/')
-			.map(|segment| crc32(segment.as_bytes()))
-			.collect::<Vec<_>>();
-
-		let hash = match hashed_segments[..] {
-			[file, directory] => (directory as u64) << 32 | file as u64,
-			_ => {
-				return Err(Error::Invalid(
-					ErrorValue::Path(path.into()),
-					"Paths must contain at least two segments.".into(),
-				))
-			}
-		};
+	/// Calculate the Index1 table key of a path.
+	pub fn key(path: &str) -> Result<u64> {
+		match path.rsplit_once('/') {
+			Some((directory, file)) => Ok(
+				u64::from(crc32(directory.as_bytes())) << 32 | u64::from(crc32(file.as_bytes()))
+			),
+			None => Err(Error::Invalid(
+				ErrorValue::Path(path.into()),
+				"Paths must contain at least two segments.".into(),
+			)),
+		}
+	}
 
-		// Look for a matching entry in the index table
-		// TODO: hashmap this probably
-		// TODO: i saw a neat impl that was a pass-through hasher for a map to save time on hashing small values. maybe?
-		self.indexes
-			.iter()
-			.find(|entry| entry.hash == hash)
-			.map(|entry| {
-				let metadata = entry.file_metadata.clone();
+	pub fn platform(&self) -> Platform {
+		self.platform
+	}
 
-				// Look up the offset after this meta, if any exists. The result's data
-				// file ID is double checked to ensure we don't return cross-dat offsets
-				// - this could occur if the requested file is the last file in a dat,
-				// but further dats exist.
-				let size = self
-					.offsets
-					.range((metadata.data_file_id, metadata.offset + 1)..)
-					.next()
-					.and_then(|(dat_id, offset)| match *dat_id == metadata.data_file_id {
-						true => Some(offset - metadata.offset),
-						false => None,
-					});
+	pub fn table(&self) -> &IndexTable {
+		&self.table
+	}
 
-				(metadata, size)
-			})
-			.ok_or_else(|| Error::NotFound(ErrorValue::Path(path.into())))
+	pub fn entries(&self) -> impl Iterator<Item = (IndexHash, FileMetadata, Option<u32>)> + '_ {
+		self.table.entries().map(|(key, metadata, size)| {
+			let hash = IndexHash::Path {
+				directory: (key >> 32) as u32,
+				file: key as u32,
+			};
+			(hash, metadata, size)
+		})
 	}
 }
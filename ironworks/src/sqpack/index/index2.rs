@@ -1,17 +1,17 @@
-use std::{collections::BTreeSet, io::SeekFrom};
+use std::io::SeekFrom;
 
-use binrw::binread;
+use binrw::{binread, Endian};
 
-use crate::error::{Error, ErrorValue, Result};
+use crate::{sqpack::platform::Platform, utility::crc32};
 
 use super::{
-	crc::crc32,
+	index::IndexHash,
 	shared::{FileMetadata, IndexHeader, SqPackHeader},
+	table::IndexTable,
 };
 
 #[binread]
 #[derive(Debug)]
-#[br(little)]
 struct Entry {
 	hash: u32,
 	file_metadata: FileMetadata,
@@ -28,44 +28,56 @@ pub struct Index2 {
 	#[br(temp)]
 	sqpack_header: SqPackHeader,
 
-	#[br(temp, seek_before = SeekFrom::Start(sqpack_header.size.into()))]
+	#[br(calc = sqpack_header.platform)]
+	platform: Platform,
+
+	#[br(
+		temp,
+		is_big = platform.endian() == Endian::Big,
+		seek_before = SeekFrom::Start(sqpack_header.size.into()),
+	)]
 	index_header: IndexHeader,
 
 	#[br(
-    seek_before = SeekFrom::Start(index_header.index_data.offset.into()),
-    count = index_header.index_data.size / Entry::SIZE,
-  )]
+		temp,
+		is_big = platform.endian() == Endian::Big,
+		seek_before = SeekFrom::Start(index_header.index_data.offset.into()),
+		count = index_header.index_data.size / Entry::SIZE,
+	)]
 	indexes: Vec<Entry>,
 
-	#[br(calc = indexes.iter().map(|entry| (
-		entry.file_metadata.data_file_id,
-		entry.file_metadata.offset
-	)).collect())]
-	offsets: BTreeSet<(u8, u32)>,
+	#[br(calc = IndexTable::new(
+		indexes
+			.into_iter()
+			.map(|entry| (Self::hash_key(entry.hash), entry.file_metadata))
+			.collect()
+	))]
+	table: IndexTable,
 }
 
 impl Index2 {
-	// TODO: this is almost purely duplicated with index1 - dedupe somehow?
-	pub fn find(&self, path: &str) -> Result<(FileMetadata, Option<u32>)> {
-		let hash = crc32(path.as_bytes());
+	/// Calculate the Index2 table key of a path.
+	pub fn key(path: &str) -> u64 {
+		Self::hash_key(crc32(path.as_bytes()))
+	}
 
-		self.indexes
-			.iter()
-			.find(|entry| entry.hash == hash)
-			.map(|entry| {
-				let metadata = entry.file_metadata.clone();
+	// Index2 hashes are only 32 bits - they're mirrored into the upper half of
+	// the key so the pass-through hash retains entropy across all bits.
+	fn hash_key(hash: u32) -> u64 {
+		u64::from(hash) << 32 | u64::from(hash)
+	}
+
+	pub fn platform(&self) -> Platform {
+		self.platform
+	}
 
-				let size = self
-					.offsets
-					.range((metadata.data_file_id, metadata.offset + 1)..)
-					.next()
-					.and_then(|(dat_id, offset)| match *dat_id == metadata.data_file_id {
-						true => Some(offset - metadata.offset),
-						false => None,
-					});
+	pub fn table(&self) -> &IndexTable {
+		&self.table
+	}
 
-				(metadata, size)
-			})
-			.ok_or_else(|| Error::NotFound(ErrorValue::Path(path.into())))
+	pub fn entries(&self) -> impl Iterator<Item = (IndexHash, FileMetadata, Option<u32>)> + '_ {
+		self.table
+			.entries()
+			.map(|(key, metadata, size)| (IndexHash::Full(key as u32), metadata, size))
 	}
 }
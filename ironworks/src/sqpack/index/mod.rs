@@ -1,7 +1,14 @@
-mod crc;
 mod index;
 mod index1;
 mod index2;
 mod shared;
+mod table;
+mod verify;
+mod write;
 
-pub use index::{Index, Location};
+pub use {
+	index::{Index, IndexEntry, IndexHash, Location},
+	shared::SqPackKind,
+	verify::verify_index,
+	write::{digest, finish_header, write_index1, write_index2, write_sqpack_header, WriteEntry},
+};
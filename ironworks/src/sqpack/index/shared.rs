@@ -1,44 +1,54 @@
 use std::fmt;
 
-use binrw::BinRead;
+use binrw::{BinRead, Endian};
+
+use crate::sqpack::platform::Platform;
 
 #[derive(BinRead, Debug)]
-#[br(little, magic = b"SqPack\0\0")]
+#[br(magic = b"SqPack\0\0")]
 pub struct SqPackHeader {
-	_platform_id: u8,
+	pub platform: Platform,
 	// unknown: [u8; 3],
-	#[br(pad_before = 3)]
+	// The remainder of the file uses the byte order of the platform.
+	#[br(pad_before = 3, is_big = platform.endian() == Endian::Big)]
 	pub size: u32,
+	#[br(is_big = platform.endian() == Endian::Big)]
 	_version: u32,
+	#[br(is_big = platform.endian() == Endian::Big)]
 	_kind: u32,
 }
 
 #[derive(BinRead, Debug)]
-#[br(little)]
 pub struct IndexHeader {
 	_size: u32,
 	_version: u32,
 	pub index_data: Section,
 	_data_file_count: u32,
-	_synonym_data: Section,
-	_empty_block_data: Section,
-	_dir_index_data: Section,
+	pub synonym_data: Section,
+	pub empty_block_data: Section,
+	pub dir_index_data: Section,
 	_index_type: u32,
 
 	#[br(pad_before = 656)] // reserved
 	_digest: Digest,
 }
 
+impl IndexHeader {
+	/// Size of the header, including the trailing padding.
+	pub const SIZE: usize = 0x400;
+	/// Offset of the digest of the header's contents within the header.
+	pub const DIGEST_OFFSET: usize = 0x3C0;
+}
+
 #[derive(BinRead, Debug)]
-#[br(little)]
 pub struct Section {
 	pub offset: u32,
 	pub size: u32,
-	_digest: Digest,
+	pub digest: Digest,
 }
 
-#[derive(BinRead)]
-struct Digest([u8; 64]);
+#[derive(BinRead, PartialEq, Eq)]
+pub struct Digest(pub [u8; 64]);
 
 impl fmt::Debug for Digest {
 	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -47,10 +57,17 @@ impl fmt::Debug for Digest {
 	}
 }
 
+/// Kind of a SqPack file, as recorded in its header.
+#[derive(Debug, Clone, Copy)]
+pub enum SqPackKind {
+	Dat = 1,
+	Index = 2,
+}
+
 #[derive(BinRead, Clone, Debug)]
 #[br(map = Self::read)]
 pub struct FileMetadata {
-	is_synonym: bool,
+	pub is_synonym: bool,
 	pub data_file_id: u8,
 	pub offset: u32,
 }
@@ -63,4 +80,8 @@ impl FileMetadata {
 			offset: (input & !0xF) * 0x08,
 		}
 	}
+
+	pub fn write(&self) -> u32 {
+		(self.offset / 0x08) & !0xF | u32::from(self.data_file_id) << 1 | u32::from(self.is_synonym)
+	}
 }
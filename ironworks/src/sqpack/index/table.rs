@@ -0,0 +1,118 @@
+use std::{
+	collections::{BTreeSet, HashMap},
+	hash::{BuildHasherDefault, Hasher},
+};
+
+use super::shared::FileMetadata;
+
+/// Hasher that passes precomputed hash keys through unchanged.
+#[derive(Debug, Default)]
+pub struct PassThroughHasher(u64);
+
+impl Hasher for PassThroughHasher {
+	fn finish(&self) -> u64 {
+		self.0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		// Only integer keys are expected, this is a fallback for completeness.
+		for byte in bytes {
+			self.0 = self.0.rotate_left(8) ^ u64::from(*byte);
+		}
+	}
+
+	fn write_u64(&mut self, value: u64) {
+		self.0 = value;
+	}
+}
+
+/// Map keyed by precomputed index hash keys.
+pub type PassThroughMap<V> = HashMap<u64, V, BuildHasherDefault<PassThroughHasher>>;
+
+/// Hashed table of the entries in a single index file.
+#[derive(Debug)]
+pub struct IndexTable {
+	entries: Vec<(u64, FileMetadata)>,
+	lookup: PassThroughMap<usize>,
+	offsets: BTreeSet<(u8, u32)>,
+}
+
+impl IndexTable {
+	pub fn new(entries: Vec<(u64, FileMetadata)>) -> Self {
+		let mut lookup =
+			PassThroughMap::with_capacity_and_hasher(entries.len(), Default::default());
+		for (position, (key, _)) in entries.iter().enumerate() {
+			lookup.entry(*key).or_insert(position);
+		}
+
+		let offsets = entries
+			.iter()
+			.map(|(_, metadata)| (metadata.data_file_id, metadata.offset))
+			.collect();
+
+		Self {
+			entries,
+			lookup,
+			offsets,
+		}
+	}
+
+	pub fn get(&self, key: u64) -> Option<(FileMetadata, Option<u32>)> {
+		let (_, metadata) = &self.entries[*self.lookup.get(&key)?];
+		Some((metadata.clone(), self.file_size(metadata)))
+	}
+
+	pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+		self.entries.iter().map(|(key, _)| *key)
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = (u64, FileMetadata, Option<u32>)> + '_ {
+		self.entries
+			.iter()
+			.map(|(key, metadata)| (*key, metadata.clone(), self.file_size(metadata)))
+	}
+
+	// Estimate the size of a file using the offset of the following file in the
+	// same data file, if any. The data file ID is checked to ensure we don't
+	// return cross-dat sizes - this could occur if the requested file is the last
+	// file in a dat, but further dats exist.
+	fn file_size(&self, metadata: &FileMetadata) -> Option<u32> {
+		self.offsets
+			.range((metadata.data_file_id, metadata.offset + 1)..)
+			.next()
+			.and_then(|(dat_id, offset)| match *dat_id == metadata.data_file_id {
+				true => Some(offset - metadata.offset),
+				false => None,
+			})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{super::shared::FileMetadata, IndexTable};
+
+	fn metadata(data_file_id: u8, offset: u32) -> FileMetadata {
+		FileMetadata {
+			is_synonym: false,
+			data_file_id,
+			offset,
+		}
+	}
+
+	#[test]
+	fn lookup_and_size() {
+		let table = IndexTable::new(vec![
+			(u64::MAX, metadata(0, 0x800)),
+			(1 << 32 | 1, metadata(0, 0x900)),
+			(3, metadata(1, 0x800)),
+		]);
+
+		let (found, size) = table.get(u64::MAX).unwrap();
+		assert_eq!(found.offset, 0x800);
+		assert_eq!(size, Some(0x100));
+
+		// Last file in a dat has no known size, even with following dats.
+		assert_eq!(table.get(1 << 32 | 1).unwrap().1, None);
+		assert!(table.get(2).is_none());
+	}
+}
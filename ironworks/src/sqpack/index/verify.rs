@@ -0,0 +1,63 @@
+use std::io::Cursor;
+
+use binrw::BinReaderExt;
+
+use crate::error::{Error, ErrorValue, Result};
+
+use super::{
+	shared::{Digest, IndexHeader, SqPackHeader},
+	write::digest,
+};
+
+/// Check the headers and digests of the index file contained in `data`. The
+/// `name` of the file is used to identify it in any returned error.
+pub fn verify_index(name: &str, data: &[u8]) -> Result<()> {
+	let invalid = |reason: String| Error::Invalid(ErrorValue::Other(name.into()), reason);
+
+	let mut cursor = Cursor::new(data);
+	let sqpack_header = cursor.read_le::<SqPackHeader>()?;
+	check_header(data, 0, "SqPack header").map_err(invalid)?;
+
+	let header_offset = usize::try_from(sqpack_header.size).unwrap();
+	cursor.set_position(header_offset.try_into().unwrap());
+	let index_header = cursor.read_type::<IndexHeader>(sqpack_header.platform.endian())?;
+	check_header(data, header_offset, "index header").map_err(invalid)?;
+
+	for (label, section) in [
+		("index data", &index_header.index_data),
+		("synonym data", &index_header.synonym_data),
+		("empty block data", &index_header.empty_block_data),
+		("directory index data", &index_header.dir_index_data),
+	] {
+		let start = usize::try_from(section.offset).unwrap();
+		let end = start + usize::try_from(section.size).unwrap();
+		let contents = data
+			.get(start..end)
+			.ok_or_else(|| invalid(format!("{label} extends past the end of the file")))?;
+		check_digest(contents, &section.digest, label).map_err(invalid)?;
+	}
+
+	Ok(())
+}
+
+// Headers store a digest of their leading contents near their end.
+fn check_header(data: &[u8], offset: usize, label: &str) -> Result<(), String> {
+	let header = data
+		.get(offset..offset + IndexHeader::SIZE)
+		.ok_or_else(|| format!("{label} extends past the end of the file"))?;
+
+	let mut cursor = Cursor::new(header);
+	cursor.set_position(IndexHeader::DIGEST_OFFSET.try_into().unwrap());
+	let expected = cursor
+		.read_le::<Digest>()
+		.map_err(|error| error.to_string())?;
+
+	check_digest(&header[..IndexHeader::DIGEST_OFFSET], &expected, label)
+}
+
+fn check_digest(data: &[u8], expected: &Digest, label: &str) -> Result<(), String> {
+	match digest(data) == expected.0 {
+		true => Ok(()),
+		false => Err(format!("{label} digest does not match its contents")),
+	}
+}
@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use sha1::{Digest as _, Sha1};
+
+use crate::utility::crc32;
+
+use super::shared::{FileMetadata, SqPackKind};
+
+const HEADER_SIZE: usize = 0x400;
+const DIGEST_OFFSET: usize = 0x3C0;
+const DIGEST_SIZE: usize = 64;
+const SYNONYM_PATH_SIZE: usize = 0xF0;
+
+/// Location of a file to be recorded in an index.
+#[derive(Debug)]
+pub struct WriteEntry<'a> {
+	pub path: &'a str,
+	pub data_file: u8,
+	pub offset: u32,
+}
+
+impl WriteEntry<'_> {
+	fn data(&self, is_synonym: bool) -> u32 {
+		FileMetadata {
+			is_synonym,
+			data_file_id: self.data_file,
+			offset: self.offset,
+		}
+		.write()
+	}
+}
+
+/// Build the contents of an `.index` file for the provided entries.
+pub fn write_index1(entries: &[WriteEntry], data_file_count: u8) -> Vec<u8> {
+	let hash_entries = entries.iter().map(|entry| {
+		let (directory, file) = entry.path.rsplit_once('/').unwrap_or(("", entry.path));
+		let directory_hash = crc32(directory.as_bytes());
+		let file_hash = crc32(file.as_bytes());
+		(
+			u64::from(directory_hash) << 32 | u64::from(file_hash),
+			entry,
+		)
+	});
+
+	let (hashes, synonyms) = group_hashes(hash_entries);
+
+	let mut index_data = Vec::new();
+	let mut directories = BTreeMap::<u32, (usize, usize)>::new();
+	for (hash, data) in &hashes {
+		let directory_hash = (hash >> 32) as u32;
+		let position = index_data.len();
+		directories.entry(directory_hash).or_insert((position, 0)).1 += 16;
+
+		index_data.extend_from_slice(&hash.to_le_bytes());
+		index_data.extend_from_slice(&data.to_le_bytes());
+		index_data.extend_from_slice(&0u32.to_le_bytes());
+	}
+
+	let mut synonym_data = Vec::new();
+	for (index, (hash, entry)) in synonyms.iter().enumerate() {
+		write_synonym(&mut synonym_data, *hash, entry, index);
+	}
+
+	// Directory offsets are absolute within the file, and can only be resolved
+	// once the index data's position is known.
+	let index_data_offset = HEADER_SIZE * 2;
+	let mut directory_data = Vec::new();
+	for (hash, (position, size)) in directories {
+		for value in [
+			hash,
+			u32::try_from(index_data_offset + position).unwrap(),
+			u32::try_from(size).unwrap(),
+			0,
+		] {
+			directory_data.extend_from_slice(&value.to_le_bytes());
+		}
+	}
+
+	write_index(
+		0,
+		data_file_count,
+		[index_data, synonym_data, Vec::new(), directory_data],
+	)
+}
+
+/// Build the contents of an `.index2` file for the provided entries.
+pub fn write_index2(entries: &[WriteEntry], data_file_count: u8) -> Vec<u8> {
+	let hash_entries = entries
+		.iter()
+		.map(|entry| (u64::from(crc32(entry.path.as_bytes())), entry));
+
+	let (hashes, synonyms) = group_hashes(hash_entries);
+
+	let mut index_data = Vec::new();
+	for (hash, data) in &hashes {
+		index_data.extend_from_slice(&(*hash as u32).to_le_bytes());
+		index_data.extend_from_slice(&data.to_le_bytes());
+	}
+
+	let mut synonym_data = Vec::new();
+	for (index, (hash, entry)) in synonyms.iter().enumerate() {
+		write_synonym(&mut synonym_data, *hash, entry, index);
+	}
+
+	write_index(
+		2,
+		data_file_count,
+		[index_data, synonym_data, Vec::new(), Vec::new()],
+	)
+}
+
+type HashGroups<'a, 'b> = (Vec<(u64, u32)>, Vec<(u64, &'b WriteEntry<'a>)>);
+
+// Sort entries by hash, collapsing colliding hashes into a single entry flagged
+// as a synonym. Colliding entries are listed in full in the synonym table.
+fn group_hashes<'a, 'b>(
+	entries: impl Iterator<Item = (u64, &'b WriteEntry<'a>)>,
+) -> HashGroups<'a, 'b> {
+	let mut grouped = BTreeMap::<u64, Vec<&WriteEntry>>::new();
+	for (hash, entry) in entries {
+		grouped.entry(hash).or_default().push(entry);
+	}
+
+	let mut hashes = Vec::with_capacity(grouped.len());
+	let mut synonyms = Vec::new();
+	for (hash, entries) in grouped {
+		let is_synonym = entries.len() > 1;
+		hashes.push((hash, entries[0].data(is_synonym)));
+		if is_synonym {
+			synonyms.extend(entries.into_iter().map(|entry| (hash, entry)));
+		}
+	}
+
+	(hashes, synonyms)
+}
+
+fn write_synonym(buffer: &mut Vec<u8>, hash: u64, entry: &WriteEntry, index: usize) {
+	buffer.extend_from_slice(&hash.to_le_bytes());
+	buffer.extend_from_slice(&entry.data(false).to_le_bytes());
+	buffer.extend_from_slice(&u32::try_from(index).unwrap().to_le_bytes());
+
+	let mut path = entry.path.as_bytes().to_vec();
+	path.resize(SYNONYM_PATH_SIZE, 0);
+	buffer.extend_from_slice(&path);
+}
+
+fn write_index(index_type: u32, data_file_count: u8, sections: [Vec<u8>; 4]) -> Vec<u8> {
+	let mut buffer = Vec::new();
+	write_sqpack_header(&mut buffer, SqPackKind::Index);
+
+	// Sections are laid out sequentially following the index header.
+	let mut header = Vec::with_capacity(HEADER_SIZE);
+	header.extend_from_slice(&u32::try_from(HEADER_SIZE).unwrap().to_le_bytes());
+	header.extend_from_slice(&1u32.to_le_bytes());
+
+	let mut offset = HEADER_SIZE * 2;
+	for (index, section) in sections.iter().enumerate() {
+		header.extend_from_slice(&u32::try_from(offset).unwrap().to_le_bytes());
+		header.extend_from_slice(&u32::try_from(section.len()).unwrap().to_le_bytes());
+		header.extend_from_slice(&digest(section));
+		offset += section.len();
+
+		// The data file count sits between the first and second sections.
+		if index == 0 {
+			header.extend_from_slice(&u32::from(data_file_count).to_le_bytes());
+		}
+	}
+
+	header.extend_from_slice(&index_type.to_le_bytes());
+	finish_header(&mut header);
+
+	buffer.extend_from_slice(&header);
+	for section in sections {
+		buffer.extend_from_slice(&section);
+	}
+
+	buffer
+}
+
+/// Write the common SqPack header that starts all index and dat files.
+pub fn write_sqpack_header(buffer: &mut Vec<u8>, kind: SqPackKind) {
+	let mut header = Vec::with_capacity(HEADER_SIZE);
+	header.extend_from_slice(b"SqPack\0\0");
+	// Platform ID, followed by 3 bytes of padding.
+	header.extend_from_slice(&[0; 4]);
+	header.extend_from_slice(&u32::try_from(HEADER_SIZE).unwrap().to_le_bytes());
+	header.extend_from_slice(&1u32.to_le_bytes());
+	header.extend_from_slice(&(kind as u32).to_le_bytes());
+	finish_header(&mut header);
+
+	buffer.extend_from_slice(&header);
+}
+
+/// Pad a 0x400 byte header, writing a digest of its contents.
+pub fn finish_header(header: &mut Vec<u8>) {
+	header.resize(DIGEST_OFFSET, 0);
+	let digest = digest(header);
+	header.extend_from_slice(&digest);
+	header.resize(HEADER_SIZE, 0);
+}
+
+/// SHA1 digest of `data`, padded to the size of digest fields in SqPack headers.
+pub fn digest(data: &[u8]) -> [u8; DIGEST_SIZE] {
+	let mut digest = [0; DIGEST_SIZE];
+	// Empty sections are recorded with an empty digest.
+	if !data.is_empty() {
+		digest[..20].copy_from_slice(&Sha1::digest(data));
+	}
+	digest
+}
@@ -5,33 +5,23 @@ use std::{
 	path::{Path, PathBuf},
 };
 
+#[cfg(feature = "async")]
+use crate::async_resource::BoxFuture;
 use crate::{
 	error::{Error, ErrorValue, Result},
 	utility::{TakeSeekable, TakeSeekableExt},
+	version::GameVersion,
 };
 
-use super::{Location, Resource};
-
-const TRY_PATHS: &[&str] = &[
-	r"C:\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
-	r"C:\Program Files (x86)\Steam\steamapps\common\FINAL FANTASY XIV Online",
-	r"C:\Program Files (x86)\Steam\steamapps\common\FINAL FANTASY XIV - A Realm Reborn",
-	r"C:\Program Files (x86)\FINAL FANTASY XIV - A Realm Reborn",
-	r"C:\Program Files (x86)\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
-];
-
-const WSL_PREFIX: &[&str] = &["/mnt", "c"];
+#[cfg(feature = "async")]
+use super::AsyncResource;
+use super::{
+	search::{find_installs, InstallCandidate},
+	Location, Platform, Resource,
+};
 
 const SQPACK_PATH: &[&str] = &["game", "sqpack"];
 
-#[allow(dead_code)]
-#[derive(Debug)]
-enum Platform {
-	Win32 = 0,
-	PS3 = 1,
-	PS4 = 2,
-}
-
 /// SqPack resource for reading game data from an on-disk FFXIV installation.
 #[derive(Debug)]
 pub struct Install {
@@ -42,9 +32,28 @@ pub struct Install {
 
 impl Install {
 	/// Search for a FFXIV install in common locations, configuring a resource
-	/// instance with the found install, if any.
+	/// instance with the found install, if any. See [`Install::search_all`] for
+	/// the locations searched.
+	///
+	/// If `IRONWORKS_FFXIV_PATH` is set but does not point to an install, no
+	/// other locations are searched, and `None` is returned.
 	pub fn search() -> Option<Self> {
-		Some(Self::at(&find_install()?))
+		let candidate = find_installs().ok()?.into_iter().next()?;
+		Some(Self::at(candidate.path()))
+	}
+
+	/// Search for all FFXIV installs in common locations, in order of preference.
+	///
+	/// If the `IRONWORKS_FFXIV_PATH` environment variable is set, the install at
+	/// that path is listed first. Default Windows install paths are searched, as
+	/// are their equivalents under WSL, XIVLauncher.Core (`~/.xlcore/ffxiv`),
+	/// Steam libraries listed in `libraryfolders.vdf`, and the `drive_c` of Wine
+	/// and Proton prefixes.
+	///
+	/// Returns an error if `IRONWORKS_FFXIV_PATH` is set, but does not point to
+	/// an install.
+	pub fn search_all() -> Result<Vec<InstallCandidate>> {
+		find_installs()
 	}
 
 	/// Configure a resource instance with an installation of FFXIV at the specified path.
@@ -63,19 +72,28 @@ impl Install {
 		}
 	}
 
-	fn build_file_path(
+	/// Set the platform of the installation's SqPack files. Defaults to
+	/// [`Platform::Win32`].
+	#[must_use]
+	pub fn with_platform(mut self, platform: Platform) -> Self {
+		self.set_platform(platform);
+		self
+	}
+
+	/// Set the platform of the installation's SqPack files. Defaults to
+	/// [`Platform::Win32`].
+	pub fn set_platform(&mut self, platform: Platform) {
+		self.platform = platform;
+	}
+
+	pub(super) fn build_file_path(
 		&self,
 		repository: u8,
 		category: u8,
 		chunk: u8,
 		extension: &str,
 	) -> Result<PathBuf> {
-		let platform = match self.platform {
-			Platform::Win32 => "win32",
-			Platform::PS3 => todo!("PS3 platform"),
-			Platform::PS4 => todo!("PS4 platform"),
-		};
-
+		let platform = self.platform.name();
 		let file_name = format!("{category:02x}{repository:02x}{chunk:02x}.{platform}.{extension}");
 
 		let file_path = self.path.join(
@@ -87,16 +105,13 @@ impl Install {
 		Ok(file_path)
 	}
 
-	fn get_repository_name(&self, repository: u8) -> Result<&String> {
-		self.repositories
-			.get(usize::from(repository))
-			.and_then(|option| option.as_ref())
-			.ok_or_else(|| Error::NotFound(ErrorValue::Other(format!("repository {repository}"))))
+	/// Get the game version of the specified repository, as recorded in its
+	/// `.ver` file.
+	pub fn game_version(&self, repository: u8) -> Result<GameVersion> {
+		GameVersion::from_ver_file(&self.version_path(repository)?)
 	}
-}
 
-impl Resource for Install {
-	fn version(&self, repository: u8) -> Result<String> {
+	pub(super) fn version_path(&self, repository: u8) -> Result<PathBuf> {
 		let path = match repository {
 			0 => self.path.join("..").join("ffxivgame.ver"),
 			repo => {
@@ -107,7 +122,20 @@ impl Resource for Install {
 			}
 		};
 
-		Ok(fs::read_to_string(path)?)
+		Ok(path)
+	}
+
+	fn get_repository_name(&self, repository: u8) -> Result<&String> {
+		self.repositories
+			.get(usize::from(repository))
+			.and_then(|option| option.as_ref())
+			.ok_or_else(|| Error::NotFound(ErrorValue::Other(format!("repository {repository}"))))
+	}
+}
+
+impl Resource for Install {
+	fn version(&self, repository: u8) -> Result<String> {
+		Ok(fs::read_to_string(self.version_path(repository)?)?)
 	}
 
 	type Index = io::Cursor<Vec<u8>>;
@@ -132,32 +160,79 @@ impl Resource for Install {
 
 		let offset = u64::from(location.offset());
 		// Resolve the size early in case we need to seek to find the end. Using
-		// longhand here so I can shortcut seek failures.
+		// longhand here so I can shortcut seek failures. Truncated dats may end
+		// before the offset, which is left to surface as a read failure.
 		let size = match location.size() {
 			Some(size) => u64::from(size),
-			None => file.seek(io::SeekFrom::End(0))? - offset,
+			None => file.seek(io::SeekFrom::End(0))?.saturating_sub(offset),
 		};
 
 		file.seek(io::SeekFrom::Start(offset))?;
 
 		Ok(file.take_seekable(size)?)
 	}
+
+	fn platform(&self) -> Platform {
+		self.platform
+	}
 }
 
-fn find_install() -> Option<PathBuf> {
-	TRY_PATHS
-		.iter()
-		.flat_map(|path| {
-			[
-				PathBuf::from(path),
-				WSL_PREFIX
-					.iter()
-					.copied()
-					.chain(path.split('\\').skip(1))
-					.collect::<PathBuf>(),
-			]
+#[cfg(feature = "async")]
+impl AsyncResource for Install {
+	fn version(&self, repository: u8) -> BoxFuture<'_, Result<String>> {
+		Box::pin(async move {
+			let path = self.version_path(repository)?;
+			Ok(tokio::fs::read_to_string(path).await?)
+		})
+	}
+
+	fn index(&self, repository: u8, category: u8, chunk: u8) -> BoxFuture<'_, Result<Vec<u8>>> {
+		Box::pin(async move {
+			let path = self.build_file_path(repository, category, chunk, "index")?;
+			tokio::fs::read(&path)
+				.await
+				.map_err(|error| index_error(&path, error))
+		})
+	}
+
+	fn index2(&self, repository: u8, category: u8, chunk: u8) -> BoxFuture<'_, Result<Vec<u8>>> {
+		Box::pin(async move {
+			let path = self.build_file_path(repository, category, chunk, "index2")?;
+			tokio::fs::read(&path)
+				.await
+				.map_err(|error| index_error(&path, error))
 		})
-		.find(|path| path.exists())
+	}
+
+	fn file(
+		&self,
+		repository: u8,
+		category: u8,
+		location: Location,
+	) -> BoxFuture<'_, Result<Vec<u8>>> {
+		use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+		Box::pin(async move {
+			let path = self.build_file_path(
+				repository,
+				category,
+				location.chunk(),
+				&format!("dat{}", location.data_file()),
+			)?;
+			let mut file = tokio::fs::File::open(path).await?;
+			file.seek(io::SeekFrom::Start(location.offset().into()))
+				.await?;
+
+			// Without a known size, the file extends to the end of the dat.
+			let mut buffer = Vec::new();
+			match location.size() {
+				Some(size) => file.take(size.into()).read_to_end(&mut buffer).await?,
+				None => file.read_to_end(&mut buffer).await?,
+			};
+
+			Ok(buffer)
+		})
+	}
 }
 
 fn find_repositories(path: &Path) -> Vec<Option<String>> {
@@ -177,11 +252,119 @@ fn read_index(path: PathBuf) -> Result<io::Cursor<Vec<u8>>> {
 	// Read the entire index into memory before returning - we typically need
 	// the full dataset anyway, and working directly on a File causes significant
 	// slowdowns due to IO syscalls.
-	let buffer = fs::read(&path).map_err(|error| match error.kind() {
+	let buffer = fs::read(&path).map_err(|error| index_error(&path, error))?;
+	Ok(io::Cursor::new(buffer))
+}
+
+pub(super) fn index_error(path: &Path, error: io::Error) -> Error {
+	match error.kind() {
 		io::ErrorKind::NotFound => {
 			Error::NotFound(ErrorValue::Other(format!("file path {path:?}")))
 		}
 		_ => Error::Resource(error.into()),
-	})?;
-	Ok(io::Cursor::new(buffer))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, io::Read, path::Path};
+
+	use crate::{
+		sqpack::{FileKind, Platform, SqPack, Writer},
+		utility::{crc32, TempDir},
+	};
+
+	use super::Install;
+
+	const PATH: &str = "exd/test.exd";
+	const DATA: &[u8] = b"console data";
+
+	fn read(install: Install) -> Vec<u8> {
+		let mut buffer = Vec::new();
+		SqPack::new(install)
+			.file(PATH)
+			.unwrap()
+			.read_to_end(&mut buffer)
+			.unwrap();
+		buffer
+	}
+
+	fn header(fields: &[u32]) -> Vec<u8> {
+		let mut buffer = fields
+			.iter()
+			.flat_map(|field| field.to_be_bytes())
+			.collect::<Vec<_>>();
+		buffer.resize(0x400, 0);
+		buffer
+	}
+
+	// Build a minimal big-endian index2 and dat pair containing a single file, as
+	// found in PS3 data.
+	fn write_ps3_fixture(repository: &Path) {
+		let sqpack_header = |kind: u32| {
+			let mut buffer = b"SqPack\0\0\x01\0\0\0".to_vec();
+			buffer.extend(&header(&[0x400, 1, kind])[..0x400 - 12]);
+			buffer
+		};
+
+		// Index header fields are separated by 64 byte digests.
+		let mut index = sqpack_header(2);
+		let mut index_header = vec![0; 0x400];
+		for (offset, value) in [(0, 0x400), (4, 1), (8, 0x800), (12, 8), (80, 1)] {
+			index_header[offset..offset + 4].copy_from_slice(&u32::to_be_bytes(value));
+		}
+		index.extend(index_header);
+		index.extend(crc32(PATH.as_bytes()).to_be_bytes());
+		// Data file 0, offset 0x800 (stored in units of 8 bytes).
+		index.extend((0x800u32 / 0x08).to_be_bytes());
+
+		let size = u32::try_from(DATA.len()).unwrap();
+		let mut dat = sqpack_header(1);
+		dat.extend(header(&[0x400]));
+		// File header, followed by block info of a 32-bit offset and 16-bit sizes.
+		let mut file = header(&[128, 2, size, 0, 0, 1, 0]);
+		file.truncate(28);
+		file.extend(u16::try_from(16 + size).unwrap().to_be_bytes());
+		file.extend(u16::try_from(size).unwrap().to_be_bytes());
+		file.resize(128, 0);
+		dat.extend(file);
+		dat.extend(&header(&[16, 0, 32_000, size])[..16]);
+		dat.extend(DATA);
+
+		fs::create_dir_all(repository).unwrap();
+		fs::write(repository.join("0a0000.ps3.index2"), index).unwrap();
+		fs::write(repository.join("0a0000.ps3.dat0"), dat).unwrap();
+	}
+
+	#[test]
+	fn ps3() {
+		let root = TempDir::new("install-ps3");
+		write_ps3_fixture(&root.join("game").join("sqpack").join("ffxiv"));
+
+		assert_eq!(read(Install::at(&root).with_platform(Platform::PS3)), DATA);
+		assert!(SqPack::new(Install::at(&root)).file(PATH).is_err());
+	}
+
+	#[test]
+	fn ps4() {
+		let root = TempDir::new("install-ps4");
+		let sqpack_path = root.join("game").join("sqpack");
+		Writer::new()
+			.with_file(PATH, DATA.to_vec(), FileKind::Standard)
+			.unwrap()
+			.write(&sqpack_path)
+			.unwrap();
+
+		// PS4 data shares the PC layout, differing in name and platform ID.
+		let repository = sqpack_path.join("ffxiv");
+		for extension in ["index", "index2", "dat0"] {
+			let path = repository.join(format!("0a0000.win32.{extension}"));
+			let mut data = fs::read(&path).unwrap();
+			data[8] = 2;
+			fs::write(repository.join(format!("0a0000.ps4.{extension}")), data).unwrap();
+			fs::remove_file(path).unwrap();
+		}
+
+		assert_eq!(read(Install::at(&root).with_platform(Platform::PS4)), DATA);
+	}
 }
@@ -0,0 +1,205 @@
+use std::{
+	fs,
+	io::{self, Cursor},
+	ops::{Deref, Range},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use memmap2::Mmap;
+
+use crate::{
+	error::{Error, Result},
+	utility::{HashMapCache, HashMapCacheExt},
+};
+
+use super::{
+	install::{index_error, Install},
+	Location, Platform, Resource,
+};
+
+/// SqPack resource for reading game data from an on-disk FFXIV installation
+/// via memory-mapped files.
+///
+/// Each `.index`, `.index2`, and `.datN` file is mapped once on first use, and
+/// retained for the lifetime of the resource. Files are read as zero-copy slices
+/// of the mapped data, avoiding per-file open and seek overhead when reading
+/// large numbers of files.
+///
+/// Mapped files must not be modified while the resource is in use, i.e. by
+/// the game's launcher applying a patch. Doing so may result in corrupt data
+/// or a crash.
+#[derive(Debug)]
+pub struct MappedInstall {
+	install: Install,
+	maps: HashMapCache<PathBuf, Mmap>,
+}
+
+impl MappedInstall {
+	/// Search for a FFXIV install in common locations, configuring a resource
+	/// instance with the found install, if any.
+	pub fn search() -> Option<Self> {
+		Install::search().map(Self::from)
+	}
+
+	/// Configure a resource instance with an installation of FFXIV at the specified path.
+	pub fn at(path: &Path) -> Self {
+		Self::from(Install::at(path))
+	}
+
+	fn map(&self, path: PathBuf) -> Result<Arc<Mmap>> {
+		self.maps.try_get_or_insert(path.clone(), || {
+			let file = fs::File::open(&path).map_err(|error| index_error(&path, error))?;
+			// SAFETY: Game files are not expected to be modified while the install
+			// is in use - this requirement is documented on the type.
+			let map = unsafe { Mmap::map(&file) }?;
+			Ok(map)
+		})
+	}
+
+	fn map_whole(&self, path: PathBuf) -> Result<MappedSlice> {
+		let map = self.map(path)?;
+		let range = 0..map.len();
+		Ok(MappedSlice { map, range })
+	}
+}
+
+impl From<Install> for MappedInstall {
+	fn from(install: Install) -> Self {
+		Self {
+			install,
+			maps: Default::default(),
+		}
+	}
+}
+
+impl Resource for MappedInstall {
+	fn version(&self, repository: u8) -> Result<String> {
+		Resource::version(&self.install, repository)
+	}
+
+	type Index = Cursor<MappedSlice>;
+	fn index(&self, repository: u8, category: u8, chunk: u8) -> Result<Self::Index> {
+		let path = self
+			.install
+			.build_file_path(repository, category, chunk, "index")?;
+		Ok(Cursor::new(self.map_whole(path)?))
+	}
+
+	type Index2 = Cursor<MappedSlice>;
+	fn index2(&self, repository: u8, category: u8, chunk: u8) -> Result<Self::Index2> {
+		let path = self
+			.install
+			.build_file_path(repository, category, chunk, "index2")?;
+		Ok(Cursor::new(self.map_whole(path)?))
+	}
+
+	type File = Cursor<MappedSlice>;
+	fn file(&self, repository: u8, category: u8, location: Location) -> Result<Self::File> {
+		let path = self.install.build_file_path(
+			repository,
+			category,
+			location.chunk(),
+			&format!("dat{}", location.data_file()),
+		)?;
+		let map = self.map(path)?;
+
+		let start = usize::try_from(location.offset()).unwrap();
+		if start > map.len() {
+			return Err(Error::Resource(
+				io::Error::from(io::ErrorKind::UnexpectedEof).into(),
+			));
+		}
+
+		// Sizes are estimates, and files without a known size extend to the end of the dat.
+		let end = match location.size() {
+			Some(size) => (start + usize::try_from(size).unwrap()).min(map.len()),
+			None => map.len(),
+		};
+
+		Ok(Cursor::new(MappedSlice {
+			map,
+			range: start..end,
+		}))
+	}
+
+	fn platform(&self) -> Platform {
+		Resource::platform(&self.install)
+	}
+}
+
+/// Zero-copy slice of a memory-mapped SqPack file.
+#[derive(Debug, Clone)]
+pub struct MappedSlice {
+	map: Arc<Mmap>,
+	range: Range<usize>,
+}
+
+impl Deref for MappedSlice {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.map[self.range.clone()]
+	}
+}
+
+impl AsRef<[u8]> for MappedSlice {
+	fn as_ref(&self) -> &[u8] {
+		self
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, io::Read, sync::Arc};
+
+	use crate::{
+		error::{Error, ErrorValue},
+		sqpack::Resource,
+		utility::TempDir,
+	};
+
+	use super::MappedInstall;
+
+	fn fixture(name: &str) -> TempDir {
+		let root = TempDir::new(&format!("mapped-{name}"));
+		let repository = root.join("game").join("sqpack").join("ffxiv");
+		fs::create_dir_all(&repository).unwrap();
+		fs::write(repository.join("000000.win32.index"), b"index data").unwrap();
+		root
+	}
+
+	#[test]
+	fn test_send_sync() {
+		fn assert_send_sync<T: Send + Sync>() {}
+		assert_send_sync::<MappedInstall>();
+	}
+
+	#[test]
+	fn maps_once() {
+		let root = fixture("once");
+		let install = MappedInstall::at(&root);
+
+		let mut buffer = Vec::new();
+		install
+			.index(0, 0, 0)
+			.unwrap()
+			.read_to_end(&mut buffer)
+			.unwrap();
+		assert_eq!(buffer, b"index data");
+
+		let first = install.index(0, 0, 0).unwrap().into_inner();
+		let second = install.index(0, 0, 0).unwrap().into_inner();
+		assert!(Arc::ptr_eq(&first.map, &second.map));
+	}
+
+	#[test]
+	fn missing_index() {
+		let root = fixture("missing");
+		let install = MappedInstall::at(&root);
+		assert!(matches!(
+			install.index2(0, 0, 0),
+			Err(Error::NotFound(ErrorValue::Other(_)))
+		));
+	}
+}
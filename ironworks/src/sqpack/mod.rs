@@ -1,21 +1,42 @@
 //! Tools for working with the SqPack package format.
 
 mod block;
+mod dictionary;
 mod file;
 mod index;
 mod install;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod platform;
 mod resource;
+mod search;
 mod sqpack;
+mod verify;
+mod writer;
 
 pub use {
-	block::{BlockMetadata, BlockPayload, BlockStream},
-	file::File,
-	index::Location,
+	block::{BlockHeader, BlockMetadata, BlockPayload, BlockStream},
+	dictionary::PathDictionary,
+	file::{encode, File, FileKind, Header, Placeholder, RawBlocks, RawFile, RsfKey},
+	index::{IndexEntry, IndexHash, Location},
 	install::Install,
+	platform::Platform,
 	resource::Resource,
+	search::InstallCandidate,
 	sqpack::SqPack,
+	verify::{VerifyIssue, VerifyReport},
+	writer::Writer,
 };
 
+#[cfg(feature = "zipatch")]
+pub(crate) use block::write_blocks;
+
+#[cfg(feature = "async")]
+pub use resource::AsyncResource;
+
+#[cfg(feature = "mmap")]
+pub use mapped::{MappedInstall, MappedSlice};
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -33,4 +54,12 @@ mod test {
 		assert_sync::<File<()>>();
 		assert_sync::<SqPack<()>>();
 	}
+
+	#[cfg(feature = "async")]
+	#[test]
+	fn test_send_async() {
+		fn assert_send<T: Send>(_: &T) {}
+		let sqpack = SqPack::new(Install::at(std::path::Path::new(".")));
+		assert_send(&sqpack.file_async("exd/root.exl"));
+	}
 }
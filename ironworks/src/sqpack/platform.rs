@@ -0,0 +1,35 @@
+use binrw::{binread, Endian};
+
+/// Platform that SqPack data was built for, determining file naming and the
+/// byte order of SqPack structures.
+#[binread]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[br(repr = u8)]
+pub enum Platform {
+	/// Windows, and other PC clients. Files are named `*.win32.*`.
+	#[default]
+	Win32 = 0,
+	/// PlayStation 3. Files are named `*.ps3.*`, and are big-endian.
+	PS3 = 1,
+	/// PlayStation 4. Files are named `*.ps4.*`.
+	PS4 = 2,
+}
+
+impl Platform {
+	/// Name of the platform, as used in SqPack file names.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Self::Win32 => "win32",
+			Self::PS3 => "ps3",
+			Self::PS4 => "ps4",
+		}
+	}
+
+	/// Byte order of SqPack structures built for the platform.
+	pub fn endian(&self) -> Endian {
+		match self {
+			Self::PS3 => Endian::Big,
+			Self::Win32 | Self::PS4 => Endian::Little,
+		}
+	}
+}
@@ -1,8 +1,10 @@
 use std::io::{Read, Seek};
 
+#[cfg(feature = "async")]
+use crate::async_resource::BoxFuture;
 use crate::error::Result;
 
-use super::index::Location;
+use super::{index::Location, platform::Platform};
 
 /// Resource adapter to fetch information and data on request for a SqPack instance.
 pub trait Resource {
@@ -23,4 +25,33 @@ pub trait Resource {
 	type File: Read + Seek;
 	/// Fetch a reader for the specified file from a dat container.
 	fn file(&self, repository: u8, category: u8, location: Location) -> Result<Self::File>;
+
+	/// Get the platform the SqPack data was built for. Defaults to
+	/// [`Platform::Win32`].
+	fn platform(&self) -> Platform {
+		Platform::Win32
+	}
+}
+
+/// Resource adapter to asynchronously fetch information and data on request for
+/// a SqPack instance.
+#[cfg(feature = "async")]
+pub trait AsyncResource {
+	/// Get the version string for a given repository.
+	fn version(&self, repository: u8) -> BoxFuture<'_, Result<String>>;
+
+	/// Fetches the full contents of the specified index resource.
+	fn index(&self, repository: u8, category: u8, chunk: u8) -> BoxFuture<'_, Result<Vec<u8>>>;
+
+	/// Fetches the full contents of the specified index2 resource.
+	fn index2(&self, repository: u8, category: u8, chunk: u8) -> BoxFuture<'_, Result<Vec<u8>>>;
+
+	/// Fetch the raw, still SqPack-encoded data for the specified file from a
+	/// dat container.
+	fn file(
+		&self,
+		repository: u8,
+		category: u8,
+		location: Location,
+	) -> BoxFuture<'_, Result<Vec<u8>>>;
 }
@@ -0,0 +1,310 @@
+use std::{
+	env, fs,
+	path::{Path, PathBuf},
+};
+
+use getset::Getters;
+
+use crate::{
+	error::{Error, ErrorValue, Result},
+	version::GameVersion,
+};
+
+// Environment variable that, when set, specifies the path to an install that
+// will be preferred over all other search locations.
+const INSTALL_PATH_VAR: &str = "IRONWORKS_FFXIV_PATH";
+
+const TRY_PATHS: &[&str] = &[
+	r"C:\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
+	r"C:\Program Files (x86)\Steam\steamapps\common\FINAL FANTASY XIV Online",
+	r"C:\Program Files (x86)\Steam\steamapps\common\FINAL FANTASY XIV - A Realm Reborn",
+	r"C:\Program Files (x86)\FINAL FANTASY XIV - A Realm Reborn",
+	r"C:\Program Files (x86)\SquareEnix\FINAL FANTASY XIV - A Realm Reborn",
+];
+
+const WSL_PREFIX: &[&str] = &["/mnt", "c"];
+
+// Install directory names used by Steam, relative to a library's `steamapps/common`.
+const STEAM_APP_NAMES: &[&str] = &[
+	"FINAL FANTASY XIV Online",
+	"FINAL FANTASY XIV - A Realm Reborn",
+];
+
+// Steam's Proton prefixes are keyed by app ID - 39210 being FFXIV.
+const STEAM_APP_ID: &str = "39210";
+
+// Steam installation roots, relative to the home directory.
+const STEAM_ROOTS: &[&str] = &[
+	".steam/steam",
+	".local/share/Steam",
+	".var/app/com.valvesoftware.Steam/.local/share/Steam",
+];
+
+// Wine prefixes, relative to the home directory.
+const WINE_PREFIXES: &[&str] = &[
+	".wine",
+	".xlcore/wineprefix",
+	"Games/final-fantasy-xiv-online",
+];
+
+// Game installs, relative to the home directory.
+const HOME_INSTALLS: &[&str] = &[".xlcore/ffxiv"];
+
+/// A FFXIV install found while searching common install locations.
+#[derive(Debug, Clone, Getters)]
+#[get = "pub"]
+pub struct InstallCandidate {
+	/// Path to the root of the install, containing the `game` directory.
+	path: PathBuf,
+	/// Version of the game, as recorded in `game/ffxivgame.ver`, if readable.
+	version: Option<GameVersion>,
+}
+
+// Search common install locations for FFXIV installs, in order of preference.
+pub fn find_installs() -> Result<Vec<InstallCandidate>> {
+	installs(
+		env::var_os(INSTALL_PATH_VAR).map(PathBuf::from),
+		env::var_os("HOME").map(PathBuf::from).as_deref(),
+		env::var_os("WINEPREFIX").map(PathBuf::from),
+	)
+}
+
+fn installs(
+	override_path: Option<PathBuf>,
+	home: Option<&Path>,
+	wine_prefix: Option<PathBuf>,
+) -> Result<Vec<InstallCandidate>> {
+	// An explicit override that doesn't point at an install is almost certainly
+	// a mistake - fail rather than quietly using some other install.
+	if let Some(path) = &override_path {
+		if !is_install(path) {
+			return Err(Error::Invalid(
+				ErrorValue::Path(path.to_string_lossy().into_owned()),
+				format!("{INSTALL_PATH_VAR} does not contain a game/sqpack directory"),
+			));
+		}
+	}
+
+	Ok(candidates(search_paths(override_path, home, wine_prefix)))
+}
+
+// Filter paths down to those containing an install, skipping duplicates.
+fn candidates(paths: Vec<PathBuf>) -> Vec<InstallCandidate> {
+	let mut seen = Vec::<PathBuf>::new();
+	let mut candidates = Vec::<InstallCandidate>::new();
+	for path in paths {
+		// The same install may be reachable via symlinks, i.e. Steam's
+		// `~/.steam/steam` - compare canonical paths where possible.
+		let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+		if seen.contains(&canonical) {
+			continue;
+		}
+		seen.push(canonical);
+
+		if !is_install(&path) {
+			continue;
+		}
+
+		let version = GameVersion::from_ver_file(&path.join("game").join("ffxivgame.ver")).ok();
+
+		candidates.push(InstallCandidate { path, version });
+	}
+
+	candidates
+}
+
+fn is_install(path: &Path) -> bool {
+	path.join("game").join("sqpack").is_dir()
+}
+
+// Build the list of paths that may contain an install, in order of preference.
+fn search_paths(
+	override_path: Option<PathBuf>,
+	home: Option<&Path>,
+	wine_prefix: Option<PathBuf>,
+) -> Vec<PathBuf> {
+	let mut paths = Vec::from_iter(override_path);
+
+	// Windows paths, and their equivalents when running under WSL.
+	let wsl_root = WSL_PREFIX.iter().collect::<PathBuf>();
+	for path in TRY_PATHS {
+		paths.push(PathBuf::from(path));
+		paths.push(windows_path(&wsl_root, path));
+	}
+
+	let Some(home) = home else {
+		return paths;
+	};
+
+	// Installs managed by XIVLauncher.Core and similar.
+	paths.extend(HOME_INSTALLS.iter().map(|path| home.join(path)));
+
+	// Steam installs, including Proton installs, are found within each library.
+	let libraries = STEAM_ROOTS
+		.iter()
+		.map(|root| home.join(root))
+		.flat_map(|root| steam_libraries(&root))
+		.collect::<Vec<_>>();
+	for library in &libraries {
+		let steamapps = library.join("steamapps");
+		paths.extend(
+			STEAM_APP_NAMES
+				.iter()
+				.map(|name| steamapps.join("common").join(name)),
+		);
+	}
+
+	// Wine prefixes mirror the Windows layout within `drive_c`.
+	let prefixes = wine_prefix
+		.into_iter()
+		.chain(WINE_PREFIXES.iter().map(|prefix| home.join(prefix)))
+		.chain(libraries.iter().map(|library| {
+			library
+				.join("steamapps")
+				.join("compatdata")
+				.join(STEAM_APP_ID)
+				.join("pfx")
+		}));
+	for prefix in prefixes {
+		let drive = prefix.join("drive_c");
+		paths.extend(TRY_PATHS.iter().map(|path| windows_path(&drive, path)));
+	}
+
+	paths
+}
+
+// Map an absolute Windows path, i.e. `C:\Path`, to the same path under `root`.
+fn windows_path(root: &Path, path: &str) -> PathBuf {
+	let mut mapped = root.to_path_buf();
+	mapped.extend(path.split('\\').skip(1));
+	mapped
+}
+
+// Get the library folders of the Steam install at `root`, including the root itself.
+fn steam_libraries(root: &Path) -> Vec<PathBuf> {
+	let mut libraries = vec![root.to_path_buf()];
+
+	let Ok(folders) = fs::read_to_string(root.join("steamapps").join("libraryfolders.vdf")) else {
+		return libraries;
+	};
+
+	for path in library_folder_paths(&folders) {
+		if !libraries.contains(&path) {
+			libraries.push(path);
+		}
+	}
+
+	libraries
+}
+
+// Extract the `"path"` values from the contents of a `libraryfolders.vdf` file.
+fn library_folder_paths(vdf: &str) -> Vec<PathBuf> {
+	vdf.lines()
+		.filter_map(|line| {
+			let mut tokens = line
+				.trim()
+				.split('"')
+				.filter(|token| !token.trim().is_empty());
+			match (tokens.next(), tokens.next()) {
+				(Some("path"), Some(value)) => Some(PathBuf::from(value.replace(r"\\", r"\"))),
+				_ => None,
+			}
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, path::Path};
+
+	use crate::{error::Error, utility::TempDir};
+
+	use super::{installs, library_folder_paths};
+
+	fn install(path: &Path, version: &str) {
+		fs::create_dir_all(path.join("game").join("sqpack")).unwrap();
+		fs::write(path.join("game").join("ffxivgame.ver"), version).unwrap();
+	}
+
+	#[test]
+	fn parses_library_folders() {
+		let vdf = r#""libraryfolders"
+{
+	"0"
+	{
+		"path"		"/home/user/.local/share/Steam"
+		"label"		""
+	}
+	"1"
+	{
+		"path"		"D:\\SteamLibrary"
+	}
+}"#;
+		assert_eq!(
+			library_folder_paths(vdf),
+			[
+				Path::new("/home/user/.local/share/Steam"),
+				Path::new(r"D:\SteamLibrary")
+			]
+		);
+	}
+
+	#[test]
+	fn finds_linux_installs() {
+		let root = TempDir::new("search");
+		let home = root.join("home");
+
+		install(&home.join(".xlcore/ffxiv"), "2024.01.01.0000.0000");
+
+		let library = root.join("library");
+		let steam = home.join(".local/share/Steam/steamapps");
+		fs::create_dir_all(&steam).unwrap();
+		fs::write(
+			steam.join("libraryfolders.vdf"),
+			format!("\"1\"\n{{\n\t\"path\"\t\t\"{}\"\n}}\n", library.display()),
+		)
+		.unwrap();
+		install(
+			&library.join("steamapps/common/FINAL FANTASY XIV Online"),
+			"2024.02.02.0000.0000",
+		);
+
+		let wine = root.join("prefix");
+		install(
+			&wine.join("drive_c/Program Files (x86)/SquareEnix/FINAL FANTASY XIV - A Realm Reborn"),
+			"2024.03.03.0000.0000\n",
+		);
+
+		let overridden = root.join("override");
+		install(&overridden, "2024.04.04.0000.0000");
+
+		// The same install reached through a symlink should only be listed once.
+		#[cfg(unix)]
+		{
+			fs::create_dir_all(home.join(".steam")).unwrap();
+			std::os::unix::fs::symlink(&library, home.join(".steam/steam")).unwrap();
+		}
+
+		let found = installs(Some(overridden.clone()), Some(&home), Some(wine.clone())).unwrap();
+		let versions = found
+			.iter()
+			.map(|candidate| candidate.version().unwrap().to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			versions,
+			[
+				"2024.04.04.0000.0000",
+				"2024.01.01.0000.0000",
+				"2024.02.02.0000.0000",
+				"2024.03.03.0000.0000",
+			]
+		);
+		assert_eq!(found[0].path(), &overridden);
+
+		let missing = root.join("missing");
+		assert!(matches!(
+			installs(Some(missing), Some(&home), Some(wine)),
+			Err(Error::Invalid(..))
+		));
+	}
+}
@@ -1,14 +1,25 @@
+#[cfg(feature = "async")]
+use std::io::{Cursor, Read};
 use std::{fmt::Debug, sync::Arc};
 
 use crate::{
 	error::{Error, ErrorValue, Result},
-	ironworks::FileStream,
+	ironworks::{FileStream, ListEntry},
 	sqpack,
-	utility::{HashMapCache, HashMapCacheExt},
+	utility::{crc32, HashMapCache, HashMapCacheExt},
 	Resource,
 };
 
-use super::{file::File, index::Index};
+#[cfg(feature = "async")]
+use crate::async_resource::{AsyncResource, BoxFuture};
+
+use super::{
+	dictionary::PathDictionary,
+	file::{File, RawFile, RsfKey, RsfTable},
+	index::{Index, IndexEntry, IndexHash},
+	platform::Platform,
+	verify::{verify_category, VerifyReport},
+};
 
 const CATEGORIES: &[Option<&str>] = &[
 	/* 0x00 */ Some("common"),
@@ -44,9 +55,13 @@ pub struct SqPack<R> {
 	resource: Arc<R>,
 
 	indexes: HashMapCache<(u8, u8), Index<R>>,
+
+	rsf: RsfTable,
+
+	dictionary: Option<PathDictionary>,
 }
 
-impl<R: sqpack::Resource> SqPack<R> {
+impl<R> SqPack<R> {
 	/// Build a representation of SqPack packages. The provided resource will be
 	/// queried for lookups as required to fulfil SqPack requests.
 	pub fn new(resource: R) -> Self {
@@ -54,61 +69,260 @@ impl<R: sqpack::Resource> SqPack<R> {
 			resource: resource.into(),
 
 			indexes: Default::default(),
+
+			rsf: Default::default(),
+
+			dictionary: None,
 		}
 	}
 
+	/// Supply the content for RSF placeholder entries identified by `key`. Files
+	/// stored as an RSF placeholder can not be read until their content has
+	/// been supplied.
+	#[must_use]
+	pub fn with_rsf(mut self, key: RsfKey, data: impl Into<Arc<[u8]>>) -> Self {
+		self.add_rsf(key, data);
+		self
+	}
+
+	/// Supply the content for RSF placeholder entries identified by `key`. Files
+	/// stored as an RSF placeholder can not be read until their content has
+	/// been supplied.
+	pub fn add_rsf(&mut self, key: RsfKey, data: impl Into<Arc<[u8]>>) {
+		self.rsf.insert(key, data.into());
+	}
+
+	/// Set the dictionary used to resolve the directories of files when
+	/// listing. Without a dictionary, only files immediately within a directory
+	/// matching the listed prefix can be found.
+	#[must_use]
+	pub fn with_dictionary(mut self, dictionary: PathDictionary) -> Self {
+		self.set_dictionary(dictionary);
+		self
+	}
+
+	/// Set the dictionary used to resolve the directories of files when
+	/// listing. Without a dictionary, only files immediately within a directory
+	/// matching the listed prefix can be found.
+	pub fn set_dictionary(&mut self, dictionary: PathDictionary) {
+		self.dictionary = Some(dictionary);
+	}
+
+	fn index(&self, repository: u8, category: u8) -> Result<Arc<Index<R>>> {
+		self.indexes.try_get_or_insert((repository, category), || {
+			Index::new(repository, category, self.resource.clone())
+		})
+	}
+}
+
+impl<R: sqpack::Resource> SqPack<R> {
 	/// Get the version string for the file at `path`.
 	pub fn version(&self, path: &str) -> Result<String> {
-		let (repository, _) = self.path_metadata(&path.to_lowercase())?;
+		let (repository, _) = path_metadata(&path.to_lowercase())?;
 		self.resource.version(repository)
 	}
 
 	/// Read the file at `path` from SqPack.
 	pub fn file(&self, path: &str) -> Result<File<R::File>> {
+		// Build a File representation.
+		let (dat, platform) = self.dat(path)?;
+
+		// TODO: Cache files? Tempted to say it's the IW struct's responsibility. Is it even possible here with streams?
+		File::with_rsf(dat, platform, &self.rsf)
+	}
+
+	/// Read the file at `path` from SqPack, without decompressing its blocks.
+	pub fn raw_file(&self, path: &str) -> Result<RawFile<R::File>> {
+		let (dat, platform) = self.dat(path)?;
+		RawFile::for_platform(dat, platform)
+	}
+
+	fn dat(&self, path: &str) -> Result<(R::File, Platform)> {
 		// SqPack paths are always lower case.
 		let path = path.to_lowercase();
 
 		// Look up the location of the requested path.
-		let (repository, category) = self.path_metadata(&path)?;
+		let (repository, category) = path_metadata(&path)?;
 
-		let location = self
-			.indexes
-			.try_get_or_insert((repository, category), || {
-				Index::new(repository, category, self.resource.clone())
-			})?
-			.find(&path)?;
+		let location = self.index(repository, category)?.find(&path)?;
+		let platform = location.platform();
 
-		// Build a File representation.
-		let dat = self.resource.file(repository, category, location)?;
+		Ok((
+			self.resource.file(repository, category, location)?,
+			platform,
+		))
+	}
 
-		// TODO: Cache files? Tempted to say it's the IW struct's responsibility. Is it even possible here with streams?
-		File::new(dat)
+	/// Check if SqPack contains a file at `path`. Only the index is consulted;
+	/// the file's data is not read.
+	pub fn exists(&self, path: &str) -> Result<bool> {
+		let path = path.to_lowercase();
+
+		let location = path_metadata(&path)
+			.and_then(|(repository, category)| self.index(repository, category)?.find(&path));
+
+		match location {
+			Ok(_) => Ok(true),
+			Err(Error::NotFound(ErrorValue::Path(_))) => Ok(false),
+			Err(error) => Err(error),
+		}
+	}
+
+	/// Get every entry in the index for the specified repository and category,
+	/// i.e. `(0, 4)` for the `040000` index set. Entries in all chunks are returned.
+	pub fn entries(&self, repository: u8, category: u8) -> Result<Vec<IndexEntry>> {
+		self.index(repository, category)?.entries()
+	}
+
+	/// Verify the integrity of all SqPack data. Index headers and digests are
+	/// checked, and every file referenced by the indexes is read in full to
+	/// ensure its blocks are intact. This reads the entirety of the data set,
+	/// and may take considerable time.
+	pub fn verify(&self) -> VerifyReport {
+		let mut report = VerifyReport::default();
+		for repository in 0..REPOSITORIES.len() {
+			self.verify_repository_into(repository.try_into().unwrap(), &mut report);
+		}
+		report
+	}
+
+	/// Verify the integrity of the SqPack data in the specified repository, i.e.
+	/// `1` for `ex1`. See [`SqPack::verify`] for details.
+	pub fn verify_repository(&self, repository: u8) -> VerifyReport {
+		let mut report = VerifyReport::default();
+		self.verify_repository_into(repository, &mut report);
+		report
+	}
+
+	/// Verify the integrity of the SqPack data for the specified repository and
+	/// category, i.e. `(0, 4)` for the `040000` index set. See [`SqPack::verify`]
+	/// for details.
+	pub fn verify_category(&self, repository: u8, category: u8) -> VerifyReport {
+		let mut report = VerifyReport::default();
+		self.verify_category_into(repository, category, &mut report);
+		report
+	}
+
+	fn verify_repository_into(&self, repository: u8, report: &mut VerifyReport) {
+		for (category, name) in CATEGORIES.iter().enumerate() {
+			if name.is_some() {
+				self.verify_category_into(repository, category.try_into().unwrap(), report);
+			}
+		}
+	}
+
+	fn verify_category_into(&self, repository: u8, category: u8, report: &mut VerifyReport) {
+		verify_category(
+			&*self.resource,
+			repository,
+			category,
+			|| self.entries(repository, category),
+			report,
+		);
 	}
 
-	fn path_metadata(&self, path: &str) -> Result<(u8, u8)> {
-		// NOTE: This could be technically-faster by doing that cursed logic the
-		// game does, checking the first 3 characters for category and such - but I
-		// think this is cleaner; especially to read.
+	/// List the files with paths beginning with `prefix`. As SqPack indexes do
+	/// not store file names, the listing is returned as file name hashes within
+	/// their directory. Directories are resolved with the dictionary set via
+	/// [`with_dictionary`](Self::with_dictionary), or by matching `prefix`
+	/// itself. Only categories using Index1 files can be listed.
+	pub fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		let prefix = prefix.to_lowercase();
+		let exact_directory = prefix.trim_end_matches('/');
+		let exact_hash = crc32(exact_directory.as_bytes());
+
+		let mut entries = Vec::new();
+		let mut listed = false;
+
+		for (repository, category) in list_targets(&prefix) {
+			for entry in self.index(repository, category)?.entries()? {
+				let IndexHash::Path { directory, file } = entry.hash() else {
+					continue;
+				};
+				listed = true;
 
-		let path_not_found = || Error::NotFound(ErrorValue::Path(path.to_string()));
+				let dictionary = self.dictionary.as_ref();
+				let name =
+					match dictionary.and_then(|dictionary| dictionary.directory(entry.hash())) {
+						Some(name) => name,
+						None if directory == exact_hash => exact_directory,
+						None => continue,
+					};
 
-		let mut split = path.split('/');
-		let (Some(category_segment), Some(repository_segment)) = (split.next(), split.next())
-		else {
-			return Err(path_not_found());
-		};
+				// If the full path is known, it can be checked against the prefix directly.
+				let matches = match dictionary.and_then(|dictionary| dictionary.path(entry.hash()))
+				{
+					Some(path) => path.starts_with(&prefix),
+					None => format!("{name}/").starts_with(&prefix),
+				};
+				if matches {
+					entries.push(ListEntry::Hashed {
+						directory: name.to_string(),
+						file_hash: file,
+					});
+				}
+			}
+		}
+
+		if !listed {
+			return Err(Error::NotFound(ErrorValue::Path(prefix)));
+		}
+
+		Ok(entries)
+	}
+}
 
-		let repository = REPOSITORIES
-			.iter()
-			.position(|&repository| repository == repository_segment)
-			.unwrap_or(0);
+// Get the repositories and categories that may contain paths beginning with `prefix`.
+fn list_targets(prefix: &str) -> Vec<(u8, u8)> {
+	// If the prefix names an expansion repository, only that repository needs checking.
+	let named_repository = path_metadata(prefix)
+		.ok()
+		.map(|(repository, _)| repository)
+		.filter(|&repository| repository != 0);
 
-		let category = CATEGORIES
-			.iter()
-			.position(|&category| category == Some(category_segment))
-			.ok_or_else(path_not_found)?;
+	let mut targets = Vec::new();
+	for (repository, repository_name) in REPOSITORIES.iter().enumerate() {
+		let repository = u8::try_from(repository).unwrap();
+		if named_repository.is_some_and(|named| named != repository) {
+			continue;
+		}
 
-		Ok((repository.try_into().unwrap(), category.try_into().unwrap()))
+		for (category, category_name) in CATEGORIES.iter().enumerate() {
+			let Some(category_name) = category_name else {
+				continue;
+			};
+			let root = match repository {
+				0 => format!("{category_name}/"),
+				_ => format!("{category_name}/{repository_name}/"),
+			};
+			if prefix.starts_with(&root) || root.starts_with(prefix) {
+				targets.push((repository, u8::try_from(category).unwrap()));
+			}
+		}
+	}
+	targets
+}
+
+#[cfg(feature = "async")]
+impl<R: sqpack::AsyncResource + Send + Sync> SqPack<R> {
+	/// Asynchronously get the version string for the file at `path`.
+	pub async fn version_async(&self, path: &str) -> Result<String> {
+		let (repository, _) = path_metadata(&path.to_lowercase())?;
+		self.resource.version(repository).await
+	}
+
+	/// Asynchronously read the file at `path` from SqPack. The file's data is
+	/// read into memory in its entirety before being returned.
+	pub async fn file_async(&self, path: &str) -> Result<File<Cursor<Vec<u8>>>> {
+		let path = path.to_lowercase();
+		let (repository, category) = path_metadata(&path)?;
+
+		let location = self.index(repository, category)?.find_async(&path).await?;
+
+		let platform = location.platform();
+		let dat = self.resource.file(repository, category, location).await?;
+
+		File::with_rsf(Cursor::new(dat), platform, &self.rsf)
 	}
 }
 
@@ -124,4 +338,60 @@ where
 	fn file(&self, path: &str) -> Result<Box<dyn FileStream>> {
 		Ok(Box::new(self.file(path)?))
 	}
+
+	fn exists(&self, path: &str) -> Result<bool> {
+		self.exists(path)
+	}
+
+	fn list(&self, prefix: &str) -> Result<Vec<ListEntry>> {
+		self.list(prefix)
+	}
+}
+
+#[cfg(feature = "async")]
+impl<R> AsyncResource for SqPack<R>
+where
+	R: sqpack::AsyncResource + Send + Sync + 'static,
+{
+	fn version<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<String>> {
+		Box::pin(self.version_async(path))
+	}
+
+	fn file<'a>(&'a self, path: &'a str) -> BoxFuture<'a, Result<Vec<u8>>> {
+		Box::pin(async move {
+			let mut buffer = Vec::new();
+			self.file_async(path).await?.read_to_end(&mut buffer)?;
+			Ok(buffer)
+		})
+	}
+}
+
+pub(super) fn path_metadata(path: &str) -> Result<(u8, u8)> {
+	// NOTE: This could be technically-faster by doing that cursed logic the
+	// game does, checking the first 3 characters for category and such - but I
+	// think this is cleaner; especially to read.
+
+	let path_not_found = || Error::NotFound(ErrorValue::Path(path.to_string()));
+
+	let mut split = path.split('/');
+	let (Some(category_segment), Some(repository_segment)) = (split.next(), split.next()) else {
+		return Err(path_not_found());
+	};
+
+	let repository = REPOSITORIES
+		.iter()
+		.position(|&repository| repository == repository_segment)
+		.unwrap_or(0);
+
+	let category = CATEGORIES
+		.iter()
+		.position(|&category| category == Some(category_segment))
+		.ok_or_else(path_not_found)?;
+
+	Ok((repository.try_into().unwrap(), category.try_into().unwrap()))
+}
+
+/// Get the name of the repository with the given ID, as used for directory names.
+pub(super) fn repository_name(repository: u8) -> Option<&'static str> {
+	REPOSITORIES.get(usize::from(repository)).copied()
 }
@@ -0,0 +1,266 @@
+use std::{
+	collections::HashSet,
+	io::{self, Read},
+};
+
+use getset::{CopyGetters, Getters};
+
+use crate::error::{Error, ErrorValue, Result};
+
+use super::{
+	file::{File, Placeholder},
+	index::{verify_index, IndexEntry, Location},
+	resource::Resource,
+};
+
+/// Report of the problems found while verifying the integrity of SqPack data.
+#[derive(Debug, Default, Getters, CopyGetters)]
+pub struct VerifyReport {
+	/// Number of index files that were checked.
+	#[get_copy = "pub"]
+	indexes_checked: usize,
+
+	/// Number of unique file entries that were checked.
+	#[get_copy = "pub"]
+	entries_checked: usize,
+
+	/// Problems found during verification.
+	#[get = "pub"]
+	issues: Vec<VerifyIssue>,
+}
+
+impl VerifyReport {
+	/// Check if verification completed without finding any problems.
+	pub fn is_ok(&self) -> bool {
+		self.issues.is_empty()
+	}
+}
+
+/// A problem found while verifying SqPack data.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum VerifyIssue {
+	/// An index file could not be read, or its headers or digests are invalid.
+	Index {
+		/// Repository the index belongs to.
+		repository: u8,
+		/// Category the index belongs to.
+		category: u8,
+		/// File name of the index, i.e. `040000.win32.index2`.
+		file: String,
+		/// Error describing the problem.
+		error: Error,
+	},
+
+	/// The entries of a category's indexes could not be read.
+	Category {
+		/// Repository of the category.
+		repository: u8,
+		/// ID of the category.
+		category: u8,
+		/// Error describing the problem.
+		error: Error,
+	},
+
+	/// A file referenced by an index entry is unreadable or corrupt.
+	Entry {
+		/// Repository the entry belongs to.
+		repository: u8,
+		/// Category the entry belongs to.
+		category: u8,
+		/// The index entry referencing the file.
+		entry: IndexEntry,
+		/// Error describing the problem.
+		error: Error,
+	},
+}
+
+/// Verify the indexes and files of a single category, recording any problems
+/// in `report`. Categories with no index files are skipped.
+pub fn verify_category<R: Resource>(
+	resource: &R,
+	repository: u8,
+	category: u8,
+	entries: impl FnOnce() -> Result<Vec<IndexEntry>>,
+	report: &mut VerifyReport,
+) {
+	if !verify_indexes(resource, repository, category, report) {
+		return;
+	}
+
+	let entries = match entries() {
+		Ok(entries) => entries,
+		Err(error) => {
+			report.issues.push(VerifyIssue::Category {
+				repository,
+				category,
+				error,
+			});
+			return;
+		}
+	};
+
+	// Entries in both index kinds typically point at the same data - only check
+	// each file once. Synonym entries do not point at valid data.
+	let mut checked = HashSet::new();
+	for entry in entries {
+		let location = entry.location();
+		let key = (location.chunk(), location.data_file(), location.offset());
+		if entry.is_synonym() || !checked.insert(key) {
+			continue;
+		}
+
+		report.entries_checked += 1;
+		if let Err(error) = verify_file(resource, repository, category, location) {
+			report.issues.push(VerifyIssue::Entry {
+				repository,
+				category,
+				entry,
+				error,
+			});
+		}
+	}
+}
+
+// Returns `true` if any index files exist for the category.
+fn verify_indexes<R: Resource>(
+	resource: &R,
+	repository: u8,
+	category: u8,
+	report: &mut VerifyReport,
+) -> bool {
+	let mut found = false;
+
+	for chunk in 0..=u8::MAX {
+		let files = [
+			(
+				"index",
+				read_index(resource.index(repository, category, chunk)),
+			),
+			(
+				"index2",
+				read_index(resource.index2(repository, category, chunk)),
+			),
+		];
+
+		let mut chunk_found = false;
+		for (extension, data) in files {
+			let file = format!(
+				"{category:02x}{repository:02x}{chunk:02x}.{}.{extension}",
+				resource.platform().name()
+			);
+			let result = match data {
+				Err(Error::NotFound(_)) => continue,
+				data => data.and_then(|data| verify_index(&file, &data)),
+			};
+
+			chunk_found = true;
+			report.indexes_checked += 1;
+			if let Err(error) = result {
+				report.issues.push(VerifyIssue::Index {
+					repository,
+					category,
+					file,
+					error,
+				});
+			}
+		}
+
+		// Chunks are sequential, stop at the first that is missing.
+		if !chunk_found {
+			break;
+		}
+		found = true;
+	}
+
+	found
+}
+
+fn read_index(reader: Result<impl Read>) -> Result<Vec<u8>> {
+	let mut buffer = Vec::new();
+	reader?.read_to_end(&mut buffer)?;
+	Ok(buffer)
+}
+
+// Reading the file in full checks its header, and that every block inflates to
+// its declared size.
+fn verify_file<R: Resource>(
+	resource: &R,
+	repository: u8,
+	category: u8,
+	location: &Location,
+) -> Result<()> {
+	let dat = resource.file(repository, category, location.clone())?;
+
+	// Known placeholders are intentionally stored without content.
+	let mut file = match File::for_platform(dat, location.platform()) {
+		Err(Error::Invalid(
+			ErrorValue::Placeholder(Placeholder::Empty | Placeholder::Rsf(_)),
+			_,
+		)) => return Ok(()),
+		other => other?,
+	};
+
+	io::copy(&mut file, &mut io::sink())?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use crate::{
+		sqpack::{FileKind, Install, SqPack, Writer},
+		utility::TempDir,
+	};
+
+	use super::VerifyIssue;
+
+	#[test]
+	fn detects_corruption() {
+		let root = TempDir::new("verify");
+		let repository = root.join("game").join("sqpack").join("ffxiv");
+		let large = (0..40_000u32)
+			.map(|value| (value.wrapping_mul(2_654_435_761) >> 13) as u8)
+			.collect::<Vec<_>>();
+
+		Writer::new()
+			.with_file(
+				"chara/test/small.bin",
+				b"small".to_vec(),
+				FileKind::Standard,
+			)
+			.unwrap()
+			.with_file("chara/test/large.bin", large, FileKind::Standard)
+			.unwrap()
+			.write(&root.join("game").join("sqpack"))
+			.unwrap();
+
+		let report = SqPack::new(Install::at(&root)).verify();
+		assert!(report.is_ok(), "{report:?}");
+		assert_eq!(report.indexes_checked(), 2);
+		assert_eq!(report.entries_checked(), 2);
+
+		// Corrupt the index data section of the index2 file.
+		let index2 = repository.join("040000.win32.index2");
+		let mut data = fs::read(&index2).unwrap();
+		data[0x800] ^= 0xFF;
+		fs::write(&index2, data).unwrap();
+
+		// Truncate the dat partway through the blocks of the large file, which also
+		// removes the small file written after it.
+		let dat = repository.join("040000.win32.dat0");
+		let data = fs::read(&dat).unwrap();
+		fs::write(&dat, &data[..0xA00]).unwrap();
+
+		let report = SqPack::new(Install::at(&root)).verify_category(0, 4);
+		let issues = report.issues();
+		assert_eq!(issues.len(), 3, "{issues:?}");
+		assert!(
+			matches!(&issues[0], VerifyIssue::Index { file, .. } if file == "040000.win32.index2")
+		);
+		assert!(issues[1..]
+			.iter()
+			.all(|issue| matches!(issue, VerifyIssue::Entry { .. })));
+	}
+}
@@ -0,0 +1,310 @@
+use std::{collections::BTreeMap, fs, io::Write, path::Path};
+
+use crate::error::{Error, ErrorValue, Result};
+
+use super::{
+	block::padding,
+	file::{encode, FileKind},
+	index::{
+		digest, finish_header, write_index1, write_index2, write_sqpack_header, SqPackKind,
+		WriteEntry,
+	},
+	sqpack::{path_metadata, repository_name},
+};
+
+const HEADER_SIZE: usize = 0x400;
+const DATA_OFFSET: usize = HEADER_SIZE * 2;
+
+// Index entries only have space for 3 bits of data file ID.
+const MAX_DAT_FILES: usize = 8;
+
+const DEFAULT_MAX_DAT_SIZE: u64 = 2_000_000_000;
+
+/// Builder for SqPack package files.
+///
+/// Files are grouped by the repository and category derived from their path,
+/// and written as `.index`, `.index2`, and `.datN` files compatible with
+/// [`Install`](super::Install), i.e. `chara/example.bin` will be written to
+/// `<path>/ffxiv/040000.win32.*`.
+#[derive(Debug)]
+pub struct Writer {
+	max_dat_size: u64,
+	files: BTreeMap<String, (Vec<u8>, FileKind)>,
+}
+
+impl Default for Writer {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Writer {
+	/// Build a new, empty writer.
+	pub fn new() -> Self {
+		Self {
+			max_dat_size: DEFAULT_MAX_DAT_SIZE,
+			files: Default::default(),
+		}
+	}
+
+	/// Set the maximum size, in bytes, of each dat file. Files that would cause a
+	/// dat to exceed this size will be written to a new dat.
+	#[must_use]
+	pub fn with_max_dat_size(mut self, size: u64) -> Self {
+		self.set_max_dat_size(size);
+		self
+	}
+
+	/// Set the maximum size, in bytes, of each dat file. Files that would cause a
+	/// dat to exceed this size will be written to a new dat.
+	pub fn set_max_dat_size(&mut self, size: u64) {
+		self.max_dat_size = size;
+	}
+
+	/// Add a file at `path` to the package, to be stored as the specified kind.
+	/// Adding a file at a path that has already been added will replace it.
+	pub fn with_file(
+		mut self,
+		path: &str,
+		data: impl Into<Vec<u8>>,
+		kind: FileKind,
+	) -> Result<Self> {
+		self.add_file(path, data, kind)?;
+		Ok(self)
+	}
+
+	/// Add a file at `path` to the package, to be stored as the specified kind.
+	/// Adding a file at a path that has already been added will replace it.
+	pub fn add_file(&mut self, path: &str, data: impl Into<Vec<u8>>, kind: FileKind) -> Result<()> {
+		// SqPack paths are always lower case.
+		let path = path.to_lowercase();
+		path_metadata(&path)?;
+		self.files.insert(path, (data.into(), kind));
+		Ok(())
+	}
+
+	/// Write the package to the SqPack directory at `path`. Any existing package
+	/// files for the categories being written will be overwritten.
+	pub fn write(&self, path: &Path) -> Result<()> {
+		let mut categories = BTreeMap::<(u8, u8), Vec<(&str, &[u8], FileKind)>>::new();
+		for (file_path, (data, kind)) in &self.files {
+			categories
+				.entry(path_metadata(file_path)?)
+				.or_default()
+				.push((file_path, data, *kind));
+		}
+
+		for ((repository, category), files) in categories {
+			let repository_name = repository_name(repository).ok_or_else(|| {
+				Error::NotFound(ErrorValue::Other(format!("repository {repository}")))
+			})?;
+			let directory = path.join(repository_name);
+			fs::create_dir_all(&directory)?;
+
+			let base_name = format!("{category:02x}{repository:02x}00.win32");
+			let (data_file_count, entries) = self.write_dats(&directory, &base_name, &files)?;
+
+			fs::write(
+				directory.join(format!("{base_name}.index")),
+				write_index1(&entries, data_file_count),
+			)?;
+			fs::write(
+				directory.join(format!("{base_name}.index2")),
+				write_index2(&entries, data_file_count),
+			)?;
+		}
+
+		Ok(())
+	}
+
+	// Dats are written as soon as they are filled, so that only one is held in
+	// memory at a time. Returns the number of dats written.
+	fn write_dats<'a>(
+		&self,
+		directory: &Path,
+		base_name: &str,
+		files: &[(&'a str, &[u8], FileKind)],
+	) -> Result<(u8, Vec<WriteEntry<'a>>)> {
+		let max_data_size = self.max_dat_size.saturating_sub(DATA_OFFSET as u64);
+		let dat_path = |index: u8| directory.join(format!("{base_name}.dat{index}"));
+
+		let mut dat = Vec::<u8>::new();
+		let mut data_file = 0u8;
+		let mut entries = Vec::with_capacity(files.len());
+
+		for (path, data, kind) in files {
+			let mut encoded = encode(data, *kind)?;
+			encoded.resize(encoded.len() + padding(encoded.len()), 0);
+
+			// Start a new dat if this file would overflow the current one. Files
+			// larger than the limit are still written, alone, to their own dat.
+			if !dat.is_empty() && (dat.len() + encoded.len()) as u64 > max_data_size {
+				if usize::from(data_file) + 1 == MAX_DAT_FILES {
+					return Err(Error::Invalid(
+						ErrorValue::Path(path.to_string()),
+						format!("package would exceed {MAX_DAT_FILES} dat files"),
+					));
+				}
+				self.write_dat(&dat_path(data_file), data_file, &dat)?;
+				dat.clear();
+				data_file += 1;
+			}
+
+			// Dat headers record the size of their data as a 32-bit value.
+			let out_of_range =
+				|reason: &str| Error::Invalid(ErrorValue::Path(path.to_string()), reason.into());
+			let offset = u32::try_from(DATA_OFFSET + dat.len())
+				.map_err(|_| out_of_range("file offset exceeds the range of a dat file"))?;
+			u32::try_from(dat.len() + encoded.len())
+				.map_err(|_| out_of_range("file would exceed the maximum size of a dat file"))?;
+
+			entries.push(WriteEntry {
+				path,
+				data_file,
+				offset,
+			});
+			dat.extend_from_slice(&encoded);
+		}
+
+		self.write_dat(&dat_path(data_file), data_file, &dat)?;
+
+		Ok((data_file + 1, entries))
+	}
+
+	fn write_dat(&self, path: &Path, index: u8, data: &[u8]) -> Result<()> {
+		let mut headers = Vec::with_capacity(DATA_OFFSET);
+		write_sqpack_header(&mut headers, SqPackKind::Dat);
+
+		let mut header = Vec::with_capacity(HEADER_SIZE);
+		for value in [
+			u32::try_from(HEADER_SIZE).unwrap(),
+			0,
+			0x10,
+			// Checked while building the dat.
+			u32::try_from(data.len()).unwrap(),
+			// Dat numbers are 1-based.
+			u32::from(index) + 1,
+			0,
+		] {
+			header.extend_from_slice(&value.to_le_bytes());
+		}
+		header.extend_from_slice(&self.max_dat_size.to_le_bytes());
+		header.extend_from_slice(&0u64.to_le_bytes());
+		header.extend_from_slice(&digest(data));
+		finish_header(&mut header);
+		headers.extend_from_slice(&header);
+
+		// Write the data directly after the headers, rather than copying it into
+		// a single buffer.
+		let mut file = fs::File::create(path)?;
+		file.write_all(&headers)?;
+		file.write_all(data)?;
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::Read;
+
+	use crate::{
+		ironworks::ListEntry,
+		sqpack::{FileKind, Install, PathDictionary, SqPack},
+		utility::{crc32, TempDir},
+	};
+
+	use super::Writer;
+
+	fn read(sqpack: &SqPack<Install>, path: &str) -> Vec<u8> {
+		let mut buffer = Vec::new();
+		sqpack.file(path).unwrap().read_to_end(&mut buffer).unwrap();
+		buffer
+	}
+
+	#[test]
+	fn round_trip() {
+		let root = TempDir::new("writer-round-trip");
+		let large = (0..40_000u32).map(|value| value as u8).collect::<Vec<_>>();
+
+		Writer::new()
+			.with_file(
+				"chara/test/small.bin",
+				b"small".to_vec(),
+				FileKind::Standard,
+			)
+			.unwrap()
+			.with_file("chara/test/large.bin", large.clone(), FileKind::Standard)
+			.unwrap()
+			.with_file("bg/ex1/test/other.bin", b"ex1".to_vec(), FileKind::Standard)
+			.unwrap()
+			.write(&root.join("game").join("sqpack"))
+			.unwrap();
+
+		let sqpack = SqPack::new(Install::at(&root));
+		assert_eq!(read(&sqpack, "chara/test/small.bin"), b"small");
+		assert_eq!(read(&sqpack, "chara/test/large.bin"), large);
+		assert_eq!(read(&sqpack, "bg/ex1/test/other.bin"), b"ex1");
+		assert!(!sqpack.exists("chara/test/missing.bin").unwrap());
+
+		let mut listing = sqpack.list("chara/test").unwrap();
+		listing.sort();
+		let mut expected = ["large.bin", "small.bin"]
+			.map(|name| ListEntry::Hashed {
+				directory: "chara/test".into(),
+				file_hash: crc32(name.as_bytes()),
+			})
+			.to_vec();
+		expected.sort();
+		assert_eq!(listing, expected);
+
+		let mut dictionary = PathDictionary::new();
+		dictionary.add_path("chara/test/small.bin");
+		let entries = sqpack.entries(0, 4).unwrap();
+		assert_eq!(entries.len(), 2);
+		let names = entries
+			.iter()
+			.filter_map(|entry| dictionary.path(entry.hash()))
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["chara/test/small.bin"]);
+		assert!(entries.iter().all(|entry| !entry.is_synonym()));
+	}
+
+	#[test]
+	fn splits_dats() {
+		let root = TempDir::new("writer-split");
+		let sqpack_path = root.join("game").join("sqpack");
+
+		let mut writer = Writer::new().with_max_dat_size(0x800 + 0x180);
+		for index in 0..3 {
+			writer
+				.add_file(
+					&format!("exd/file{index}.exd"),
+					vec![index; 100],
+					FileKind::Standard,
+				)
+				.unwrap();
+		}
+		writer.write(&sqpack_path).unwrap();
+
+		let repository = sqpack_path.join("ffxiv");
+		assert!(repository.join("0a0000.win32.dat2").exists());
+		assert!(!repository.join("0a0000.win32.dat3").exists());
+
+		let sqpack = SqPack::new(Install::at(&root));
+		for index in 0..3 {
+			assert_eq!(
+				read(&sqpack, &format!("exd/file{index}.exd")),
+				vec![index; 100]
+			);
+		}
+	}
+
+	#[test]
+	fn rejects_unknown_category() {
+		let mut writer = Writer::new();
+		assert!(writer
+			.add_file("unknown/file.bin", Vec::new(), FileKind::Standard)
+			.is_err());
+	}
+}
@@ -39,7 +39,12 @@ const fn build_table() -> Table {
 }
 
 pub fn crc32(bytes: &[u8]) -> u32 {
-	let mut working = u32::MAX;
+	crc32_continue(u32::MAX, bytes)
+}
+
+/// Continue a checksum previously returned by [`crc32`] with additional bytes.
+pub fn crc32_continue(crc: u32, bytes: &[u8]) -> u32 {
+	let mut working = crc;
 
 	let mut start = 0;
 	let mut end = bytes.len();
@@ -1,9 +1,18 @@
+mod crc;
 mod hash_map_cache;
 mod option_cache;
 mod take_seekable;
+#[cfg(all(test, any(feature = "loose", feature = "sqpack")))]
+mod temp_dir;
 
 pub use {
+	crc::crc32,
 	hash_map_cache::{HashMapCache, HashMapCacheExt},
 	option_cache::{OptionCache, OptionCacheExt},
 	take_seekable::{TakeSeekable, TakeSeekableExt},
 };
+
+#[cfg(feature = "patch")]
+pub use crc::crc32_continue;
+#[cfg(all(test, any(feature = "loose", feature = "sqpack")))]
+pub use temp_dir::TempDir;
@@ -0,0 +1,43 @@
+use std::{
+	fs,
+	ops::Deref,
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Uniquely named directory within the system temporary directory, removed
+/// along with its contents when dropped.
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+	pub fn new(name: &str) -> Self {
+		// Tests run in parallel, and may share a name - keep each directory distinct.
+		static COUNTER: AtomicU64 = AtomicU64::new(0);
+		let path = std::env::temp_dir().join(format!(
+			"ironworks-{name}-{}-{}",
+			std::process::id(),
+			COUNTER.fetch_add(1, Ordering::Relaxed)
+		));
+
+		// Clear out anything left behind by an earlier process with the same ID.
+		let _ = fs::remove_dir_all(&path);
+		fs::create_dir_all(&path).unwrap();
+
+		Self(path)
+	}
+}
+
+impl Deref for TempDir {
+	type Target = Path;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl Drop for TempDir {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
@@ -0,0 +1,107 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use getset::CopyGetters;
+
+use crate::error::{Error, ErrorValue, Result};
+
+/// Version of game data, as recorded in `.ver` files and patch names, i.e.
+/// `2024.06.18.0000.0000`. Versions are ordered chronologically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, CopyGetters)]
+#[get_copy = "pub"]
+pub struct GameVersion {
+	/// Year the version was built.
+	year: u16,
+	/// Month the version was built.
+	month: u8,
+	/// Day the version was built.
+	day: u8,
+	/// Build part, distinguishing multiple builds made on the same day.
+	part: u16,
+	/// Revision of the build.
+	revision: u16,
+}
+
+impl GameVersion {
+	/// Build a version from its components.
+	pub fn new(year: u16, month: u8, day: u8, part: u16, revision: u16) -> Self {
+		Self {
+			year,
+			month,
+			day,
+			part,
+			revision,
+		}
+	}
+
+	/// Read the version recorded in the `.ver` file at `path`, i.e. `ffxivgame.ver`.
+	pub fn from_ver_file(path: &Path) -> Result<Self> {
+		fs::read_to_string(path)?.parse()
+	}
+
+	/// Parse the version from a patch name, i.e. `D2024.06.18.0000.0001`. The
+	/// leading patch type and any trailing part suffix, i.e. the `a` of
+	/// `H2017.06.06.0000.0001a`, are ignored.
+	pub fn from_patch_name(name: &str) -> Result<Self> {
+		name.trim_start_matches(|char: char| char.is_ascii_uppercase())
+			.trim_end_matches(|char: char| char.is_ascii_lowercase())
+			.parse()
+	}
+}
+
+impl FromStr for GameVersion {
+	type Err = Error;
+
+	fn from_str(value: &str) -> Result<Self> {
+		let invalid = || {
+			Error::Invalid(
+				ErrorValue::Other(format!("game version {value:?}")),
+				"expected a version in the format YYYY.MM.DD.PPPP.RRRR".into(),
+			)
+		};
+
+		let mut segments = value.trim().split('.');
+		let mut next = || segments.next().ok_or_else(invalid);
+		let version = Self {
+			year: next()?.parse().map_err(|_| invalid())?,
+			month: next()?.parse().map_err(|_| invalid())?,
+			day: next()?.parse().map_err(|_| invalid())?,
+			part: next()?.parse().map_err(|_| invalid())?,
+			revision: next()?.parse().map_err(|_| invalid())?,
+		};
+
+		match segments.next() {
+			Some(_) => Err(invalid()),
+			None => Ok(version),
+		}
+	}
+}
+
+impl fmt::Display for GameVersion {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			formatter,
+			"{:04}.{:02}.{:02}.{:04}.{:04}",
+			self.year, self.month, self.day, self.part, self.revision
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::GameVersion;
+
+	#[test]
+	fn parse_and_order() {
+		let version = "2024.06.18.0000.0000\r\n".parse::<GameVersion>().unwrap();
+		assert_eq!(version, GameVersion::new(2024, 6, 18, 0, 0));
+		assert_eq!(version.to_string(), "2024.06.18.0000.0000");
+
+		let patch = GameVersion::from_patch_name("H2017.06.06.0000.0001a").unwrap();
+		assert_eq!(patch, GameVersion::new(2017, 6, 6, 0, 1));
+		assert!(patch < version);
+		assert!(GameVersion::new(2024, 6, 18, 0, 1) > version);
+
+		assert!("2024.06.18".parse::<GameVersion>().is_err());
+		assert!("2024.06.18.0000.0000.0".parse::<GameVersion>().is_err());
+	}
+}
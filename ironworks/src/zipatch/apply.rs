@@ -0,0 +1,592 @@
+use std::{
+	collections::HashMap,
+	fs,
+	io::{self, BufReader, Read, Seek, SeekFrom, Write},
+	path::{Component, Path, PathBuf},
+};
+
+use getset::{CopyGetters, Getters};
+
+use crate::{
+	error::{Error, ErrorValue, Result},
+	file::{
+		patch::{
+			Chunk, FileOperation, FileOperationCommand, HeaderFileKind, HeaderKind, OptionKind,
+			SqPackChunk, SqPackFile, TargetPlatform, ZiPatch as ZiPatchFile,
+		},
+		File,
+	},
+	sqpack::{BlockPayload, Platform},
+};
+
+use super::repository::{Patch, PatchRepository};
+
+const BLOCK_SIZE: u32 = 1 << 7;
+const HEADER_SIZE: u64 = 1024;
+
+/// Applies ZiPatch files to an on-disk game installation.
+///
+/// Paths within patches are relative to the game directory of an installation,
+/// i.e. `C:\...\FINAL FANTASY XIV - A Realm Reborn\game`, which should be used
+/// as the target directory. Patches must be applied in order, starting from
+/// the data the first patch was built against.
+#[derive(Debug)]
+pub struct Applier {
+	target: PathBuf,
+	platform: Platform,
+	dry_run: bool,
+}
+
+impl Applier {
+	/// Build an applier targeting the game directory at `target`.
+	pub fn new(target: impl Into<PathBuf>) -> Self {
+		Self {
+			target: target.into(),
+			platform: Platform::default(),
+			dry_run: false,
+		}
+	}
+
+	/// Set the platform of the target installation. Patches targeting other
+	/// platforms will be rejected.
+	#[must_use]
+	pub fn with_platform(mut self, platform: Platform) -> Self {
+		self.set_platform(platform);
+		self
+	}
+
+	/// Set the platform of the target installation. Patches targeting other
+	/// platforms will be rejected.
+	pub fn set_platform(&mut self, platform: Platform) {
+		self.platform = platform;
+	}
+
+	/// Set whether patches should only be checked and reported, without making
+	/// any changes to the target directory.
+	#[must_use]
+	pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+		self.set_dry_run(dry_run);
+		self
+	}
+
+	/// Set whether patches should only be checked and reported, without making
+	/// any changes to the target directory.
+	pub fn set_dry_run(&mut self, dry_run: bool) {
+		self.dry_run = dry_run;
+	}
+
+	/// Apply every patch in `repository`, in order.
+	pub fn apply_repository(&self, repository: &PatchRepository) -> Result<ApplyReport> {
+		let mut report = ApplyReport::default();
+		for patch in &repository.patches {
+			self.apply_into(patch, &mut report)?;
+		}
+		Ok(report)
+	}
+
+	/// Apply a single patch.
+	pub fn apply(&self, patch: &Patch) -> Result<ApplyReport> {
+		let mut report = ApplyReport::default();
+		self.apply_into(patch, &mut report)?;
+		Ok(report)
+	}
+
+	fn apply_into(&self, patch: &Patch, report: &mut ApplyReport) -> Result<()> {
+		let zipatch = ZiPatchFile::read(BufReader::new(fs::File::open(&patch.path)?))?;
+
+		let mut context = ApplyContext {
+			applier: self,
+			patch,
+			source: BufReader::new(fs::File::open(&patch.path)?),
+			files: HashMap::new(),
+			ignore_missing: false,
+			report,
+		};
+
+		for chunk in zipatch.chunks() {
+			match chunk? {
+				Chunk::Apply(chunk) => {
+					if let OptionKind::IgnoreMissing = chunk.option() {
+						context.ignore_missing = chunk.value() != 0;
+					}
+				}
+				Chunk::AddDirectory(chunk) => context.add_directory(chunk.path())?,
+				Chunk::DeleteDirectory(chunk) => context.delete_directory(chunk.path())?,
+				Chunk::SqPack(chunk) => context.sqpack(chunk)?,
+				Chunk::FileHeader(_) | Chunk::EndOfFile => {}
+			}
+		}
+
+		for file in context.files.values_mut() {
+			file.flush()?;
+		}
+
+		context.report.patches.push(patch.name.clone());
+
+		Ok(())
+	}
+}
+
+/// Report of the changes made, or that would be made in a dry run, while
+/// applying patches.
+#[derive(Debug, Default, Getters)]
+#[get = "pub"]
+pub struct ApplyReport {
+	/// Names of the patches that were applied, in order.
+	patches: Vec<String>,
+	/// Changes made to the target directory, in order.
+	actions: Vec<ApplyAction>,
+}
+
+/// A single change made to the target directory.
+#[derive(Debug, Clone, Getters, CopyGetters)]
+pub struct ApplyAction {
+	/// Name of the patch the change was made by.
+	#[get = "pub"]
+	patch: String,
+	/// Path of the changed file or directory, relative to the target directory.
+	#[get = "pub"]
+	path: PathBuf,
+	/// The change that was made.
+	#[get_copy = "pub"]
+	kind: ApplyActionKind,
+}
+
+/// Kind of change made to the target directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ApplyActionKind {
+	/// Data was written to a file.
+	Write {
+		/// Offset within the file, in bytes.
+		offset: u64,
+		/// Number of bytes written.
+		size: u64,
+	},
+	/// A region of a file was cleared.
+	Clear {
+		/// Offset within the file, in bytes.
+		offset: u64,
+		/// Number of bytes cleared.
+		size: u64,
+	},
+	/// A file was truncated to empty.
+	Truncate,
+	/// A file was deleted.
+	DeleteFile,
+	/// A directory was created.
+	CreateDirectory,
+	/// A directory was deleted.
+	DeleteDirectory,
+}
+
+struct ApplyContext<'a> {
+	applier: &'a Applier,
+	patch: &'a Patch,
+	source: BufReader<fs::File>,
+	files: HashMap<PathBuf, io::BufWriter<fs::File>>,
+	ignore_missing: bool,
+	report: &'a mut ApplyReport,
+}
+
+impl ApplyContext<'_> {
+	fn sqpack(&mut self, chunk: SqPackChunk) -> Result<()> {
+		match chunk {
+			SqPackChunk::TargetInfo(command) => self.check_platform(command.platform()),
+
+			SqPackChunk::Add(command) => {
+				let path = self.sqpack_path(command.file(), SqPackFileKind::Dat);
+				let offset = u64::from(command.target_offset());
+				let size = u64::from(command.data_size());
+
+				let mut data = vec![0; size.try_into().unwrap()];
+				self.source.seek(SeekFrom::Start(command.source_offset()))?;
+				self.source.read_exact(&mut data)?;
+
+				self.write(&path, offset, &data)?;
+				self.clear(&path, offset + size, command.delete_size().into())
+			}
+
+			SqPackChunk::Delete(command) => {
+				let path = self.sqpack_path(command.file(), SqPackFileKind::Dat);
+				self.empty_block(
+					&path,
+					command.target_offset().into(),
+					command.delete_size().into(),
+				)
+			}
+
+			SqPackChunk::Expand(command) => {
+				let path = self.sqpack_path(command.file(), SqPackFileKind::Dat);
+				self.empty_block(
+					&path,
+					command.target_offset().into(),
+					command.delete_size().into(),
+				)
+			}
+
+			SqPackChunk::HeaderUpdate(command) => {
+				let kind = match command.file_kind() {
+					HeaderFileKind::Dat => SqPackFileKind::Dat,
+					HeaderFileKind::Index => SqPackFileKind::Index,
+				};
+				let path = self.sqpack_path(command.file(), kind);
+				let offset = match command.header_kind() {
+					HeaderKind::Version => 0,
+					HeaderKind::Data | HeaderKind::Index => HEADER_SIZE,
+				};
+
+				let mut data = vec![0; command.size().try_into().unwrap()];
+				self.source.seek(SeekFrom::Start(command.offset()))?;
+				self.source.read_exact(&mut data)?;
+
+				self.write(&path, offset, &data)
+			}
+
+			SqPackChunk::FileOperation(command) => self.file_operation(command),
+
+			// Index updates are unused in practice, and patch info is purely informational.
+			SqPackChunk::IndexUpdate(_) | SqPackChunk::PatchInfo(_) => Ok(()),
+		}
+	}
+
+	fn check_platform(&self, platform: TargetPlatform) -> Result<()> {
+		let expected = self.applier.platform;
+		let matches = matches!(
+			(platform, expected),
+			(TargetPlatform::Win32, Platform::Win32)
+				| (TargetPlatform::Ps3, Platform::PS3)
+				| (TargetPlatform::Ps4, Platform::PS4)
+		);
+
+		match matches {
+			true => Ok(()),
+			false => Err(Error::Invalid(
+				ErrorValue::Other(format!("patch {}", self.patch.name)),
+				format!("patch targets platform {platform:?}, expected {expected:?}"),
+			)),
+		}
+	}
+
+	fn file_operation(&mut self, command: FileOperationCommand) -> Result<()> {
+		let path = PathBuf::from(command.path().to_string());
+		self.target_path(&path)?;
+
+		match command.operation() {
+			FileOperation::AddFile(blocks) => {
+				let offset = command.target_offset();
+				if offset == 0 {
+					self.truncate(&path)?;
+				}
+
+				let mut data = Vec::with_capacity(command.target_size().try_into().unwrap());
+				for block in blocks {
+					self.source.seek(SeekFrom::Start(block.offset()))?;
+					BlockPayload::new(
+						&mut self.source,
+						block.compressed_size(),
+						block.decompressed_size(),
+					)
+					.read_to_end(&mut data)?;
+				}
+
+				self.write(&path, offset, &data)
+			}
+
+			FileOperation::DeleteFile => self.delete_file(&path),
+
+			FileOperation::MakeDirTree => self.add_directory(&path.to_string_lossy()),
+
+			FileOperation::RemoveAll => {
+				let repository = repository_name(command.repository_id());
+				for directory in ["sqpack", "movie"] {
+					self.remove_all(&Path::new(directory).join(&repository))?;
+				}
+				Ok(())
+			}
+		}
+	}
+
+	fn sqpack_path(&self, file: SqPackFile, kind: SqPackFileKind) -> PathBuf {
+		let repository = repository_name(file.sub_id() >> 8);
+		let extension = match (kind, file.file_id()) {
+			(SqPackFileKind::Dat, id) => format!("dat{id}"),
+			(SqPackFileKind::Index, 0) => "index".to_string(),
+			(SqPackFileKind::Index, id) => format!("index{id}"),
+		};
+
+		Path::new("sqpack").join(repository).join(format!(
+			"{:02x}{:04x}.{}.{extension}",
+			file.main_id(),
+			file.sub_id(),
+			self.applier.platform.name()
+		))
+	}
+
+	fn record(&mut self, path: &Path, kind: ApplyActionKind) {
+		self.report.actions.push(ApplyAction {
+			patch: self.patch.name.clone(),
+			path: path.to_owned(),
+			kind,
+		});
+	}
+
+	// Resolve a path from the patch within the target directory. Paths that could
+	// escape the target are rejected.
+	fn target_path(&self, path: &Path) -> Result<PathBuf> {
+		let escapes = path
+			.components()
+			.any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+		if escapes {
+			return Err(Error::Invalid(
+				ErrorValue::Path(path.display().to_string()),
+				format!(
+					"patch {} references a path outside the target directory",
+					self.patch.name
+				),
+			));
+		}
+
+		Ok(self.applier.target.join(path))
+	}
+
+	fn file(&mut self, path: &Path) -> Result<&mut io::BufWriter<fs::File>> {
+		if !self.files.contains_key(path) {
+			let full_path = self.target_path(path)?;
+			if let Some(parent) = full_path.parent() {
+				fs::create_dir_all(parent)?;
+			}
+
+			let file = fs::OpenOptions::new()
+				.write(true)
+				.create(true)
+				.truncate(false)
+				.open(full_path)?;
+			self.files.insert(path.to_owned(), io::BufWriter::new(file));
+		}
+
+		Ok(self.files.get_mut(path).unwrap())
+	}
+
+	fn write(&mut self, path: &Path, offset: u64, data: &[u8]) -> Result<()> {
+		self.record(
+			path,
+			ApplyActionKind::Write {
+				offset,
+				size: data.len().try_into().unwrap(),
+			},
+		);
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		let file = self.file(path)?;
+		file.seek(SeekFrom::Start(offset))?;
+		file.write_all(data)?;
+		Ok(())
+	}
+
+	fn clear(&mut self, path: &Path, offset: u64, size: u64) -> Result<()> {
+		if size == 0 {
+			return Ok(());
+		}
+
+		self.record(path, ApplyActionKind::Clear { offset, size });
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		let file = self.file(path)?;
+		file.seek(SeekFrom::Start(offset))?;
+		io::copy(&mut io::repeat(0).take(size), file)?;
+		Ok(())
+	}
+
+	// Clears a region of a dat file, marking it as a single empty block spanning the region.
+	fn empty_block(&mut self, path: &Path, offset: u64, size: u64) -> Result<()> {
+		self.clear(path, offset, size)?;
+		if self.applier.dry_run || size == 0 {
+			return Ok(());
+		}
+
+		let block_count = u32::try_from(size / u64::from(BLOCK_SIZE)).unwrap();
+		let header = [BLOCK_SIZE, 0, 0, block_count - 1]
+			.into_iter()
+			.flat_map(u32::to_le_bytes)
+			.collect::<Vec<_>>();
+
+		let file = self.file(path)?;
+		file.seek(SeekFrom::Start(offset))?;
+		file.write_all(&header)?;
+		Ok(())
+	}
+
+	fn truncate(&mut self, path: &Path) -> Result<()> {
+		self.record(path, ApplyActionKind::Truncate);
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		let file = self.file(path)?;
+		file.flush()?;
+		file.get_ref().set_len(0)?;
+		Ok(())
+	}
+
+	fn delete_file(&mut self, path: &Path) -> Result<()> {
+		self.record(path, ApplyActionKind::DeleteFile);
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		let full_path = self.target_path(path)?;
+		self.files.remove(path);
+		let result = fs::remove_file(full_path);
+		self.check_missing(result)
+	}
+
+	fn remove_all(&mut self, directory: &Path) -> Result<()> {
+		let full_path = self.target_path(directory)?;
+		if !full_path.is_dir() {
+			return Ok(());
+		}
+
+		for entry in fs::read_dir(full_path)? {
+			let entry = entry?;
+			if entry.file_type()?.is_file() {
+				self.delete_file(&directory.join(entry.file_name()))?;
+			}
+		}
+
+		Ok(())
+	}
+
+	fn add_directory(&mut self, path: &str) -> Result<()> {
+		let path = Path::new(path);
+		let full_path = self.target_path(path)?;
+		self.record(path, ApplyActionKind::CreateDirectory);
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		fs::create_dir_all(full_path)?;
+		Ok(())
+	}
+
+	fn delete_directory(&mut self, path: &str) -> Result<()> {
+		let path = Path::new(path);
+		let full_path = self.target_path(path)?;
+		self.record(path, ApplyActionKind::DeleteDirectory);
+		if self.applier.dry_run {
+			return Ok(());
+		}
+
+		let result = fs::remove_dir(full_path);
+		self.check_missing(result)
+	}
+
+	fn check_missing(&self, result: io::Result<()>) -> Result<()> {
+		match result {
+			Err(error) if error.kind() == io::ErrorKind::NotFound && self.ignore_missing => Ok(()),
+			other => Ok(other?),
+		}
+	}
+}
+
+#[derive(Clone, Copy)]
+enum SqPackFileKind {
+	Dat,
+	Index,
+}
+
+fn repository_name(repository: u16) -> String {
+	match repository {
+		0 => "ffxiv".to_string(),
+		other => format!("ex{other}"),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{fs, path::Path};
+
+	use crate::{error::Error, sqpack::Platform, utility::TempDir};
+
+	use super::{
+		super::fixture::{add_file, chunk, sqpack, target_info, write_patch},
+		Applier, ApplyActionKind, Patch,
+	};
+
+	fn patch(directory: &Path, platform: u16) -> Patch {
+		let mut bytes = target_info(platform);
+
+		// Add directory
+		let path = b"movie/ffxiv";
+		let mut data = u32::try_from(path.len()).unwrap().to_be_bytes().to_vec();
+		data.extend_from_slice(path);
+		bytes.extend(chunk(b"ADIR", &data));
+
+		// Add 128 bytes of data to 0a0000.win32.dat0 at offset 256, clearing 128 after.
+		let mut data = vec![0; 3];
+		for value in [0x0Au16, 0] {
+			data.extend_from_slice(&value.to_be_bytes());
+		}
+		for value in [0u32, 2, 1, 1] {
+			data.extend_from_slice(&value.to_be_bytes());
+		}
+		data.extend_from_slice(&[0xAB; 128]);
+		bytes.extend(sqpack(b'A', &data));
+
+		// Add an uncompressed file.
+		bytes.extend(add_file("ffxivgame.ver", b"2024.01.01.0000.0000"));
+
+		write_patch(directory, "D2024.01.01.0000.0000", bytes)
+	}
+
+	#[test]
+	fn applies_patch() {
+		let root = TempDir::new("apply");
+		let target = root.join("game");
+		fs::create_dir_all(&target).unwrap();
+		let patch = patch(&root, 0);
+
+		let report = Applier::new(&target)
+			.with_dry_run(true)
+			.apply(&patch)
+			.unwrap();
+		assert_eq!(report.actions().len(), 5);
+		assert_eq!(
+			report.actions()[1].kind(),
+			ApplyActionKind::Write {
+				offset: 256,
+				size: 128
+			}
+		);
+		assert!(!target.join("movie").exists());
+
+		Applier::new(&target).apply(&patch).unwrap();
+		assert!(target.join("movie/ffxiv").is_dir());
+		let dat = fs::read(target.join("sqpack/ffxiv/0a0000.win32.dat0")).unwrap();
+		assert_eq!(dat.len(), 512);
+		assert!(dat[256..384].iter().all(|&byte| byte == 0xAB));
+		assert_eq!(
+			fs::read_to_string(target.join("ffxivgame.ver")).unwrap(),
+			"2024.01.01.0000.0000"
+		);
+
+		let result = Applier::new(&target)
+			.with_platform(Platform::PS4)
+			.apply(&patch);
+		assert!(result.is_err());
+
+		// Paths escaping the target directory are rejected, even in dry runs.
+		let mut bytes = target_info(0);
+		bytes.extend(add_file("../escape", b"escaped"));
+		let patch = write_patch(&root, "D2024.02.01.0000.0000", bytes);
+		for dry_run in [true, false] {
+			let result = Applier::new(&target).with_dry_run(dry_run).apply(&patch);
+			assert!(matches!(result, Err(Error::Invalid(..))));
+		}
+		assert!(!root.join("escape").exists());
+	}
+}
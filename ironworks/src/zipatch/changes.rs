@@ -0,0 +1,312 @@
+use std::{
+	collections::{BTreeMap, BTreeSet, HashMap},
+	ops::RangeBounds,
+	sync::Arc,
+};
+
+use getset::{CopyGetters, Getters};
+
+use crate::{
+	error::Result,
+	sqpack::{IndexEntry, IndexHash, Location, PathDictionary, SqPack},
+	version::GameVersion,
+};
+
+use super::{
+	lookup::{PatchLookupData, SqPackFileExtension, SqPackSpecifier},
+	repository::{Patch, PatchRepository},
+	view::View,
+	zipatch::LookupCache,
+};
+
+/// Game files changed by a single patch.
+#[derive(Debug, Getters, CopyGetters)]
+pub struct PatchChanges {
+	/// Name of the patch.
+	#[get = "pub"]
+	patch: String,
+	/// Game version the patch updates data to.
+	#[get_copy = "pub"]
+	version: GameVersion,
+	/// Files changed by the patch.
+	#[get = "pub"]
+	files: Vec<FileChange>,
+}
+
+/// A game file changed by a patch.
+#[derive(Debug, Getters, CopyGetters)]
+pub struct FileChange {
+	/// SqPack category the file belongs to.
+	#[get_copy = "pub"]
+	category: u8,
+	/// Index hashes identifying the file. Files referenced by multiple index
+	/// entries will be identified by the hash of each entry.
+	#[get = "pub"]
+	hashes: Vec<IndexHash>,
+	/// Path of the file, if it could be resolved via the provided dictionary.
+	#[get = "pub"]
+	path: Option<String>,
+	/// The change made to the file.
+	#[get_copy = "pub"]
+	kind: ChangeKind,
+}
+
+/// Kind of change made to a game file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ChangeKind {
+	/// The file was added to the game data.
+	Added,
+	/// The file's data was modified.
+	Changed,
+	/// The file was removed from the game data.
+	Removed,
+}
+
+pub fn changes(
+	cache: &Arc<LookupCache>,
+	repository_id: u8,
+	repository: &PatchRepository,
+	range: impl RangeBounds<GameVersion>,
+	dictionary: Option<&PathDictionary>,
+) -> Result<Vec<PatchChanges>> {
+	// Each patch's "after" state is the next patch's "before" state - carry it
+	// forward so that each index state is only built once.
+	let mut previous: Option<(usize, SqPack<View>)> = None;
+	let mut changes = Vec::new();
+
+	for (index, patch) in repository.patches.iter().enumerate() {
+		if !range.contains(&patch.version) {
+			continue;
+		}
+
+		let before = match previous.take() {
+			Some((previous_index, after)) if previous_index + 1 == index => after,
+			_ => sqpack(cache, repository_id, &repository.patches[..index]),
+		};
+		let after = sqpack(cache, repository_id, &repository.patches[..=index]);
+		let lookup = cache.lookup(patch)?;
+
+		let mut files = Vec::new();
+		for category in categories(lookup.data(), repository_id) {
+			files.extend(diff_category(
+				lookup.data(),
+				(repository_id, category),
+				&before.entries(repository_id, category)?,
+				&after.entries(repository_id, category)?,
+				dictionary,
+			));
+		}
+
+		changes.push(PatchChanges {
+			patch: patch.name.clone(),
+			version: patch.version,
+			files,
+		});
+		previous = Some((index, after));
+	}
+
+	Ok(changes)
+}
+
+fn sqpack(cache: &Arc<LookupCache>, repository_id: u8, patches: &[Patch]) -> SqPack<View> {
+	let repository = PatchRepository {
+		patches: patches.to_vec(),
+	};
+
+	SqPack::new(View::new(
+		HashMap::from([(repository_id, Arc::new(repository))]),
+		cache.clone(),
+	))
+}
+
+// Get the categories touched by any of the commands in a patch.
+fn categories(data: &PatchLookupData, repository_id: u8) -> BTreeSet<u8> {
+	data.file_chunks
+		.keys()
+		.chain(data.resource_chunks.keys().map(|(specifier, _)| specifier))
+		.filter(|specifier| specifier.repository == repository_id)
+		.map(|specifier| specifier.category)
+		.collect()
+}
+
+fn diff_category(
+	data: &PatchLookupData,
+	(repository, category): (u8, u8),
+	before: &[IndexEntry],
+	after: &[IndexEntry],
+	dictionary: Option<&PathDictionary>,
+) -> Vec<FileChange> {
+	// Synonym entries do not point to valid file data, and can't be compared.
+	let entry_map = |entries: &[IndexEntry]| {
+		entries
+			.iter()
+			.filter(|entry| !entry.is_synonym())
+			.map(|entry| (entry.hash(), entry.location().clone()))
+			.collect::<HashMap<_, _>>()
+	};
+	let before = entry_map(before);
+	let after = entry_map(after);
+
+	// Multiple entries may point at the same data - group hashes by the location
+	// of the file to report each file once.
+	let mut groups = BTreeMap::<_, Vec<IndexHash>>::new();
+	for (hash, location) in &after {
+		let kind = match before.get(hash) {
+			None => ChangeKind::Added,
+			Some(previous) if location_key(previous) != location_key(location) => {
+				ChangeKind::Changed
+			}
+			Some(_) if is_written(data, repository, category, location) => ChangeKind::Changed,
+			Some(_) => continue,
+		};
+		groups
+			.entry((kind, location_key(location)))
+			.or_default()
+			.push(*hash);
+	}
+
+	for (hash, location) in &before {
+		if !after.contains_key(hash) {
+			groups
+				.entry((ChangeKind::Removed, location_key(location)))
+				.or_default()
+				.push(*hash);
+		}
+	}
+
+	groups
+		.into_iter()
+		.map(|((kind, _), mut hashes)| {
+			hashes.sort_unstable();
+			let path = dictionary.and_then(|dictionary| {
+				hashes
+					.iter()
+					.find_map(|hash| dictionary.path(*hash))
+					.map(String::from)
+			});
+
+			FileChange {
+				category,
+				hashes,
+				path,
+				kind,
+			}
+		})
+		.collect()
+}
+
+fn location_key(location: &Location) -> (u8, u8, u32) {
+	(location.chunk(), location.data_file(), location.offset())
+}
+
+// Check if the data at a location was written to by the patch.
+fn is_written(data: &PatchLookupData, repository: u8, category: u8, location: &Location) -> bool {
+	let specifier = SqPackSpecifier {
+		repository,
+		category,
+		chunk: location.chunk(),
+		extension: SqPackFileExtension::Dat(location.data_file()),
+	};
+
+	if data
+		.resource_chunks
+		.contains_key(&(specifier.clone(), location.offset()))
+	{
+		return true;
+	}
+
+	let start = u64::from(location.offset());
+	let end = start + u64::from(location.size().unwrap_or(1));
+
+	data.file_chunks.get(&specifier).is_some_and(|chunks| {
+		chunks.iter().any(|chunk| {
+			chunk.target_offset < end && chunk.target_offset + chunk.target_size > start
+		})
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use crate::{
+		sqpack::{FileKind, PathDictionary, Writer},
+		utility::TempDir,
+		zipatch::{fixture, PatchRepository, ZiPatch},
+	};
+
+	use super::ChangeKind;
+
+	fn dataset_patch(root: &std::path::Path, name: &str, files: &[(&str, &[u8])]) -> super::Patch {
+		let directory = root.join(name);
+		let mut writer = Writer::new();
+		for (path, data) in files {
+			writer.add_file(path, *data, FileKind::Standard).unwrap();
+		}
+		writer.write(&directory).unwrap();
+
+		let mut chunks = Vec::new();
+		for entry in fs::read_dir(directory.join("ffxiv")).unwrap() {
+			let entry = entry.unwrap();
+			let path = format!("sqpack/ffxiv/{}", entry.file_name().to_string_lossy());
+			chunks.extend(fixture::add_file(&path, &fs::read(entry.path()).unwrap()));
+		}
+
+		fixture::write_patch(root, name, chunks)
+	}
+
+	#[test]
+	fn lists_changes() {
+		let root = TempDir::new("changes");
+
+		let repository = PatchRepository {
+			patches: vec![
+				dataset_patch(
+					&root,
+					"D2024.01.01.0000.0000",
+					&[("exd/a.exd", b"one"), ("exd/b.exd", b"two")],
+				),
+				dataset_patch(
+					&root,
+					"D2024.02.01.0000.0000",
+					&[("exd/a.exd", b"one, but longer"), ("exd/c.exd", b"three")],
+				),
+			],
+		};
+
+		let mut dictionary = PathDictionary::new();
+		for path in ["exd/a.exd", "exd/b.exd", "exd/c.exd"] {
+			dictionary.add_path(path);
+		}
+
+		let changes = ZiPatch::new()
+			.changes(0, &repository, .., Some(&dictionary))
+			.unwrap();
+		let summary = |index: usize| {
+			let mut files = changes[index]
+				.files()
+				.iter()
+				.map(|file| (file.path().clone().unwrap(), file.kind()))
+				.collect::<Vec<_>>();
+			files.sort();
+			files
+		};
+
+		assert_eq!(changes.len(), 2);
+		assert_eq!(
+			summary(0),
+			[
+				("exd/a.exd".into(), ChangeKind::Added),
+				("exd/b.exd".into(), ChangeKind::Added)
+			]
+		);
+		assert_eq!(
+			summary(1),
+			[
+				("exd/a.exd".into(), ChangeKind::Changed),
+				("exd/b.exd".into(), ChangeKind::Removed),
+				("exd/c.exd".into(), ChangeKind::Added)
+			]
+		);
+	}
+}
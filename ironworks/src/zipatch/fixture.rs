@@ -0,0 +1,68 @@
+//! Builders for ZiPatch files used across the module's tests.
+
+use std::{fs, path::Path};
+
+use crate::{utility, version::GameVersion};
+
+use super::Patch;
+
+/// Build a chunk with a valid checksum.
+pub fn chunk(magic: &[u8; 4], data: &[u8]) -> Vec<u8> {
+	let mut chunk = u32::try_from(data.len()).unwrap().to_be_bytes().to_vec();
+	chunk.extend_from_slice(magic);
+	chunk.extend_from_slice(data);
+	let checksum = !utility::crc32(&[magic.as_slice(), data].concat());
+	chunk.extend_from_slice(&checksum.to_be_bytes());
+	chunk
+}
+
+/// Build an SQPK chunk for `command`.
+pub fn sqpack(command: u8, data: &[u8]) -> Vec<u8> {
+	let size = u32::try_from(data.len() + 5).unwrap();
+	let mut body = size.to_be_bytes().to_vec();
+	body.push(command);
+	body.extend_from_slice(data);
+	chunk(b"SQPK", &body)
+}
+
+/// Build a target info command for `platform`.
+pub fn target_info(platform: u16) -> Vec<u8> {
+	let mut data = vec![0; 3];
+	data.extend_from_slice(&platform.to_be_bytes());
+	data.extend_from_slice(&(-1i16).to_be_bytes());
+	data.extend_from_slice(&[0; 20]);
+	sqpack(b'T', &data)
+}
+
+/// Build a file operation chunk adding `data` as a single uncompressed block.
+pub fn add_file(path: &str, data: &[u8]) -> Vec<u8> {
+	let path = [path.as_bytes(), &[0]].concat();
+	let mut command = vec![b'A', 0, 0];
+	command.extend_from_slice(&0u64.to_be_bytes());
+	command.extend_from_slice(&u64::try_from(data.len()).unwrap().to_be_bytes());
+	command.extend_from_slice(&u32::try_from(path.len()).unwrap().to_be_bytes());
+	command.extend_from_slice(&[0; 4]);
+	command.extend_from_slice(&path);
+	for value in [16u32, 0, 32_000, u32::try_from(data.len()).unwrap()] {
+		command.extend_from_slice(&value.to_le_bytes());
+	}
+	command.extend_from_slice(data);
+	let aligned = (data.len() + 0x8F) & !0x7F;
+	command.resize(command.len() + aligned - 16 - data.len(), 0);
+	sqpack(b'F', &command)
+}
+
+/// Write a patch file named `name` containing `chunks` to `directory`.
+pub fn write_patch(directory: &Path, name: &str, chunks: Vec<u8>) -> Patch {
+	let mut bytes = b"\x91ZIPATCH\x0D\x0A\x1A\x0A".to_vec();
+	bytes.extend(chunks);
+	bytes.extend(chunk(b"EOF_", &[]));
+
+	let path = directory.join(format!("{name}.patch"));
+	fs::write(&path, bytes).unwrap();
+	Patch {
+		name: name.into(),
+		version: GameVersion::from_patch_name(name).unwrap(),
+		path,
+	}
+}
@@ -0,0 +1,591 @@
+use std::{
+	collections::BTreeSet,
+	fs,
+	io::{BufReader, Cursor, Read, Seek, SeekFrom, Write},
+	path::{Path, PathBuf},
+};
+
+use binrw::{BinWrite, Endian};
+
+use crate::{
+	error::Result,
+	file::patch::{
+		AddCommand, AddDirectoryChunk, DeleteCommand, DeleteDirectoryChunk, ExpandCommand,
+		FileHeaderChunk, FileOperation, FileOperationCommand, HeaderFileKind, HeaderKind,
+		HeaderUpdateCommand, PatchKind, SqPackFile, TargetInfoCommand, TargetPlatform,
+		TargetRegion, ZIPATCH_MAGIC,
+	},
+	sqpack::{write_blocks, Platform},
+	utility::crc32,
+};
+
+const BLOCK_SIZE: usize = 1 << 7;
+const HEADER_SIZE: usize = 1024;
+// Patches published by SE split data into commands of at most this size.
+const MAX_COMMAND_SIZE: usize = 1_600_000;
+
+/// Generates ZiPatch files describing the differences between two game
+/// installations.
+///
+/// As with [`Applier`](super::Applier), paths are relative to the game
+/// directory of an installation. Changes to SqPack dat files are expressed as
+/// block-level writes where possible, with emptied or newly allocated regions
+/// cleared rather than written out. All other files are replaced wholesale.
+/// Files are streamed from disk, and are never held in memory in full.
+#[derive(Debug, Default)]
+pub struct Generator {
+	platform: Platform,
+}
+
+impl Generator {
+	/// Build a generator for patches targeting the default platform.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the platform the generated patches will target.
+	#[must_use]
+	pub fn with_platform(mut self, platform: Platform) -> Self {
+		self.set_platform(platform);
+		self
+	}
+
+	/// Set the platform the generated patches will target.
+	pub fn set_platform(&mut self, platform: Platform) {
+		self.platform = platform;
+	}
+
+	/// Generate a patch that will update the game directory at `old` to match
+	/// the game directory at `new`, writing it to `writer`.
+	pub fn generate(&self, old: &Path, new: &Path, mut writer: impl Write) -> Result<()> {
+		let (old_files, old_directories) = walk(old)?;
+		let (new_files, new_directories) = walk(new)?;
+
+		// Work out the changes ahead of time so the header can report the file count.
+		// Only paths are kept - contents are re-read while writing commands.
+		let mut changed = Vec::new();
+		for path in &new_files {
+			let existed = old_files.contains(path);
+			if !existed || files_differ(&old.join(path), &new.join(path))? {
+				changed.push((path, existed));
+			}
+		}
+		let removed = old_files.difference(&new_files).collect::<Vec<_>>();
+
+		writer.write_all(ZIPATCH_MAGIC)?;
+
+		let entry_files = u32::try_from(changed.len() + removed.len()).unwrap();
+		write_chunk(
+			&mut writer,
+			b"FHDR",
+			&serialize(&FileHeaderChunk::new(PatchKind::Diff, entry_files))?,
+		)?;
+
+		let platform = match self.platform {
+			Platform::Win32 => TargetPlatform::Win32,
+			Platform::PS3 => TargetPlatform::Ps3,
+			Platform::PS4 => TargetPlatform::Ps4,
+		};
+		let target_info = TargetInfoCommand::new(platform, TargetRegion::Global, false, 0);
+		write_sqpack(&mut writer, b'T', &serialize(&target_info)?)?;
+
+		// Sets are ordered, so parent directories will be created before their children.
+		for directory in new_directories.difference(&old_directories) {
+			let chunk = AddDirectoryChunk::new(patch_path(directory));
+			write_chunk(&mut writer, b"ADIR", &serialize(&chunk)?)?;
+		}
+
+		for (path, existed) in changed {
+			let (old_path, new_path) = (old.join(path), new.join(path));
+			let written = match (self.sqpack_file(path), existed) {
+				(Some(file), true) => write_dat(&mut writer, file, &old_path, &new_path)?,
+				_ => false,
+			};
+			if !written {
+				write_file(&mut writer, path, &new_path)?;
+			}
+		}
+
+		for path in removed {
+			let command =
+				FileOperationCommand::new(FileOperation::DeleteFile, &patch_path(path), 0, 0, 0);
+			write_sqpack(&mut writer, b'F', &serialize(&command)?)?;
+		}
+
+		// Remove the deepest directories first, so they're empty by the time they're removed.
+		let removed_directories = old_directories
+			.difference(&new_directories)
+			.collect::<Vec<_>>();
+		for directory in removed_directories.into_iter().rev() {
+			let chunk = DeleteDirectoryChunk::new(patch_path(directory));
+			write_chunk(&mut writer, b"DELD", &serialize(&chunk)?)?;
+		}
+
+		write_chunk(&mut writer, b"EOF_", &[])?;
+
+		Ok(())
+	}
+
+	// Resolve the SqPack file represented by a dat file path, i.e.
+	// `sqpack/ffxiv/0a0000.win32.dat0`.
+	fn sqpack_file(&self, path: &Path) -> Option<SqPackFile> {
+		let path = patch_path(path);
+		let mut segments = path.split('/');
+		let (Some("sqpack"), Some(repository), Some(name), None) = (
+			segments.next(),
+			segments.next(),
+			segments.next(),
+			segments.next(),
+		) else {
+			return None;
+		};
+
+		let repository = match repository {
+			"ffxiv" => 0,
+			other => other.strip_prefix("ex")?.parse::<u16>().ok()?,
+		};
+
+		let mut parts = name.split('.');
+		let (Some(id), Some(platform), Some(extension), None) =
+			(parts.next(), parts.next(), parts.next(), parts.next())
+		else {
+			return None;
+		};
+
+		if id.len() != 6 || platform != self.platform.name() {
+			return None;
+		}
+		let main_id = u16::from_str_radix(&id[..2], 16).ok()?;
+		let sub_id = u16::from_str_radix(&id[2..], 16).ok()?;
+		let file_id = extension.strip_prefix("dat")?.parse::<u32>().ok()?;
+
+		(sub_id >> 8 == repository).then(|| SqPackFile::new(main_id, sub_id, file_id))
+	}
+}
+
+// Write the changes to a dat file as header updates and block commands.
+// Returns `false` if the changes can't be represented this way.
+fn write_dat(writer: &mut impl Write, file: SqPackFile, old: &Path, new: &Path) -> Result<bool> {
+	let mut old = FileReader::open(old)?;
+	let mut new = FileReader::open(new)?;
+
+	let representable = new.length >= old.length
+		&& old.length >= HEADER_SIZE * 2
+		&& old.length.is_multiple_of(BLOCK_SIZE)
+		&& new.length.is_multiple_of(BLOCK_SIZE);
+	if !representable {
+		return Ok(false);
+	}
+
+	for (header_kind, offset) in [(HeaderKind::Version, 0), (HeaderKind::Data, HEADER_SIZE)] {
+		let previous = old.read(offset, HEADER_SIZE)?;
+		let header = new.read(offset, HEADER_SIZE)?;
+		if previous != header {
+			let command = HeaderUpdateCommand::new(HeaderFileKind::Dat, header_kind, file);
+			let mut data = serialize(&command)?;
+			data.extend_from_slice(&header);
+			write_sqpack(writer, b'H', &data)?;
+		}
+	}
+
+	// Collect runs of changed blocks, writing each as a single command. Regions
+	// that have been emptied are cleared rather than written out in full.
+	let mut run = DatRun::default();
+	let mut offset = HEADER_SIZE * 2;
+	while offset < new.length {
+		let block = new.read(offset, BLOCK_SIZE)?;
+		let unchanged = offset < old.length && old.read(offset, BLOCK_SIZE)? == block;
+		if unchanged {
+			run.flush(writer, file)?;
+			offset += BLOCK_SIZE;
+			continue;
+		}
+
+		if let Some(size) = empty_region(&mut new, offset, &block)? {
+			run.flush(writer, file)?;
+			let target_offset = u32::try_from(offset).unwrap();
+			let delete_size = u32::try_from(size).unwrap();
+			// Regions past the end of the old file grow it, rather than replacing data.
+			match offset < old.length {
+				true => {
+					let command = DeleteCommand::new(file, target_offset, delete_size);
+					write_sqpack(writer, b'D', &serialize(&command)?)?;
+				}
+				false => {
+					let command = ExpandCommand::new(file, target_offset, delete_size);
+					write_sqpack(writer, b'E', &serialize(&command)?)?;
+				}
+			}
+			offset += size;
+			continue;
+		}
+
+		run.push(writer, file, offset, &block)?;
+		offset += BLOCK_SIZE;
+	}
+	run.flush(writer, file)?;
+
+	Ok(true)
+}
+
+// A run of contiguous changed blocks within a dat file.
+#[derive(Default)]
+struct DatRun {
+	offset: usize,
+	data: Vec<u8>,
+}
+
+impl DatRun {
+	fn push(
+		&mut self,
+		writer: &mut impl Write,
+		file: SqPackFile,
+		offset: usize,
+		block: &[u8],
+	) -> Result<()> {
+		if self.data.is_empty() {
+			self.offset = offset;
+		}
+		self.data.extend_from_slice(block);
+		if self.data.len() >= MAX_COMMAND_SIZE {
+			self.flush(writer, file)?;
+		}
+		Ok(())
+	}
+
+	fn flush(&mut self, writer: &mut impl Write, file: SqPackFile) -> Result<()> {
+		if self.data.is_empty() {
+			return Ok(());
+		}
+
+		let command = AddCommand::new(
+			file,
+			u32::try_from(self.offset).unwrap(),
+			u32::try_from(self.data.len()).unwrap(),
+			0,
+		);
+		let mut data = serialize(&command)?;
+		data.append(&mut self.data);
+		write_sqpack(writer, b'A', &data)
+	}
+}
+
+// Check if the block at `offset` starts an empty region - a block header
+// spanning a number of blocks, followed only by zeros. Returns the size of the
+// region if it does.
+fn empty_region(reader: &mut FileReader, offset: usize, block: &[u8]) -> Result<Option<usize>> {
+	let value =
+		|index: usize| u32::from_le_bytes(block[index * 4..index * 4 + 4].try_into().unwrap());
+	let header_matches = value(0) == u32::try_from(BLOCK_SIZE).unwrap()
+		&& value(1) == 0
+		&& value(2) == 0
+		&& block[16..].iter().all(|&byte| byte == 0);
+	if !header_matches {
+		return Ok(None);
+	}
+
+	let size = (usize::try_from(value(3)).unwrap() + 1) * BLOCK_SIZE;
+	if offset + size > reader.length {
+		return Ok(None);
+	}
+
+	let mut position = offset + BLOCK_SIZE;
+	while position < offset + size {
+		let length = (offset + size - position).min(MAX_COMMAND_SIZE);
+		if reader.read(position, length)?.iter().any(|&byte| byte != 0) {
+			return Ok(None);
+		}
+		position += length;
+	}
+
+	Ok(Some(size))
+}
+
+// Write the full contents of a file, split across as many commands as required.
+fn write_file(writer: &mut impl Write, path: &Path, source: &Path) -> Result<()> {
+	let path = patch_path(path);
+	let mut reader = FileReader::open(source)?;
+
+	// Empty files still need a command to create (or truncate) them.
+	let mut offset = 0;
+	loop {
+		let length = (reader.length - offset).min(MAX_COMMAND_SIZE);
+		let segment = reader.read(offset, length)?;
+
+		let command = FileOperationCommand::new(
+			FileOperation::AddFile(Vec::new()),
+			&path,
+			0,
+			offset.try_into().unwrap(),
+			length.try_into().unwrap(),
+		);
+		let mut body = serialize(&command)?;
+		write_blocks(&mut body, &segment)?;
+		write_sqpack(writer, b'F', &body)?;
+
+		offset += length;
+		if offset >= reader.length {
+			break;
+		}
+	}
+
+	Ok(())
+}
+
+// Buffered reader over a file, avoiding seeks for sequential reads.
+struct FileReader {
+	reader: BufReader<fs::File>,
+	position: usize,
+	length: usize,
+}
+
+impl FileReader {
+	fn open(path: &Path) -> Result<Self> {
+		let file = fs::File::open(path)?;
+		let length = file.metadata()?.len().try_into().unwrap();
+		Ok(Self {
+			reader: BufReader::new(file),
+			position: 0,
+			length,
+		})
+	}
+
+	fn read(&mut self, offset: usize, length: usize) -> Result<Vec<u8>> {
+		if offset != self.position {
+			self.reader
+				.seek(SeekFrom::Start(offset.try_into().unwrap()))?;
+		}
+
+		let mut buffer = vec![0; length];
+		self.reader.read_exact(&mut buffer)?;
+		self.position = offset + length;
+		Ok(buffer)
+	}
+}
+
+// Compare the contents of two files without loading either in full.
+fn files_differ(old: &Path, new: &Path) -> Result<bool> {
+	let mut old = FileReader::open(old)?;
+	let mut new = FileReader::open(new)?;
+	if old.length != new.length {
+		return Ok(true);
+	}
+
+	let mut offset = 0;
+	while offset < new.length {
+		let length = (new.length - offset).min(MAX_COMMAND_SIZE);
+		if old.read(offset, length)? != new.read(offset, length)? {
+			return Ok(true);
+		}
+		offset += length;
+	}
+
+	Ok(false)
+}
+
+fn serialize<T>(value: &T) -> Result<Vec<u8>>
+where
+	T: BinWrite,
+	for<'a> T::Args<'a>: Default,
+{
+	let mut cursor = Cursor::new(Vec::new());
+	value.write_options(&mut cursor, Endian::Big, Default::default())?;
+	Ok(cursor.into_inner())
+}
+
+fn write_sqpack(writer: &mut impl Write, command: u8, data: &[u8]) -> Result<()> {
+	// The SQPK chunk's inner size includes itself and the command byte.
+	let size = u32::try_from(data.len() + 5).unwrap();
+	let mut body = Vec::with_capacity(data.len() + 5);
+	body.extend_from_slice(&size.to_be_bytes());
+	body.push(command);
+	body.extend_from_slice(data);
+	write_chunk(writer, b"SQPK", &body)
+}
+
+fn write_chunk(writer: &mut impl Write, magic: &[u8; 4], data: &[u8]) -> Result<()> {
+	// utility::crc32 omits the final xor of the standard algorithm.
+	let checksum = !crc32(&[magic.as_slice(), data].concat());
+
+	writer.write_all(&u32::try_from(data.len()).unwrap().to_be_bytes())?;
+	writer.write_all(magic)?;
+	writer.write_all(data)?;
+	writer.write_all(&checksum.to_be_bytes())?;
+	Ok(())
+}
+
+// Patch paths are always separated with forward slashes.
+fn patch_path(path: &Path) -> String {
+	path.components()
+		.map(|component| component.as_os_str().to_string_lossy())
+		.collect::<Vec<_>>()
+		.join("/")
+}
+
+// List the files and directories within `root`, relative to it. Missing
+// directories are treated as empty.
+fn walk(root: &Path) -> Result<(BTreeSet<PathBuf>, BTreeSet<PathBuf>)> {
+	let mut files = BTreeSet::new();
+	let mut directories = BTreeSet::new();
+
+	if !root.exists() {
+		return Ok((files, directories));
+	}
+
+	let mut pending = vec![PathBuf::new()];
+	while let Some(directory) = pending.pop() {
+		for entry in fs::read_dir(root.join(&directory))? {
+			let entry = entry?;
+			let path = directory.join(entry.file_name());
+			if entry.file_type()?.is_dir() {
+				directories.insert(path.clone());
+				pending.push(path);
+			} else {
+				files.insert(path);
+			}
+		}
+	}
+
+	Ok((files, directories))
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs;
+
+	use crate::{
+		file::{
+			patch::{Chunk, SqPackChunk, ZiPatch as ZiPatchFile},
+			File,
+		},
+		sqpack::{FileKind, Writer},
+		utility::TempDir,
+		version::GameVersion,
+		zipatch::{Applier, Patch},
+	};
+
+	use super::Generator;
+
+	fn write_game(root: &std::path::Path, files: &[(&str, &[u8])], version: &str) {
+		let mut writer = Writer::new();
+		for (path, data) in files {
+			writer.add_file(path, *data, FileKind::Standard).unwrap();
+		}
+		writer.write(&root.join("sqpack")).unwrap();
+		fs::write(root.join("ffxivgame.ver"), version).unwrap();
+	}
+
+	fn read_tree(root: &std::path::Path) -> Vec<(String, Vec<u8>)> {
+		let (files, _) = super::walk(root).unwrap();
+		files
+			.into_iter()
+			.map(|path| (super::patch_path(&path), fs::read(root.join(path)).unwrap()))
+			.collect()
+	}
+
+	#[test]
+	fn round_trips() {
+		let root = TempDir::new("generate");
+		let (old, new, target) = (root.join("old"), root.join("new"), root.join("target"));
+
+		write_game(
+			&old,
+			&[("exd/a.exd", b"one"), ("exd/b.exd", b"two")],
+			"2024.01.01.0000.0000",
+		);
+		write_game(
+			&new,
+			&[("exd/a.exd", b"one, but longer"), ("exd/c.exd", b"three")],
+			"2024.02.01.0000.0000",
+		);
+		write_game(
+			&target,
+			&[("exd/a.exd", b"one"), ("exd/b.exd", b"two")],
+			"2024.01.01.0000.0000",
+		);
+		fs::create_dir_all(old.join("movie/ffxiv")).unwrap();
+		fs::create_dir_all(new.join("boot")).unwrap();
+		fs::create_dir_all(target.join("movie/ffxiv")).unwrap();
+
+		let name = "D2024.02.01.0000.0000";
+		let path = root.join(format!("{name}.patch"));
+		Generator::new()
+			.generate(&old, &new, fs::File::create(&path).unwrap())
+			.unwrap();
+
+		let zipatch = ZiPatchFile::read(fs::File::open(&path).unwrap()).unwrap();
+		assert!(zipatch
+			.chunks()
+			.with_verify_checksums(true)
+			.all(|chunk| chunk.is_ok()));
+
+		let patch = Patch {
+			name: name.into(),
+			version: GameVersion::from_patch_name(name).unwrap(),
+			path,
+		};
+		Applier::new(&target).apply(&patch).unwrap();
+
+		assert_eq!(read_tree(&target), read_tree(&new));
+		assert!(target.join("boot").is_dir());
+		assert!(!target.join("movie").exists());
+	}
+
+	#[test]
+	fn clears_empty_regions() {
+		let root = TempDir::new("generate-dat");
+		let (old, new, target) = (root.join("old"), root.join("new"), root.join("target"));
+		let dat = "sqpack/ffxiv/0a0000.win32.dat0";
+
+		let empty_region = |blocks: u32| {
+			let mut data = [128u32, 0, 0, blocks - 1]
+				.into_iter()
+				.flat_map(u32::to_le_bytes)
+				.collect::<Vec<_>>();
+			data.resize(usize::try_from(blocks).unwrap() * 128, 0);
+			data
+		};
+
+		let mut old_data = vec![1; 2048];
+		old_data.extend([0xAA; 4 * 128]);
+
+		// Empty the middle of the file, change its end, and grow it.
+		let mut new_data = vec![1; 2048];
+		new_data.extend([0xAA; 128]);
+		new_data.extend(empty_region(2));
+		new_data.extend([0xBB; 128]);
+		new_data.extend(empty_region(2));
+
+		for (directory, data) in [(&old, &old_data), (&new, &new_data), (&target, &old_data)] {
+			fs::create_dir_all(directory.join("sqpack/ffxiv")).unwrap();
+			fs::write(directory.join(dat), data).unwrap();
+		}
+
+		let name = "D2024.02.01.0000.0000";
+		let path = root.join(format!("{name}.patch"));
+		Generator::new()
+			.generate(&old, &new, fs::File::create(&path).unwrap())
+			.unwrap();
+
+		let zipatch = ZiPatchFile::read(fs::File::open(&path).unwrap()).unwrap();
+		let commands = zipatch
+			.chunks()
+			.filter_map(|chunk| match chunk.unwrap() {
+				Chunk::SqPack(SqPackChunk::Add(_)) => Some('A'),
+				Chunk::SqPack(SqPackChunk::Delete(_)) => Some('D'),
+				Chunk::SqPack(SqPackChunk::Expand(_)) => Some('E'),
+				Chunk::SqPack(SqPackChunk::FileOperation(_)) => Some('F'),
+				_ => None,
+			})
+			.collect::<String>();
+		assert_eq!(commands, "DAE");
+
+		let patch = Patch {
+			name: name.into(),
+			version: GameVersion::from_patch_name(name).unwrap(),
+			path,
+		};
+		Applier::new(&target).apply(&patch).unwrap();
+		assert_eq!(fs::read(target.join(dat)).unwrap(), new_data);
+	}
+}
@@ -90,18 +90,18 @@ pub struct ResourceChunk {
 }
 
 impl PatchLookup {
-	pub fn new(path: &Path) -> Result<Self> {
-		read_lookup(path)
+	pub fn new(path: &Path, verify_checksums: bool) -> Result<Self> {
+		read_lookup(path, verify_checksums)
 	}
 }
 
-fn read_lookup(path: &Path) -> Result<PatchLookup> {
+fn read_lookup(path: &Path, verify_checksums: bool) -> Result<PatchLookup> {
 	let file = BufReader::new(fs::File::open(path)?);
 	let zipatch = ZiPatchFile::read(file)?;
 
-	// TODO: Retry on failure?
 	zipatch
 		.chunks()
+		.with_verify_checksums(verify_checksums)
 		.try_fold(PatchLookupData::default(), |mut data, chunk| -> Result<_> {
 			match chunk? {
 				Chunk::SqPack(SqPackChunk::FileOperation(command)) => {
@@ -1,12 +1,21 @@
 //! Adapters to allow working with game data directly out of ZiPatch files.
 
+mod apply;
+mod changes;
+#[cfg(test)]
+mod fixture;
+mod generate;
 mod lookup;
+mod persist;
 mod repository;
 mod utility;
 mod view;
 mod zipatch;
 
 pub use {
+	apply::{Applier, ApplyAction, ApplyActionKind, ApplyReport},
+	changes::{ChangeKind, FileChange, PatchChanges},
+	generate::Generator,
 	repository::{Patch, PatchRepository},
 	view::View,
 	zipatch::ZiPatch,
@@ -0,0 +1,198 @@
+use std::{
+	fs,
+	io::{self, BufReader, BufWriter, Write},
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicU64, Ordering},
+	time::UNIX_EPOCH,
+};
+
+use binrw::{binread, binrw, BinRead, BinWrite};
+use sha1::{Digest as _, Sha1};
+
+use crate::error::Result;
+
+use super::lookup::VersionedPatchLookupData;
+
+// Bump whenever the layout of persisted lookups changes - files written with
+// other versions will be rebuilt.
+const FORMAT_VERSION: u32 = 2;
+
+const MAGIC: &[u8; 4] = b"ZPLT";
+
+#[binread]
+#[br(little, magic = b"ZPLT")]
+#[derive(Debug)]
+struct PersistedLookup {
+	#[br(temp)]
+	version: u32,
+	#[br(if(version == FORMAT_VERSION))]
+	source: Option<PatchSource>,
+	#[br(if(version == FORMAT_VERSION))]
+	data: Option<VersionedPatchLookupData>,
+}
+
+/// Identity of the patch file a lookup was built from.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PatchSource {
+	size: u64,
+	modified: u64,
+	hash: [u8; 20],
+}
+
+/// Get the path the lookup for the patch at `patch_path` is persisted to.
+/// Lookups are stored alongside their patch by default.
+pub fn lookup_path(patch_path: &Path, directory: Option<&Path>) -> PathBuf {
+	let Some(directory) = directory else {
+		let mut path = patch_path.as_os_str().to_owned();
+		path.push(".lut");
+		return path.into();
+	};
+
+	// Patches in different repositories share names, disambiguate with the
+	// name of the directory they're stored in.
+	let name = |path: Option<&Path>| {
+		path.and_then(Path::file_name)
+			.map(|name| name.to_string_lossy().into_owned())
+			.unwrap_or_default()
+	};
+	directory.join(format!(
+		"{}-{}.lut",
+		name(patch_path.parent()),
+		name(Some(patch_path))
+	))
+}
+
+/// Read a persisted lookup, if it exists and is valid for the patch at
+/// `patch_path`. Missing, stale, or damaged lookups are treated as absent.
+pub fn read(lookup_path: &Path, patch_path: &Path) -> Result<Option<VersionedPatchLookupData>> {
+	let Ok(file) = fs::File::open(lookup_path) else {
+		return Ok(None);
+	};
+
+	let Ok(PersistedLookup {
+		source: Some(source),
+		data: Some(data),
+		..
+	}) = PersistedLookup::read(&mut BufReader::new(file))
+	else {
+		return Ok(None);
+	};
+
+	// Size and modification time are cheap to check. If the patch was copied or
+	// touched, fall back to the hash before rebuilding.
+	let (size, modified) = metadata(patch_path)?;
+	if size != source.size {
+		return Ok(None);
+	}
+	if modified == source.modified {
+		return Ok(Some(data));
+	}
+
+	let hash = hash(patch_path)?;
+	if hash != source.hash {
+		return Ok(None);
+	}
+
+	// Record the new modification time so later reads can skip hashing. This is
+	// best-effort, as with any other persisted lookup write.
+	let source = PatchSource {
+		size,
+		modified,
+		hash,
+	};
+	let _ = write_source(lookup_path, &source, &data);
+
+	Ok(Some(data))
+}
+
+/// Persist a lookup built from the patch at `patch_path`. The lookup is
+/// written to a temporary file before being moved into place, so that readers
+/// never observe a partially written lookup.
+pub fn write(lookup_path: &Path, patch_path: &Path, data: &VersionedPatchLookupData) -> Result<()> {
+	write_with(lookup_path, data, || {
+		let (size, modified) = metadata(patch_path)?;
+		Ok(PatchSource {
+			size,
+			modified,
+			hash: hash(patch_path)?,
+		})
+	})
+}
+
+fn write_source(
+	lookup_path: &Path,
+	source: &PatchSource,
+	data: &VersionedPatchLookupData,
+) -> Result<()> {
+	write_with(lookup_path, data, || Ok(source.clone()))
+}
+
+fn write_with(
+	lookup_path: &Path,
+	data: &VersionedPatchLookupData,
+	source: impl FnOnce() -> Result<PatchSource>,
+) -> Result<()> {
+	if let Some(parent) = lookup_path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	// Multiple writers within a process may target the same lookup, each needs
+	// its own temporary file.
+	static COUNTER: AtomicU64 = AtomicU64::new(0);
+	let mut temp_path = lookup_path.as_os_str().to_owned();
+	temp_path.push(format!(
+		".{}-{}.tmp",
+		std::process::id(),
+		COUNTER.fetch_add(1, Ordering::Relaxed)
+	));
+	let temp_path = PathBuf::from(temp_path);
+
+	// Create the file before hashing the patch, so unwritable locations fail early.
+	let file = fs::File::create(&temp_path)?;
+	let result = source()
+		.and_then(|source| write_file(file, &source, data))
+		.and_then(|()| {
+			fs::rename(&temp_path, lookup_path)?;
+			Ok(())
+		});
+	if result.is_err() {
+		let _ = fs::remove_file(&temp_path);
+	}
+
+	result
+}
+
+fn write_file(file: fs::File, source: &PatchSource, data: &VersionedPatchLookupData) -> Result<()> {
+	// `PersistedLookup` owns its data, write the pieces directly to avoid a copy.
+	let mut writer = BufWriter::new(file);
+	writer.write_all(MAGIC)?;
+	FORMAT_VERSION.write_le(&mut writer)?;
+	source.write_le(&mut writer)?;
+	data.write(&mut writer)?;
+
+	let file = writer
+		.into_inner()
+		.map_err(io::IntoInnerError::into_error)?;
+	file.sync_all()?;
+
+	Ok(())
+}
+
+fn metadata(path: &Path) -> Result<(u64, u64)> {
+	let metadata = fs::metadata(path)?;
+	let modified = metadata
+		.modified()
+		.ok()
+		.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+		.and_then(|duration| u64::try_from(duration.as_nanos()).ok())
+		.unwrap_or(0);
+
+	Ok((metadata.len(), modified))
+}
+
+fn hash(path: &Path) -> Result<[u8; 20]> {
+	let mut hasher = Sha1::new();
+	io::copy(&mut BufReader::new(fs::File::open(path)?), &mut hasher)?;
+	Ok(hasher.finalize().into())
+}
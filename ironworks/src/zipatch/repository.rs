@@ -1,23 +1,26 @@
 use std::{
 	cmp::Ordering,
 	fs,
+	ops::RangeBounds,
 	path::{Path, PathBuf},
 };
 
-use crate::error::Result;
+use crate::{error::Result, version::GameVersion};
 
 /// Representation of a single patch file.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Patch {
 	/// Canonical name of the patch. Typically conforms to the format Y.M.D.P.Rp,
 	/// where \[Y]ear, \[M]onth, \[D]ay, \[P]art, \[R]evision, \[p]art-but-for-HISTs
 	pub name: String,
+	/// Game version the patch updates data to, parsed from the patch name.
+	pub version: GameVersion,
 	/// Path to the patch file on disk.
 	pub path: PathBuf,
 }
 
 /// Representation of a folder containing patch files.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PatchRepository {
 	/// List of patches in this repository
 	pub patches: Vec<Patch>,
@@ -30,7 +33,7 @@ impl PatchRepository {
 		let mut patches = fs::read_dir(repository_path)?
 			.filter_map(|entry| {
 				let patch_path = match entry {
-					Err(err) => return Some(Err(err)),
+					Err(err) => return Some(Err(err.into())),
 					Ok(entry) => entry.path(),
 				};
 
@@ -43,27 +46,129 @@ impl PatchRepository {
 				// TODO: should this error if the string conversion fails? atm it just ->None's
 				let name = patch_path.file_stem()?.to_str()?.to_string();
 
+				// Likewise, files that don't follow the patch naming scheme are skipped.
+				let version = GameVersion::from_patch_name(&name).ok()?;
+
 				Some(Ok(Patch {
 					name,
+					version,
 					path: patch_path,
 				}))
 			})
-			.collect::<Result<Vec<_>, _>>()?;
+			.collect::<Result<Vec<_>>>()?;
 
 		patches.sort_unstable_by(sort_patches);
 
 		Ok(Self { patches })
 	}
 
-	// TODO: fn before - so i.e. a simple use case can `.at().before()` to get a repo of a folder containing patches before a particular point.
+	/// Build a repository containing only the patches prior to `version`.
+	pub fn before(&self, version: GameVersion) -> Self {
+		self.range(..version)
+	}
+
+	/// Build a repository containing only the patches up to and including
+	/// `version`. A view of the resulting repository will represent the game
+	/// data as of `version`.
+	pub fn until(&self, version: GameVersion) -> Self {
+		self.range(..=version)
+	}
+
+	/// Build a repository containing only the patches with versions within `range`.
+	pub fn range(&self, range: impl RangeBounds<GameVersion>) -> Self {
+		Self {
+			patches: self
+				.patches
+				.iter()
+				.filter(|patch| range.contains(&patch.version))
+				.cloned()
+				.collect(),
+		}
+	}
 }
 
-fn sort_patches(Patch { name: ref a, .. }: &Patch, Patch { name: ref b, .. }: &Patch) -> Ordering {
-	match a[1..].cmp(&b[1..]) {
-		// The prefix "type" character is only ever [D]IFF or [H]IST - HIST always sorts first.
-		Ordering::Equal => a[0..1].cmp(&b[0..1]).reverse(),
+fn sort_patches(a: &Patch, b: &Patch) -> Ordering {
+	// The version is followed by an optional part suffix, which is string-sortable.
+	let order = a
+		.version
+		.cmp(&b.version)
+		.then_with(|| a.name[1..].cmp(&b.name[1..]));
 
-		// The primary "version" portion of the patch name is string-sortable.
+	match order {
+		// The prefix "type" character is only ever [D]IFF or [H]IST - HIST always sorts first.
+		Ordering::Equal => a.name[0..1].cmp(&b.name[0..1]).reverse(),
 		order => order,
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::{fs, path::PathBuf};
+
+	use crate::{utility::TempDir, version::GameVersion};
+
+	use super::{Patch, PatchRepository};
+
+	fn repository(names: &[&str]) -> PatchRepository {
+		PatchRepository {
+			patches: names
+				.iter()
+				.map(|name| Patch {
+					name: name.to_string(),
+					version: GameVersion::from_patch_name(name).unwrap(),
+					path: PathBuf::from(format!("{name}.patch")),
+				})
+				.collect(),
+		}
+	}
+
+	fn names(repository: &PatchRepository) -> Vec<&str> {
+		repository
+			.patches
+			.iter()
+			.map(|patch| patch.name.as_str())
+			.collect()
+	}
+
+	#[test]
+	fn version_bounds() {
+		let repository = repository(&[
+			"H2017.06.06.0000.0001a",
+			"H2017.06.06.0000.0001b",
+			"D2020.08.11.0000.0000",
+			"D2021.04.20.0000.0001",
+		]);
+		let version = "2020.08.11.0000.0000".parse::<GameVersion>().unwrap();
+
+		assert_eq!(
+			names(&repository.before(version)),
+			["H2017.06.06.0000.0001a", "H2017.06.06.0000.0001b"]
+		);
+		assert_eq!(
+			names(&repository.until(version)),
+			[
+				"H2017.06.06.0000.0001a",
+				"H2017.06.06.0000.0001b",
+				"D2020.08.11.0000.0000"
+			]
+		);
+		assert_eq!(
+			names(&repository.range(version..)),
+			["D2020.08.11.0000.0000", "D2021.04.20.0000.0001"]
+		);
+	}
+
+	#[test]
+	fn skips_unrecognised_names() {
+		let root = TempDir::new("repository");
+		for name in ["D2020.08.11.0000.0000", "H2017.06.06.0000.0001a", "notes"] {
+			fs::write(root.join(format!("{name}.patch")), b"").unwrap();
+		}
+
+		let repository = PatchRepository::at(&root).unwrap();
+		assert_eq!(
+			names(&repository),
+			["H2017.06.06.0000.0001a", "D2020.08.11.0000.0000"]
+		);
+	}
+}
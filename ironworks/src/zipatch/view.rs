@@ -11,6 +11,7 @@ use crate::{
 	error::{Error, ErrorValue, Result},
 	sqpack,
 	utility::{TakeSeekable, TakeSeekableExt},
+	version::GameVersion,
 };
 
 use super::{
@@ -70,6 +71,25 @@ impl View {
 		}
 	}
 
+	/// Get the game version of the specified repository, as of the latest patch
+	/// in the view.
+	pub fn game_version(&self, repository_id: u8) -> Result<GameVersion> {
+		let repository = self.repositories.get(&repository_id).ok_or_else(|| {
+			Error::NotFound(ErrorValue::Other(format!("repository {repository_id}")))
+		})?;
+
+		repository
+			.patches
+			.last()
+			.map(|patch| patch.version)
+			.ok_or_else(|| {
+				Error::Invalid(
+					ErrorValue::Other(format!("repository {repository_id}")),
+					"unspecified repository version".to_string(),
+				)
+			})
+	}
+
 	fn lookups(
 		&self,
 		repository_id: u8,
@@ -158,20 +178,7 @@ impl View {
 
 impl sqpack::Resource for View {
 	fn version(&self, repository_id: u8) -> Result<String> {
-		let repository = self.repositories.get(&repository_id).ok_or_else(|| {
-			Error::NotFound(ErrorValue::Other(format!("repository {repository_id}")))
-		})?;
-
-		repository
-			.patches
-			.last()
-			.map(|x| x.name.clone())
-			.ok_or_else(|| {
-				Error::Invalid(
-					ErrorValue::Other(format!("repository {repository_id}")),
-					"unspecified repository version".to_string(),
-				)
-			})
+		Ok(self.game_version(repository_id)?.to_string())
 	}
 
 	// ASSUMPTION: IndexUpdate chunks are unused, new indexes will always be distributed via FileOperation::AddFile.
@@ -1,6 +1,8 @@
 use std::{
 	collections::{hash_map::Entry, HashMap},
 	fs,
+	io::BufReader,
+	ops::RangeBounds,
 	path::PathBuf,
 	sync::{
 		atomic::{AtomicBool, Ordering},
@@ -8,13 +10,18 @@ use std::{
 	},
 };
 
-use binrw::{BinRead, BinWrite};
-
-use crate::error::Result;
+use crate::{
+	error::{Error, ErrorValue, Result},
+	file::{patch::ZiPatch as ZiPatchFile, File},
+	sqpack::PathDictionary,
+	version::GameVersion,
+};
 
 use super::{
-	lookup::{PatchLookup, VersionedPatchLookupData},
-	repository::Patch,
+	changes::{changes, PatchChanges},
+	lookup::PatchLookup,
+	persist,
+	repository::{Patch, PatchRepository},
 	view::ViewBuilder,
 };
 
@@ -33,22 +40,103 @@ impl ZiPatch {
 	}
 
 	/// Enable persistance of lookup tables used when reading patch files. Enabling
-	/// this will cause additional files to be written alongside patch files.
+	/// this will cause additional files to be written alongside patch files,
+	/// unless a directory is set with [`with_lookup_directory`](Self::with_lookup_directory).
+	///
+	/// Persisted lookup tables are validated against the size, modification
+	/// time, and if required, hash of their patch file, and will be rebuilt if
+	/// the patch has changed. Failure to write a lookup table is not an error.
 	pub fn with_persisted_lookups(mut self) -> Self {
 		self.persist_lookups();
 		self
 	}
 
 	/// Enable persistance of lookup tables used when reading patch files. Enabling
-	/// this will cause additional files to be written alongside patch files.
+	/// this will cause additional files to be written alongside patch files,
+	/// unless a directory is set with [`set_lookup_directory`](Self::set_lookup_directory).
+	///
+	/// Persisted lookup tables are validated against the size, modification
+	/// time, and if required, hash of their patch file, and will be rebuilt if
+	/// the patch has changed. Failure to write a lookup table is not an error.
 	pub fn persist_lookups(&mut self) {
 		self.cache.persist_lookups()
 	}
 
+	/// Enable persistance of lookup tables, storing them in `directory` rather
+	/// than alongside patch files. Useful when patch files are read-only.
+	pub fn with_lookup_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+		self.set_lookup_directory(directory);
+		self
+	}
+
+	/// Enable persistance of lookup tables, storing them in `directory` rather
+	/// than alongside patch files. Useful when patch files are read-only.
+	pub fn set_lookup_directory(&mut self, directory: impl Into<PathBuf>) {
+		self.cache.set_lookup_directory(directory.into())
+	}
+
+	/// Enable verification of patch file checksums while building lookup tables.
+	/// Truncated or corrupt patch files will fail with an error rather than
+	/// producing incorrect data, at the cost of reading patch files in full.
+	/// Persisted lookup tables are not re-verified.
+	pub fn with_verified_lookups(mut self) -> Self {
+		self.verify_lookups();
+		self
+	}
+
+	/// Enable verification of patch file checksums while building lookup tables.
+	/// Truncated or corrupt patch files will fail with an error rather than
+	/// producing incorrect data, at the cost of reading patch files in full.
+	/// Persisted lookup tables are not re-verified.
+	pub fn verify_lookups(&mut self) {
+		self.cache.verify_lookups()
+	}
+
+	/// Verify the checksum of every chunk within `patch`. Truncated or corrupt
+	/// patch files will fail with an error noting the offset of the first
+	/// invalid chunk.
+	pub fn verify(&self, patch: &Patch) -> Result<()> {
+		let zipatch = ZiPatchFile::read(BufReader::new(fs::File::open(&patch.path)?))?;
+
+		for chunk in zipatch.chunks().with_verify_checksums(true) {
+			chunk.map_err(|error| match error {
+				Error::Invalid(value, reason) => Error::Invalid(
+					ErrorValue::Other(format!("patch {}", patch.name)),
+					format!("{value}: {reason}"),
+				),
+				other => other,
+			})?;
+		}
+
+		Ok(())
+	}
+
 	/// Build a view of patch repository files to be used as a SqPack resource.
+	///
+	/// Views built from the same instance share lookup tables, allowing views of
+	/// data at multiple versions (see [`PatchRepository::until`](super::PatchRepository::until))
+	/// to be built without re-reading patch files.
 	pub fn view(&self) -> ViewBuilder {
 		ViewBuilder::new(self.cache.clone())
 	}
+
+	/// List the game files added, changed, or removed by each patch in
+	/// `repository` with a version within `range`. Changes are found by comparing
+	/// the indexes before and after each patch, alongside the data written by the
+	/// patch. Paths will be resolved using `dictionary`, if provided.
+	///
+	/// The repository should contain all patches for the SqPack repository
+	/// `repository_id`, as prior patches are required to find the state of the
+	/// data before the first patch within `range`.
+	pub fn changes(
+		&self,
+		repository_id: u8,
+		repository: &PatchRepository,
+		range: impl RangeBounds<GameVersion>,
+		dictionary: Option<&PathDictionary>,
+	) -> Result<Vec<PatchChanges>> {
+		changes(&self.cache, repository_id, repository, range, dictionary)
+	}
 }
 
 impl Default for ZiPatch {
@@ -56,11 +144,15 @@ impl Default for ZiPatch {
 		Self::new()
 	}
 }
-type CacheSync<T> = Arc<(Mutex<Option<T>>, Condvar)>;
+// The outer option is empty while the value is being built, the inner option
+// is empty if building the value failed.
+type CacheSync<T> = Arc<(Mutex<Option<Option<T>>>, Condvar)>;
 
 #[derive(Debug)]
 pub struct LookupCache {
 	persist_lookups: AtomicBool,
+	lookup_directory: Mutex<Option<PathBuf>>,
+	verify_lookups: AtomicBool,
 	cache: Mutex<HashMap<PathBuf, CacheSync<Arc<PatchLookup>>>>,
 }
 
@@ -68,6 +160,8 @@ impl LookupCache {
 	pub fn new() -> Self {
 		Self {
 			persist_lookups: false.into(),
+			lookup_directory: Default::default(),
+			verify_lookups: false.into(),
 			cache: Default::default(),
 		}
 	}
@@ -76,72 +170,189 @@ impl LookupCache {
 		self.persist_lookups.store(true, Ordering::SeqCst)
 	}
 
+	fn set_lookup_directory(&self, directory: PathBuf) {
+		*self.lookup_directory.lock().unwrap() = Some(directory);
+		self.persist_lookups();
+	}
+
+	fn verify_lookups(&self) {
+		self.verify_lookups.store(true, Ordering::SeqCst)
+	}
+
 	pub fn lookup(&self, patch: &Patch) -> Result<Arc<PatchLookup>> {
 		// TODO: honestly this might make sense as an alternate impl of the hashmapcache
-		// Get a lock on the main cache and fetch the internal sync primative. We're
-		// also recording if it existed prior to this call.
-		let mut cache = self.cache.lock().unwrap();
-		let (occupied, value) = match cache.entry(patch.path.clone()) {
-			Entry::Occupied(entry) => (true, entry.get().clone()),
-			Entry::Vacant(entry) => (
-				false,
-				entry
-					.insert(Arc::new((Mutex::new(None), Condvar::new())))
-					.clone(),
-			),
-		};
-		drop(cache);
+		loop {
+			// Get a lock on the main cache and fetch the internal sync primative. We're
+			// also recording if it existed prior to this call.
+			let mut cache = self.cache.lock().unwrap();
+			let (occupied, value) = match cache.entry(patch.path.clone()) {
+				Entry::Occupied(entry) => (true, entry.get().clone()),
+				Entry::Vacant(entry) => (
+					false,
+					entry
+						.insert(Arc::new((Mutex::new(None), Condvar::new())))
+						.clone(),
+				),
+			};
+			drop(cache);
 
-		let (mutex, condvar) = &*value;
+			let (mutex, condvar) = &*value;
 
-		// If the cache entry already existed, some other thread is building the
-		// lookup already - wait for it to complete via the condvar.
-		if occupied {
-			let mut value = mutex.lock().unwrap();
-			while value.is_none() {
-				value = condvar.wait(value).unwrap();
+			// If the cache entry already existed, some other thread is building the
+			// lookup already - wait for it to complete via the condvar. If it failed,
+			// try building it again.
+			if occupied {
+				let mut value = mutex.lock().unwrap();
+				while value.is_none() {
+					value = condvar.wait(value).unwrap();
+				}
+				match value.as_ref().expect("lock condition broken") {
+					Some(lookup) => return Ok(lookup.clone()),
+					None => continue,
+				}
 			}
-			return Ok(value.as_ref().expect("lock condition broken").clone());
-		}
 
-		// Build a new lookup for this patch.
-		let lookup = Arc::new(self.read_lookup(patch)?);
+			// Build a new lookup for this patch. On failure, the cache entry is removed
+			// so that later calls can retry, i.e. after a patch has been re-downloaded.
+			let lookup = self.read_lookup(patch).map(Arc::new);
+			if lookup.is_err() {
+				self.cache.lock().unwrap().remove(&patch.path);
+			}
 
-		// Write the new lookup to the cache.
-		let mut value = mutex.lock().unwrap();
-		*value = Some(lookup.clone());
-		condvar.notify_all();
+			// Write the result to the cache.
+			let mut value = mutex.lock().unwrap();
+			*value = Some(lookup.as_ref().ok().cloned());
+			condvar.notify_all();
 
-		Ok(lookup)
+			return lookup;
+		}
 	}
 
 	fn read_lookup(&self, patch: &Patch) -> Result<PatchLookup> {
+		let verify_lookups = self.verify_lookups.load(Ordering::SeqCst);
 		let persist_lookups = self.persist_lookups.load(Ordering::SeqCst);
 		if !persist_lookups {
-			return PatchLookup::new(&patch.path);
+			return PatchLookup::new(&patch.path, verify_lookups);
 		}
 
-		let mut lut_path = patch.path.as_os_str().to_owned();
-		lut_path.push(".lut");
-		let lut_path = PathBuf::from(lut_path);
+		let directory = self.lookup_directory.lock().unwrap().clone();
+		let lookup_path = persist::lookup_path(&patch.path, directory.as_deref());
 
-		let lookup = match lut_path.exists() {
-			true => {
-				let mut file = fs::File::open(lut_path)?;
-				PatchLookup {
-					path: patch.path.to_owned(),
-					data: VersionedPatchLookupData::read(&mut file)?,
-				}
-			}
+		if let Some(data) = persist::read(&lookup_path, &patch.path)? {
+			return Ok(PatchLookup {
+				path: patch.path.to_owned(),
+				data,
+			});
+		}
+
+		// Persisting is best-effort - the lookup is still usable if it can't be written.
+		let lookup = PatchLookup::new(&patch.path, verify_lookups)?;
+		let _ = persist::write(&lookup_path, &patch.path, &lookup.data);
+
+		Ok(lookup)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		fs,
+		time::{Duration, SystemTime},
+	};
+
+	use crate::{error::Error, utility::TempDir};
 
-			false => {
-				let lookup = PatchLookup::new(&patch.path)?;
-				let mut file = fs::File::create(lut_path)?;
-				lookup.data.write(&mut file)?;
-				lookup
+	use super::{
+		super::{fixture, persist},
+		ZiPatch,
+	};
+
+	#[test]
+	fn verifies_checksums() {
+		let root = TempDir::new("verify");
+
+		let patch = fixture::write_patch(
+			&root,
+			"D2024.01.01.0000.0000",
+			fixture::add_file("ffxivgame.ver", b"2024.01.01.0000.0000"),
+		);
+		let zipatch = ZiPatch::new();
+		zipatch.verify(&patch).unwrap();
+
+		let bytes = fs::read(&patch.path).unwrap();
+		let reason = |bytes: &[u8]| {
+			fs::write(&patch.path, bytes).unwrap();
+			match zipatch.verify(&patch) {
+				Err(Error::Invalid(_, reason)) => reason,
+				other => panic!("unexpected result {other:?}"),
 			}
 		};
 
-		Ok(lookup)
+		// Corrupt the file's data, within the first chunk.
+		let mut corrupt = bytes.clone();
+		corrupt[100] ^= 0xFF;
+		assert!(reason(&corrupt).starts_with("ZiPatch chunk at offset 0xc: checksum mismatch"));
+
+		// Corrupt the size of the first chunk.
+		let mut corrupt = bytes.clone();
+		corrupt[12..16].copy_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+		assert_eq!(
+			reason(&corrupt),
+			"ZiPatch chunk at offset 0xc: patch file is truncated"
+		);
+
+		// Drop the end of file chunk, and some of the data before it.
+		assert!(reason(&bytes[..bytes.len() - 20]).ends_with("patch file is truncated"));
+	}
+
+	#[test]
+	fn persists_lookups() {
+		let root = TempDir::new("persist");
+		let directory = root.join("lut");
+
+		let patch = fixture::write_patch(
+			&root,
+			"D2024.01.01.0000.0000",
+			fixture::add_file("sqpack/ffxiv/0a0000.win32.index", &[1; 64]),
+		);
+		let path = persist::lookup_path(&patch.path, Some(&directory));
+		let chunks = |zipatch: ZiPatch| {
+			zipatch
+				.cache
+				.lookup(&patch)
+				.unwrap()
+				.data()
+				.file_chunks
+				.len()
+		};
+
+		// Lookups are written to the configured directory.
+		assert_eq!(chunks(ZiPatch::new().with_lookup_directory(&directory)), 1);
+		assert!(path.is_file());
+		assert!(!persist::lookup_path(&patch.path, None).exists());
+
+		// Damaged and outdated lookups are rebuilt.
+		fs::write(&path, b"ZPLT\x01\x00\x00\x00").unwrap();
+		assert_eq!(chunks(ZiPatch::new().with_lookup_directory(&directory)), 1);
+		let persisted = fs::read(&path).unwrap();
+		assert_eq!(persisted[4..8], 2u32.to_le_bytes());
+
+		// Touched patches are validated by hash, and their record refreshed.
+		let modified = SystemTime::now() + Duration::from_secs(60);
+		fs::File::options()
+			.write(true)
+			.open(&patch.path)
+			.unwrap()
+			.set_modified(modified)
+			.unwrap();
+		assert_eq!(chunks(ZiPatch::new().with_lookup_directory(&directory)), 1);
+		let refreshed = fs::read(&path).unwrap();
+		assert_ne!(refreshed[16..24], persisted[16..24]);
+		assert_eq!(refreshed[24..], persisted[24..]);
+
+		// Lookups for modified patches are rebuilt.
+		fixture::write_patch(&root, "D2024.01.01.0000.0000", Vec::new());
+		assert_eq!(chunks(ZiPatch::new().with_lookup_directory(&directory)), 0);
+		assert_ne!(fs::read(&path).unwrap(), persisted);
 	}
 }